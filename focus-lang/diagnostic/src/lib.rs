@@ -0,0 +1,5 @@
+mod diagnostic;
+mod render;
+
+pub use diagnostic::{Diagnostic, Label, Severity, Span, Suggestion};
+pub use render::Renderer;
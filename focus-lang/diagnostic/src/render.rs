@@ -0,0 +1,300 @@
+use std::fmt::Write;
+
+use crate::{Diagnostic, Label, Severity};
+
+const TAB_WIDTH: usize = 4;
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const RED: &str = "\x1b[1;31m";
+const YELLOW: &str = "\x1b[1;33m";
+const GREEN: &str = "\x1b[1;32m";
+const BLUE: &str = "\x1b[1;34m";
+
+/// Renders [`Diagnostic`]s as text, with the labelled parts of the source
+/// underlined below each line.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Renderer {
+    color: bool,
+}
+
+struct Underline<'a> {
+    line: usize,
+    start: usize,
+    end: usize,
+    primary: bool,
+    message: &'a str,
+}
+
+impl Renderer {
+    pub fn plain() -> Self {
+        Self { color: false }
+    }
+
+    /// A renderer that uses ANSI escape codes for colour.
+    pub fn colored() -> Self {
+        Self { color: true }
+    }
+
+    /// Renders `diagnostic` against the `source` it was emitted for. Labels
+    /// that do not fit in the source are left out.
+    pub fn render(&self, diagnostic: &Diagnostic, source: &str, file: Option<&str>) -> String {
+        let severity_color = match diagnostic.severity {
+            Severity::Error => RED,
+            Severity::Warning => YELLOW,
+            Severity::Note => GREEN,
+        };
+        let mut out = String::new();
+        out.push_str(&self.paint(severity_color, &diagnostic.severity.to_string()));
+        if let Some(code) = diagnostic.code {
+            out.push_str(&self.paint(severity_color, &format!("[{code}]")));
+        }
+        out.push_str(&self.paint(BOLD, &format!(": {}", diagnostic.message)));
+
+        let lines = source.split('\n').collect::<Vec<_>>();
+        let line_starts = lines
+            .iter()
+            .scan(0, |start, line| {
+                let line_start = *start;
+                *start += line.len() + 1;
+                Some(line_start)
+            })
+            .collect::<Vec<_>>();
+
+        let labels = diagnostic
+            .primary
+            .iter()
+            .map(|label| (label, true))
+            .chain(diagnostic.secondary.iter().map(|label| (label, false)));
+        let mut underlines = labels
+            .filter_map(|(label, primary)| underline(label, primary, &lines, &line_starts))
+            .collect::<Vec<_>>();
+        underlines.sort_by_key(|underline| (underline.line, !underline.primary, underline.start));
+
+        let gutter = underlines
+            .iter()
+            .map(|underline| (underline.line + 1).to_string().len())
+            .max()
+            .unwrap_or(0);
+        let pipe = self.paint(BLUE, "|");
+
+        if let Some(first) = underlines
+            .iter()
+            .find(|underline| underline.primary)
+            .or(underlines.first())
+        {
+            let _ = write!(
+                out,
+                "\n{}{} {}:{}:{}",
+                " ".repeat(gutter),
+                self.paint(BLUE, "-->"),
+                file.unwrap_or("<source>"),
+                first.line + 1,
+                first.start + 1
+            );
+            let _ = write!(out, "\n{} {pipe}", " ".repeat(gutter));
+
+            let mut previous_line = None;
+            for underline in &underlines {
+                if previous_line != Some(underline.line) {
+                    if previous_line.is_some_and(|line| line + 1 < underline.line) {
+                        let _ = write!(out, "\n{}", self.paint(BLUE, "..."));
+                    }
+                    let number = format!("{:>gutter$}", underline.line + 1);
+                    let _ = write!(
+                        out,
+                        "\n{} {pipe} {}",
+                        self.paint(BLUE, &number),
+                        expand_tabs(lines[underline.line])
+                    );
+                    previous_line = Some(underline.line);
+                }
+                let (marker, color) = if underline.primary {
+                    ("^", severity_color)
+                } else {
+                    ("-", BLUE)
+                };
+                let mut marks = marker.repeat((underline.end - underline.start).max(1));
+                if !underline.message.is_empty() {
+                    marks.push(' ');
+                    marks.push_str(underline.message);
+                }
+                let _ = write!(
+                    out,
+                    "\n{} {pipe} {}{}",
+                    " ".repeat(gutter),
+                    " ".repeat(underline.start),
+                    self.paint(color, &marks)
+                );
+            }
+            if !diagnostic.notes.is_empty()
+                || diagnostic.help.is_some()
+                || !diagnostic.suggestions.is_empty()
+            {
+                let _ = write!(out, "\n{} {pipe}", " ".repeat(gutter));
+            }
+        }
+
+        for note in &diagnostic.notes {
+            let _ = write!(
+                out,
+                "\n{} {} {note}",
+                " ".repeat(gutter),
+                self.paint(BOLD, "= note:")
+            );
+        }
+        for suggestion in &diagnostic.suggestions {
+            let _ = write!(
+                out,
+                "\n{} {} {}: `{}`",
+                " ".repeat(gutter),
+                self.paint(BOLD, "= help:"),
+                suggestion.message,
+                suggestion.replacement
+            );
+        }
+        if let Some(help) = &diagnostic.help {
+            let _ = write!(
+                out,
+                "\n{} {} {help}",
+                " ".repeat(gutter),
+                self.paint(BOLD, "= help:")
+            );
+        }
+        out
+    }
+
+    fn paint(&self, color: &str, text: &str) -> String {
+        if self.color {
+            format!("{color}{text}{RESET}")
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+/// Finds the line of the start of the label. Labels spanning several lines
+/// are underlined up to the end of their first line.
+fn underline<'a>(
+    label: &'a Label,
+    primary: bool,
+    lines: &[&str],
+    line_starts: &[usize],
+) -> Option<Underline<'a>> {
+    let Label { span, message } = label;
+    if span.start > span.end || span.end > line_starts.last()? + lines.last()?.len() {
+        return None;
+    }
+    let line = line_starts.partition_point(|start| *start <= span.start) - 1;
+    let text = lines[line];
+    let line_start = line_starts[line];
+    let end = (span.end - line_start).min(text.len());
+    let start = span.start - line_start;
+    if !text.is_char_boundary(start) || !text.is_char_boundary(end) {
+        return None;
+    }
+    Some(Underline {
+        line,
+        start: display_width(&text[..start]),
+        end: display_width(&text[..end]),
+        primary,
+        message,
+    })
+}
+
+fn display_width(text: &str) -> usize {
+    text.chars()
+        .map(|c| if c == '\t' { TAB_WIDTH } else { 1 })
+        .sum()
+}
+
+fn expand_tabs(text: &str) -> String {
+    text.replace('\t', &" ".repeat(TAB_WIDTH))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Diagnostic, Renderer, Suggestion};
+
+    #[test]
+    fn render_primary_label() {
+        let diagnostic = Diagnostic::error("Division by zero.")
+            .with_code("E0302")
+            .with_primary(4..9, "the divisor is zero")
+            .with_help("check the divisor before dividing");
+        let rendered = Renderer::plain().render(&diagnostic, "1 + 2 / 0", None);
+        assert_eq!(
+            rendered,
+            "error[E0302]: Division by zero.
+ --> <source>:1:5
+  |
+1 | 1 + 2 / 0
+  |     ^^^^^ the divisor is zero
+  |
+  = help: check the divisor before dividing"
+        );
+    }
+
+    #[test]
+    fn render_secondary_labels() {
+        let source = "let f x: (int -> int) =\n\tx / 0\n\nlet a = 1\nf a";
+        let diagnostic = Diagnostic::error("Division by zero.")
+            .with_primary(25..30, "")
+            .with_secondary(36..37, "")
+            .with_secondary(42..45, "in this call to f")
+            .with_note("the error happened in a function call");
+        let rendered = Renderer::plain().render(&diagnostic, source, Some("main.fl"));
+        assert_eq!(
+            rendered,
+            "error: Division by zero.
+ --> main.fl:2:5
+  |
+2 |     x / 0
+  |     ^^^^^
+...
+4 | let a = 1
+  |     -
+5 | f a
+  | --- in this call to f
+  |
+  = note: the error happened in a function call"
+        );
+    }
+
+    #[test]
+    fn render_suggestion() {
+        let source = "let count = 1\ncont + 1";
+        let suggestion = Suggestion::new(14..18, "count", "a local with a similar name exists");
+        let diagnostic = Diagnostic::error("Unknown name cont.")
+            .with_primary(14..18, "not found in this scope")
+            .with_suggestion(suggestion.clone());
+        assert_eq!(
+            Renderer::plain().render(&diagnostic, source, None),
+            "error: Unknown name cont.
+ --> <source>:2:1
+  |
+2 | cont + 1
+  | ^^^^ not found in this scope
+  |
+  = help: a local with a similar name exists: `count`"
+        );
+        assert_eq!(suggestion.apply(source), "let count = 1\ncount + 1");
+    }
+
+    #[test]
+    fn render_without_source() {
+        let diagnostic = Diagnostic::error("Unknown name a.")
+            .with_code("E0101")
+            .with_primary(10..11, "not found");
+        let rendered = Renderer::plain().render(&diagnostic, "a", None);
+        assert_eq!(rendered, "error[E0101]: Unknown name a.");
+    }
+
+    #[test]
+    fn render_colored() {
+        let diagnostic = Diagnostic::warning("Unused value.").with_primary(0..1, "");
+        let rendered = Renderer::colored().render(&diagnostic, "a", None);
+        assert!(rendered.starts_with("\x1b[1;33mwarning\x1b[0m"));
+        assert!(rendered.contains("\x1b[1;33m^\x1b[0m"));
+    }
+}
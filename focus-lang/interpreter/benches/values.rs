@@ -0,0 +1,150 @@
+//! Measures the time, the allocations and the peak memory use of numeric
+//! workloads. Run it with `cargo bench -p interpreter`.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{Duration, Instant},
+};
+
+use interpreter::Interpreter;
+
+/// Counts the allocations made through the system allocator.
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+static LIVE_BYTES: AtomicUsize = AtomicUsize::new(0);
+static PEAK_BYTES: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        let live = LIVE_BYTES.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+        PEAK_BYTES.fetch_max(live, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        LIVE_BYTES.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+const INTEGERS: &str = r#"
+let rec fib n: (int -> int) = if n < 2 then n else
+    let a = fib (n - 1)
+    let b = fib (n - 2)
+    a + b
+fib 22
+"#;
+
+const FLOATS: &str = r#"
+let rec settle i x: (int -> float -> float) = if i == 0 then x else
+    settle (i - 1) (x * 0.5 + 1.0)
+let rec repeat j total: (int -> float -> float) = if j == 0 then total else
+    let x = settle 1000 0.0
+    repeat (j - 1) (total + x)
+repeat 100 0.0
+"#;
+
+const ARRAYS: &str = r#"
+let mut grid = [
+    0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15,
+    16, 17, 18, 19, 20, 21, 22, 23, 24, 25, 26, 27, 28, 29, 30, 31,
+    32, 33, 34, 35, 36, 37, 38, 39, 40, 41, 42, 43, 44, 45, 46, 47,
+    48, 49, 50, 51, 52, 53, 54, 55, 56, 57, 58, 59, 60, 61, 62, 63
+]
+let rec sweep i: (int -> ()) = if i > 0 then
+    let k = i % 64
+    grid[k] = grid[k] * 3 % 1000 + i
+    sweep (i - 1)
+let rec rounds j: (int -> ()) = if j > 0 then
+    sweep 1000
+    rounds (j - 1)
+rounds 100
+grid[7]
+"#;
+
+/// Repeats `statements` after `init`, then ends with `result`. Code like
+/// this, without loops, recursion, assignments or `%`, runs on every version
+/// of the interpreter, so the representation of values can be compared
+/// against the first one.
+fn unrolled(init: &str, statements: &str, result: &str) -> String {
+    const ROUNDS: usize = 500;
+    let mut code = format!("{init}\n");
+    for _ in 0..ROUNDS {
+        code += statements;
+        code += "\n";
+    }
+    code + result
+}
+
+struct Measurement {
+    time: Duration,
+    allocations: usize,
+    peak_bytes: usize,
+    value_bytes: usize,
+}
+
+fn measure(code: &str) -> Measurement {
+    let mut interpreter = Interpreter::new();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed);
+    let live = LIVE_BYTES.load(Ordering::Relaxed);
+    PEAK_BYTES.store(live, Ordering::Relaxed);
+    let start = Instant::now();
+    let value = interpreter
+        .interpret_str(code)
+        .expect("Benchmark should run.");
+    Measurement {
+        time: start.elapsed(),
+        allocations: ALLOCATIONS.load(Ordering::Relaxed) - allocations,
+        peak_bytes: PEAK_BYTES.load(Ordering::Relaxed) - live,
+        value_bytes: std::mem::size_of_val(&value),
+    }
+}
+
+/// Runs the workloads whose names start with the first argument, or all of
+/// them.
+fn main() {
+    const RUNS: usize = 5;
+    let filter = std::env::args()
+        .skip(1)
+        .find(|arg| !arg.starts_with("--"))
+        .unwrap_or_default();
+    let workloads = [
+        ("integers", INTEGERS.to_string()),
+        ("floats", FLOATS.to_string()),
+        ("arrays", ARRAYS.to_string()),
+        (
+            "unrolled integers",
+            unrolled("let x = 1", "let x = x * 3 / 4 + 100", "x"),
+        ),
+        (
+            "unrolled floats",
+            unrolled("let x = 1.5", "let x = x * 0.5 + 1.0", "x"),
+        ),
+        (
+            "unrolled arrays",
+            unrolled(
+                "let a = [1, 2, 3, 4]",
+                "let p = a[0]\nlet q = a[1]\nlet r = a[2]\nlet s = a[3]\nlet a = [q + 1, r * 2 / 3, s - p, p]",
+                "a[0]",
+            ),
+        ),
+    ];
+    for (name, code) in workloads {
+        if !name.starts_with(&filter) {
+            continue;
+        }
+        let runs = (0..RUNS).map(|_| measure(&code)).collect::<Vec<_>>();
+        let fastest = runs.iter().map(|run| run.time).min().unwrap_or_default();
+        let last = &runs[RUNS - 1];
+        println!(
+            "{name:<18} {fastest:>12.2?} {:>10} allocations {:>10} peak bytes {:>3} bytes per value",
+            last.allocations, last.peak_bytes, last.value_bytes
+        );
+    }
+}
@@ -0,0 +1,541 @@
+use std::{collections::HashMap, rc::Rc};
+
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use parser::{
+    op::ArithmeticOperator,
+    stmt::{Literal, Operator, Span},
+};
+
+use super::{CallSite, Chunk, Instruction, Prototype};
+use crate::{
+    error::{ErrorKind, RuntimeError, StackFrame},
+    module::{find_module_value, Module},
+    object::Value,
+    typed::{Binding, Capture, TypedExpr, TypedExprKind, TypedLet, TypedStmt},
+};
+
+/// Compiles a statement of the top level to a chunk that ends with its value.
+/// The `source` it was parsed from, if it is known, gives the positions of
+/// its calls in stack traces.
+pub fn compile_stmt(
+    stmt: &TypedStmt,
+    source: Option<&str>,
+    modules: &HashMap<String, Rc<Module>>,
+) -> Result<Chunk, RuntimeError> {
+    let mut compiler = Compiler::new(modules, source, Vec::new());
+    compiler.stmt(stmt, true)?;
+    compiler.emit(Instruction::Return);
+    Ok(compiler.chunk)
+}
+
+struct Compiler<'a> {
+    modules: &'a HashMap<String, Rc<Module>>,
+    source: Option<&'a str>,
+    chunk: Chunk,
+    /// The span of the innermost expression being compiled that records one.
+    span: Option<Span>,
+    /// Whether each scope of the function being compiled has a frame, the
+    /// innermost one last. The top level has none, and neither do blocks
+    /// that declare no names.
+    scopes: Vec<bool>,
+    /// The position the last jump patched goes to. The instruction emitted
+    /// there is not fused with the one before it, which the jump skips.
+    target: usize,
+}
+
+impl<'a> Compiler<'a> {
+    fn new(
+        modules: &'a HashMap<String, Rc<Module>>,
+        source: Option<&'a str>,
+        scopes: Vec<bool>,
+    ) -> Self {
+        Self {
+            modules,
+            source,
+            chunk: Chunk::default(),
+            span: None,
+            scopes,
+            target: 0,
+        }
+    }
+
+    /// The number of frames between the innermost scope and the one `depth`
+    /// scopes out from it, which is the depth of that scope on the stack.
+    fn frame_depth(&self, depth: usize) -> usize {
+        self.scopes
+            .iter()
+            .rev()
+            .take(depth)
+            .filter(|frame| **frame)
+            .count()
+    }
+
+    /// Translates the depth of a local to its depth on the stack.
+    fn binding(&self, binding: &Binding) -> Binding {
+        match binding {
+            Binding::Local { depth, slot } => Binding::Local {
+                depth: self.frame_depth(*depth),
+                slot: *slot,
+            },
+            binding => binding.clone(),
+        }
+    }
+
+    fn emit(&mut self, instruction: Instruction) -> usize {
+        self.chunk.code.push(instruction);
+        self.chunk.spans.push(self.span.clone());
+        self.chunk.code.len() - 1
+    }
+
+    fn constant(&mut self, value: Value) {
+        self.chunk.constants.push(value);
+        self.emit(Instruction::Constant(self.chunk.constants.len() - 1));
+    }
+
+    /// Makes the jump at `jump` go to the next instruction.
+    fn patch(&mut self, jump: usize) {
+        let target = self.chunk.code.len();
+        match &mut self.chunk.code[jump] {
+            Instruction::Jump(to)
+            | Instruction::JumpIfFalse(to)
+            | Instruction::CompareJump(_, to) => *to = target,
+            _ => unreachable!("Only jumps are patched."),
+        }
+        self.target = target;
+    }
+
+    /// The last instruction emitted, unless a jump goes to the next one, so
+    /// that the next one can be fused with it.
+    fn fusable(&mut self) -> Option<&mut Instruction> {
+        if self.target == self.chunk.code.len() {
+            return None;
+        }
+        self.chunk.code.last_mut()
+    }
+
+    /// Emits a jump taken if the condition on top is false, which is fused
+    /// with the comparison of the condition if there is one.
+    fn jump_if_false(&mut self) -> usize {
+        if let Some(last) = self.fusable() {
+            if let Instruction::Comparison(operator) = last {
+                *last = Instruction::CompareJump(operator.clone(), 0);
+                return self.chunk.code.len() - 1;
+            }
+        }
+        self.emit(Instruction::JumpIfFalse(0))
+    }
+
+    /// Emits an arithmetic operator, which is fused with its right operand if
+    /// it is a constant. Its errors are at the span of the operation.
+    fn arithmetic(&mut self, operator: &ArithmeticOperator) {
+        if let Some(&mut Instruction::Constant(index)) = self.fusable() {
+            let last = self.chunk.code.len() - 1;
+            self.chunk.code[last] = Instruction::ArithmeticConstant(operator.clone(), index);
+            self.chunk.spans[last] = self.span.clone();
+            return;
+        }
+        self.emit(Instruction::Arithmetic(operator.clone()));
+    }
+
+    /// Compiles a statement, which leaves its value on top of the stack if
+    /// `keep` is set. A `let` leaves the value of its name, in its slot,
+    /// either way.
+    fn stmt(&mut self, stmt: &TypedStmt, keep: bool) -> Result<(), RuntimeError> {
+        match stmt {
+            TypedStmt::Item(_) => {
+                if keep {
+                    self.constant(Value::UNIT);
+                }
+            }
+            TypedStmt::Let(l) => match &l.expr {
+                // A name declared without a value takes a slot all the same,
+                // which is only read once a value is assigned to it.
+                None => {
+                    self.constant(Value::UNIT);
+                    if keep {
+                        self.constant(Value::UNIT);
+                    }
+                }
+                // A recursive function is bound before its closure is
+                // created, so that it can capture itself.
+                Some(expr) if captures_itself(l, expr) => {
+                    self.constant(Value::UNIT);
+                    self.expr(expr)?;
+                    self.define(l, keep);
+                }
+                Some(expr) => {
+                    self.expr(expr)?;
+                    if keep {
+                        self.emit(Instruction::GetLocal {
+                            depth: 0,
+                            slot: l.slot,
+                        });
+                    }
+                }
+            },
+            TypedStmt::LetRec(lets) => {
+                for _ in lets {
+                    self.constant(Value::UNIT);
+                }
+                let defined = lets.iter().filter(|l| l.expr.is_some()).count();
+                for (i, (l, expr)) in lets
+                    .iter()
+                    .filter_map(|l| Some((l, l.expr.as_ref()?)))
+                    .enumerate()
+                {
+                    self.expr(expr)?;
+                    self.define(l, keep && i == defined - 1);
+                }
+                if keep && defined == 0 {
+                    self.constant(Value::UNIT);
+                }
+            }
+            TypedStmt::Expr(expr) => {
+                self.expr(expr)?;
+                if !keep {
+                    self.emit(Instruction::Pop);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Sets the value of a name bound before its expression is evaluated.
+    fn define(&mut self, l: &TypedLet, keep: bool) {
+        self.emit(Instruction::Define(l.slot));
+        if keep {
+            self.emit(Instruction::GetLocal {
+                depth: 0,
+                slot: l.slot,
+            });
+        }
+    }
+
+    fn expr(&mut self, expr: &TypedExpr) -> Result<(), RuntimeError> {
+        let outer = match &expr.span {
+            Some(span) => self.span.replace(span.clone()),
+            None => self.span.clone(),
+        };
+        let result = self.unspanned_expr(expr);
+        let result = match &expr.span {
+            Some(span) => result.map_err(|err| err.with_span(span)),
+            None => result,
+        };
+        self.span = outer;
+        result
+    }
+
+    fn unspanned_expr(&mut self, expr: &TypedExpr) -> Result<(), RuntimeError> {
+        match &expr.kind {
+            TypedExprKind::Literal(literal) => {
+                let value = literal_value(literal)?;
+                self.constant(value);
+            }
+            TypedExprKind::Path { ident, binding } => self.get(ident, binding)?,
+            TypedExprKind::Field { value, offset } => {
+                self.expr(value)?;
+                self.emit(Instruction::GetField(*offset));
+            }
+            TypedExprKind::Operation { op, lhs, rhs } => match op {
+                Operator::Assignment => {
+                    self.expr(rhs)?;
+                    self.place(lhs)?;
+                    self.emit(Instruction::Assign);
+                }
+                Operator::CompoundAssignment(operator) => {
+                    self.expr(rhs)?;
+                    self.place(lhs)?;
+                    self.emit(Instruction::CompoundAssign(operator.clone().into()));
+                }
+                // Both operands of a boolean operator are evaluated.
+                _ => {
+                    self.expr(lhs)?;
+                    self.expr(rhs)?;
+                    match op {
+                        Operator::Arithmetic(operator) => self.arithmetic(operator),
+                        Operator::Comparison(operator) => {
+                            self.emit(Instruction::Comparison(operator.clone()));
+                        }
+                        Operator::Boolean(operator) => {
+                            self.emit(Instruction::Boolean(operator.clone()));
+                        }
+                        Operator::Assignment | Operator::CompoundAssignment(_) => unreachable!(),
+                    }
+                }
+            },
+            TypedExprKind::Call {
+                path,
+                binding,
+                params,
+            } => {
+                match binding {
+                    Binding::ModuleItem(path) => {
+                        let value = find_module_value(self.modules, path)
+                            .map(|(value, _)| (*value).clone())
+                            .ok_or(ErrorKind::UnknownName { name: path.clone() })?;
+                        self.constant(value);
+                    }
+                    binding => self.get(path, binding)?,
+                }
+                let start = self.chunk.code.len();
+                for param in params {
+                    self.expr(param)?;
+                }
+                let end = self.emit(Instruction::Call(params.len()));
+                let call_site = expr.span.clone().unwrap_or_default();
+                self.chunk.call_sites.push(CallSite {
+                    code: start..end + 1,
+                    frame: StackFrame {
+                        function: path.clone(),
+                        position: self
+                            .source
+                            .and_then(|source| position(source, call_site.start)),
+                        call_site,
+                        repeated: 0,
+                        omitted_calls: 0,
+                    },
+                });
+            }
+            TypedExprKind::Struct(values) | TypedExprKind::Array(values) => {
+                for value in values {
+                    self.expr(value)?;
+                }
+                self.emit(Instruction::Object {
+                    len: values.len(),
+                    type_id: expr.type_id,
+                });
+            }
+            TypedExprKind::Index { value, index } => {
+                self.expr(value)?;
+                self.expr(index)?;
+                self.emit(Instruction::Index);
+            }
+            TypedExprKind::Slice { value, from, to } => {
+                self.expr(value)?;
+                // Each bound is checked with the array and the bounds before
+                // it below it.
+                for (depth, bound) in (1..).zip([from, to].into_iter().flatten()) {
+                    self.expr(bound)?;
+                    self.emit(Instruction::SliceBound { depth });
+                }
+                self.emit(Instruction::Slice {
+                    from: from.is_some(),
+                    to: to.is_some(),
+                });
+            }
+            TypedExprKind::IfElse {
+                condition,
+                if_expr,
+                else_expr,
+            } => {
+                self.expr(condition)?;
+                let to_else = self.jump_if_false();
+                self.expr(if_expr)?;
+                let to_end = self.emit(Instruction::Jump(0));
+                self.patch(to_else);
+                match else_expr {
+                    Some(else_expr) => self.expr(else_expr)?,
+                    None => self.constant(Value::UNIT),
+                }
+                self.patch(to_end);
+            }
+            TypedExprKind::Block(stmts) => {
+                // A block that declares no names needs no frame, and the
+                // locals around it are as many frames out without it.
+                let frame = stmts
+                    .iter()
+                    .any(|stmt| matches!(stmt, TypedStmt::Let(_) | TypedStmt::LetRec(_)));
+                if frame {
+                    self.emit(Instruction::PushFrame);
+                }
+                self.scopes.push(frame);
+                let result = self.block(stmts);
+                self.scopes.pop();
+                result?;
+                if frame {
+                    self.emit(Instruction::PopFrame);
+                }
+            }
+            TypedExprKind::Closure {
+                args,
+                captures,
+                body,
+            } => {
+                // The arguments are in the frame of the call.
+                let mut compiler = Compiler::new(self.modules, self.source, vec![true]);
+                compiler.expr(body)?;
+                compiler.emit(Instruction::Return);
+                mark_tail_calls(&mut compiler.chunk);
+                let captures = captures
+                    .iter()
+                    .map(|capture| Capture {
+                        binding: self.binding(&capture.binding),
+                        ..capture.clone()
+                    })
+                    .collect();
+                self.chunk.prototypes.push(Rc::new(Prototype {
+                    arity: args.len(),
+                    captures,
+                    chunk: Rc::new(compiler.chunk),
+                }));
+                self.emit(Instruction::Closure(self.chunk.prototypes.len() - 1));
+            }
+            TypedExprKind::Cast(value) => {
+                self.expr(value)?;
+                self.emit(Instruction::Cast(expr.type_id));
+            }
+            TypedExprKind::Reference(value) => {
+                self.place(value)?;
+                self.emit(Instruction::Reference);
+            }
+            TypedExprKind::Deref(reference) => {
+                self.expr(reference)?;
+                self.emit(Instruction::Deref);
+            }
+        }
+        Ok(())
+    }
+
+    fn block(&mut self, stmts: &[TypedStmt]) -> Result<(), RuntimeError> {
+        match stmts.split_last() {
+            Some((last, stmts)) => {
+                for stmt in stmts {
+                    self.stmt(stmt, false)?;
+                }
+                self.stmt(last, true)
+            }
+            None => {
+                self.constant(Value::UNIT);
+                Ok(())
+            }
+        }
+    }
+
+    /// Compiles the read of a local or a captured variable.
+    fn get(&mut self, ident: &str, binding: &Binding) -> Result<(), RuntimeError> {
+        match self.binding(binding) {
+            Binding::Local { depth, slot } => self.emit(Instruction::GetLocal { depth, slot }),
+            Binding::Capture { index } => self.emit(Instruction::GetCapture(index)),
+            Binding::ModuleItem(_) => {
+                return Err(ErrorKind::Internal(format!("{ident} is not a local.")).into())
+            }
+        };
+        Ok(())
+    }
+
+    /// Compiles an expression that can be assigned to, like a path, a field
+    /// or an index, to its place. Any other expression is evaluated to a
+    /// temporary place, whose fields and elements can be read.
+    fn place(&mut self, expr: &TypedExpr) -> Result<(), RuntimeError> {
+        match &expr.kind {
+            TypedExprKind::Path { ident, binding } => match self.binding(binding) {
+                Binding::Local { depth, slot } => {
+                    self.emit(Instruction::PlaceLocal { depth, slot });
+                }
+                Binding::Capture { index } => {
+                    self.emit(Instruction::PlaceCapture(index));
+                }
+                Binding::ModuleItem(_) => {
+                    return Err(ErrorKind::Internal(format!("{ident} is not a local.")).into())
+                }
+            },
+            TypedExprKind::Field { value, offset } => {
+                self.place(value)?;
+                self.emit(Instruction::PlaceField(*offset));
+            }
+            TypedExprKind::Index { value, index } => {
+                self.place(value)?;
+                self.expr(index)?;
+                self.emit(Instruction::PlaceIndex);
+            }
+            TypedExprKind::Deref(reference) => {
+                self.expr(reference)?;
+                self.emit(Instruction::PlaceDeref);
+            }
+            _ => {
+                self.expr(expr)?;
+                self.emit(Instruction::PlaceTemporary);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Turns the calls whose value a function returns as it is into tail calls.
+/// A block ending with such a call only pops its frame on the way to the
+/// return, which the tail call drops along with the rest of the frames of
+/// the function.
+fn mark_tail_calls(chunk: &mut Chunk) {
+    for ip in 0..chunk.code.len() {
+        if let Instruction::Call(argc) = chunk.code[ip] {
+            if returns(&chunk.code, ip + 1) {
+                chunk.code[ip] = Instruction::TailCall(argc);
+            }
+        }
+    }
+}
+
+/// Whether the code from `ip` returns the value on top without changing it.
+fn returns(code: &[Instruction], mut ip: usize) -> bool {
+    loop {
+        match code[ip] {
+            Instruction::Return => return true,
+            Instruction::PopFrame => ip += 1,
+            // Jumps only go forwards.
+            Instruction::Jump(target) => ip = target,
+            _ => return false,
+        }
+    }
+}
+
+/// The line and the column, both counted from 1, of a byte of the source.
+fn position(source: &str, at: usize) -> Option<(usize, usize)> {
+    let before = source.get(..at)?;
+    let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+    let line = before.matches('\n').count() + 1;
+    Some((line, before[line_start..].chars().count() + 1))
+}
+
+fn captures_itself(l: &TypedLet, expr: &TypedExpr) -> bool {
+    match &expr.kind {
+        TypedExprKind::Closure { captures, .. } => captures.iter().any(|capture| {
+            capture.binding
+                == Binding::Local {
+                    depth: 0,
+                    slot: l.slot,
+                }
+        }),
+        _ => false,
+    }
+}
+
+fn literal_value(literal: &Literal) -> Result<Value, RuntimeError> {
+    match literal {
+        Literal::Unit => Ok(Value::UNIT),
+        Literal::Boolean(bool) => Ok(Value::from(*bool)),
+        Literal::Char(char) => Ok(Value::from(*char)),
+        Literal::Integer(integer) => Ok(Value::from(*integer)),
+        Literal::SizedInteger(integer, kind) => Value::integer(*integer, *kind).ok_or_else(|| {
+            ErrorKind::Internal("Integer literal is out of range.".to_string()).into()
+        }),
+        Literal::BigInteger(digits) => Ok(Value::from(Rc::new(parse_digits(digits)?))),
+        Literal::Rational(decimal) => {
+            let (whole, fraction) = decimal.split_once('.').unwrap_or((decimal, ""));
+            let numer = parse_digits(&format!("{whole}{fraction}"))?;
+            let denom = BigInt::from(10).pow(fraction.len() as u32);
+            Ok(Value::from(Rc::new(BigRational::new(numer, denom))))
+        }
+        Literal::Float(float) => Ok(Value::from(*float)),
+        Literal::String(_) => Err(ErrorKind::Internal(
+            "String literals are rejected by the checker.".to_string(),
+        )
+        .into()),
+    }
+}
+
+fn parse_digits(digits: &str) -> Result<BigInt, RuntimeError> {
+    digits
+        .parse()
+        .map_err(|_| ErrorKind::Internal(format!("Unable to parse number {digits}.")).into())
+}
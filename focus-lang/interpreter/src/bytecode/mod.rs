@@ -0,0 +1,141 @@
+//! The bytecode the interpreter runs, compiled from the [typed tree](crate::typed)
+//! one statement of the top level at a time. The body of every function is
+//! compiled to its own [`Chunk`].
+//!
+//! Instructions work on the values on top of the stack. Locals live on the
+//! same stack, in the frame of the block or call they are declared in, so
+//! the value a `let` pushes is the local itself. Places that can be assigned
+//! to or referenced are built on a separate stack of places, as they are not
+//! values.
+
+mod compiler;
+
+use std::{ops::Range, rc::Rc};
+
+use parser::{
+    op::{ArithmeticOperator, BooleanOperator, ComparisonOperator},
+    stmt::Span,
+};
+
+pub use compiler::compile_stmt;
+
+use crate::{error::StackFrame, object::Value, r#type::TypeId, typed::Capture};
+
+#[derive(Debug)]
+pub enum Instruction {
+    /// Pushes a constant of the chunk.
+    Constant(usize),
+    /// Pushes the value of a local declared `depth` frames out from the
+    /// innermost one.
+    GetLocal {
+        depth: usize,
+        slot: usize,
+    },
+    /// Pushes the value of the `index`th variable captured by the function
+    /// being run.
+    GetCapture(usize),
+    /// Replaces the struct on top of the stack with one of its fields.
+    GetField(usize),
+    /// Pops an index and the array below it, and pushes the element.
+    Index,
+    /// Replaces the reference on top of the stack with the value it refers to.
+    Deref,
+    Pop,
+    /// Pops a value into a local of the innermost frame, which is assigned
+    /// through its cell if it has been captured.
+    Define(usize),
+    /// Pushes the place of a local.
+    PlaceLocal {
+        depth: usize,
+        slot: usize,
+    },
+    /// Pushes the place of a captured variable.
+    PlaceCapture(usize),
+    /// Replaces the place on top with one of its fields.
+    PlaceField(usize),
+    /// Pops an index and replaces the place on top with the element.
+    PlaceIndex,
+    /// Pops a reference and pushes the place it refers to.
+    PlaceDeref,
+    /// Pops a value and pushes a new place holding it.
+    PlaceTemporary,
+    /// Pops a place and pushes a reference to it.
+    Reference,
+    /// Pops a place and assigns it the value popped after it.
+    Assign,
+    /// Pops a place and applies the operator to its value and the value
+    /// popped after it.
+    CompoundAssign(ArithmeticOperator),
+    Arithmetic(ArithmeticOperator),
+    /// Applies the operator to the value on top and a constant of the chunk.
+    ArithmeticConstant(ArithmeticOperator, usize),
+    Comparison(ComparisonOperator),
+    Boolean(BooleanOperator),
+    /// Converts the value on top to the type.
+    Cast(TypeId),
+    /// Pops the values of the fields of a struct, or of the elements of an
+    /// array, and pushes it.
+    Object {
+        len: usize,
+        type_id: TypeId,
+    },
+    /// Checks the bound of a slice on top, with the array `depth` values
+    /// below it.
+    SliceBound {
+        depth: usize,
+    },
+    /// Pops the bounds that are given and an array, and pushes a copy of the
+    /// elements between the bounds.
+    Slice {
+        from: bool,
+        to: bool,
+    },
+    /// Pushes a function created from a prototype of the chunk.
+    Closure(usize),
+    /// Calls the function below the arguments on top.
+    Call(usize),
+    /// Calls the function below the arguments on top in place of the
+    /// function being run, which returns the value of the call.
+    TailCall(usize),
+    Jump(usize),
+    /// Pops a boolean and jumps if it is false.
+    JumpIfFalse(usize),
+    /// Pops two values and jumps if the comparison of them is false.
+    CompareJump(ComparisonOperator, usize),
+    /// Pushes a frame for the locals of a block.
+    PushFrame,
+    /// Pops the frame of a block, keeping the value on top.
+    PopFrame,
+    /// Ends the chunk with the value on top.
+    Return,
+}
+
+/// A compiled statement of the top level or body of a function.
+#[derive(Debug, Default)]
+pub struct Chunk {
+    pub code: Vec<Instruction>,
+    /// The span of the innermost expression that records one, for each
+    /// instruction.
+    pub spans: Vec<Option<Span>>,
+    pub constants: Vec<Value>,
+    pub prototypes: Vec<Rc<Prototype>>,
+    pub call_sites: Vec<CallSite>,
+}
+
+/// A call in a chunk, which is in the stack trace of the errors that happen
+/// while its instructions run.
+#[derive(Debug)]
+pub struct CallSite {
+    /// The instructions that evaluate the arguments and call the function.
+    pub code: Range<usize>,
+    pub frame: StackFrame,
+}
+
+/// What a function is created from, besides the variables it captures.
+#[derive(Debug)]
+pub struct Prototype {
+    /// The number of arguments the function takes.
+    pub arity: usize,
+    pub captures: Vec<Capture>,
+    pub chunk: Rc<Chunk>,
+}
@@ -0,0 +1,34 @@
+use parser::stmt::Cast;
+
+use super::{infer::ConstraintKind, TypeChecker};
+use crate::{
+    error::RuntimeError,
+    r#type::Type,
+    typed::{TypedExpr, TypedExprKind},
+};
+
+impl TypeChecker<'_> {
+    pub(super) fn check_cast(&mut self, cast: Cast) -> Result<TypedExpr, RuntimeError> {
+        let expr = self.check_expr(*cast.expr, None)?;
+        let to = self.resolve_type(&cast.ty)?;
+        self.constrain(
+            expr.type_id,
+            ConstraintKind::CastTo(to.type_id),
+            Some(cast.span),
+        )?;
+
+        Ok(TypedExpr::new(
+            TypedExprKind::Cast(Box::new(expr)),
+            to.type_id,
+        ))
+    }
+}
+
+/// Checks if a value of type `from` can be explicitly cast to type `to`.
+/// Both types are expected to be resolved.
+pub(super) fn is_valid_cast(from: &Type, to: &Type) -> bool {
+    from == to
+        || (from.is_numeric() && to.is_numeric())
+        || (from.integer_kind().is_some() && to.is_char())
+        || (from.is_char() && to.integer_kind().is_some())
+}
@@ -0,0 +1,420 @@
+use std::rc::Rc;
+
+use parser::stmt::{Arg, Call, Closure, Expression, IfElse, Index, Literal, Span, Stmt};
+
+use super::{Declaration, TypeChecker};
+use crate::{
+    error::{ErrorKind, RuntimeError},
+    r#type::{FunctionType, PrimitiveType, TypeId, TypeLayout},
+    typed::{TypedExpr, TypedExprKind, TypedStmt},
+};
+
+impl TypeChecker<'_> {
+    pub(super) fn check_expr(
+        &mut self,
+        expr: Expression,
+        expected_type: Option<TypeId>,
+    ) -> Result<TypedExpr, RuntimeError> {
+        let span = expr.span().clone();
+        match self.check_unspanned_expr(expr, expected_type) {
+            Ok(typed) => Ok(TypedExpr {
+                span: Some(span),
+                ..typed
+            }),
+            Err(err) => Err(err.with_span(&span)),
+        }
+    }
+
+    fn check_unspanned_expr(
+        &mut self,
+        expr: Expression,
+        expected_type: Option<TypeId>,
+    ) -> Result<TypedExpr, RuntimeError> {
+        let typed = match expr {
+            Expression::Literal(literal, _) => {
+                let type_id = self.resolve_literal_type(&literal)?;
+                TypedExpr::new(TypedExprKind::Literal(literal), type_id)
+            }
+            Expression::Path(path) => self.check_path(path)?,
+            Expression::Operation(operation) => self.check_operation(operation, expected_type)?,
+            Expression::Call(call) => self.check_call(call)?,
+            Expression::Struct(r#struct) => self.check_struct(r#struct)?,
+            Expression::Range(_) => return Err(unsupported("Ranges outside of an index").into()),
+            Expression::Array(array, _) => self.check_array(array, expected_type)?,
+            Expression::Index(index) => self.check_index(index)?,
+            Expression::Field(field) => {
+                let value = self.check_expr(*field.value, None)?;
+                let start = field.span.end - field.ident.len();
+                self.check_field(value, &field.ident, start, || {
+                    "the value whose field is accessed".to_string()
+                })?
+            }
+            Expression::IfElse(if_else) => self.check_if_else(if_else, expected_type)?,
+            Expression::Match(_) => return Err(unsupported("Match expressions").into()),
+            Expression::For(_) => return Err(unsupported("For loops").into()),
+            Expression::Block(block, _) => {
+                self.with_block_scope(|checker| checker.check_block(block, expected_type))?
+            }
+            Expression::Closure(closure) => self.check_closure(closure, expected_type)?,
+            Expression::Cast(cast) => self.check_cast(cast)?,
+            Expression::Reference(reference) => self.check_reference(reference, expected_type)?,
+            Expression::Deref(deref) => self.check_deref(deref)?,
+        };
+
+        self.expect_type(typed.type_id, expected_type)?;
+        Ok(typed)
+    }
+
+    fn resolve_literal_type(&self, literal: &Literal) -> Result<TypeId, ErrorKind> {
+        match literal {
+            Literal::Unit => self
+                .type_registry
+                .get_type_id_from_name(PrimitiveType::Unit.name())
+                .ok_or(ErrorKind::Internal("Unable to find unit type.".to_string())),
+            Literal::Boolean(_) => self
+                .type_registry
+                .get_type_id_from_name(PrimitiveType::Boolean.name())
+                .ok_or(ErrorKind::Internal("Unable to find bool type.".to_string())),
+            Literal::Char(_) => self
+                .type_registry
+                .get_type_id_from_name(PrimitiveType::Char.name())
+                .ok_or(ErrorKind::Internal("Unable to find char type.".to_string())),
+            Literal::SizedInteger(_, kind) => Ok(PrimitiveType::from_integer_kind(*kind).type_id()),
+            Literal::BigInteger(_) => Ok(PrimitiveType::BigInteger.type_id()),
+            Literal::Rational(_) => Ok(PrimitiveType::Rational.type_id()),
+            Literal::Integer(_) => self
+                .type_registry
+                .get_type_id_from_name(PrimitiveType::Integer.name())
+                .ok_or(ErrorKind::Internal(
+                    "Unable to find integer type.".to_string(),
+                )),
+            Literal::Float(_) => self
+                .type_registry
+                .get_type_id_from_name(PrimitiveType::Float.name())
+                .ok_or(ErrorKind::Internal(
+                    "Unable to find float type.".to_string(),
+                )),
+            Literal::String(_) => Err(unsupported("String literals")),
+        }
+    }
+
+    /// Checks a call. Functions are curried, so a call with fewer parameters
+    /// than the function takes is a function of the remaining ones, and the
+    /// extra parameters of a call with more are passed to the function it
+    /// returns. Calling a name whose type is not known yet infers it to be a
+    /// function of the types of the parameters.
+    fn check_call(&mut self, call: Call) -> Result<TypedExpr, RuntimeError> {
+        let (mut type_id, binding) = self.resolve_callee_type(&call.path, &call.span)?;
+        let mut params = Vec::with_capacity(call.params.len());
+        let mut remaining = call.params.into_iter().peekable();
+
+        while remaining.peek().is_some() {
+            let callee_type_id = self.prune(type_id);
+            let ty = self
+                .type_registry
+                .get_resolved_type_from_id(callee_type_id)
+                .ok_or(ErrorKind::Internal(format!(
+                    "Type with id {callee_type_id} not found."
+                )))?;
+
+            let (arg_types, ret_type) = match ty.as_function() {
+                Some(FunctionType {
+                    arg_types,
+                    ret_type,
+                }) => (arg_types.clone(), *ret_type),
+                None if ty.is_variable() => {
+                    let arg_types = (0..remaining.len())
+                        .map(|_| self.fresh_type_variable())
+                        .collect::<Vec<_>>();
+                    let ret_type = self.fresh_type_variable();
+                    let function_type = self
+                        .type_registry
+                        .insert_or_get_function_type(arg_types.clone(), ret_type);
+                    self.expect_type(function_type, Some(callee_type_id))?;
+                    (arg_types, ret_type)
+                }
+                None => {
+                    return Err(ErrorKind::NotCallable {
+                        name: call.path,
+                        found: ty.ident.clone(),
+                    }
+                    .into())
+                }
+            };
+
+            let mut applied = 0;
+            while applied < arg_types.len() {
+                let Some(param) = remaining.next() else {
+                    break;
+                };
+                params.push(self.check_expr(param, Some(arg_types[applied]))?);
+                applied += 1;
+            }
+
+            type_id = if applied < arg_types.len() {
+                self.type_registry
+                    .insert_or_get_function_type(arg_types[applied..].to_vec(), ret_type)
+            } else {
+                ret_type
+            };
+        }
+
+        Ok(TypedExpr::new(
+            TypedExprKind::Call {
+                path: call.path,
+                binding,
+                params,
+            },
+            type_id,
+        ))
+    }
+
+    /// Checks an array literal. All elements must have the same type, which
+    /// is inferred from their uses for an empty array.
+    fn check_array(
+        &mut self,
+        array: Vec<Expression>,
+        expected_type: Option<TypeId>,
+    ) -> Result<TypedExpr, RuntimeError> {
+        let expected_el_type_id = expected_type
+            .map(|type_id| self.prune(type_id))
+            .and_then(|type_id| self.type_registry.get_resolved_type_from_id(type_id))
+            .and_then(|ty| match ty.layout {
+                TypeLayout::Array(el_type_id) => Some(el_type_id),
+                _ => None,
+            });
+        let el_type_id = expected_el_type_id.unwrap_or_else(|| self.fresh_type_variable());
+
+        let values = array
+            .into_iter()
+            .map(|el| self.check_expr(el, Some(el_type_id)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let type_id = self.array_type(el_type_id);
+        Ok(TypedExpr::new(TypedExprKind::Array(values), type_id))
+    }
+
+    fn check_index(&mut self, index: Index) -> Result<TypedExpr, RuntimeError> {
+        let value = self.check_expr(*index.value, None)?;
+        let value = self.auto_deref(value);
+        let type_id = self.prune(value.type_id);
+        let ty = self
+            .type_registry
+            .get_resolved_type_from_id(type_id)
+            .ok_or(ErrorKind::Internal(format!(
+                "Type with id {type_id} not found."
+            )))?;
+        let el_type_id = match ty.layout {
+            TypeLayout::Array(el_type_id) => el_type_id,
+            TypeLayout::Variable => {
+                let el_type_id = self.fresh_type_variable();
+                let array_type = self.array_type(el_type_id);
+                self.expect_type(array_type, Some(type_id))?;
+                el_type_id
+            }
+            _ => {
+                return Err(ErrorKind::NotIndexable {
+                    found: ty.ident.clone(),
+                }
+                .into())
+            }
+        };
+
+        let integer = Some(PrimitiveType::Integer.type_id());
+        match *index.index {
+            Expression::Range(range) => {
+                let mut bound = |expr: Option<Box<Expression>>| {
+                    expr.map(|expr| self.check_expr(*expr, integer).map(Box::new))
+                        .transpose()
+                };
+                let from = bound(range.from)?;
+                let to = bound(range.to)?;
+                let type_id = value.type_id;
+                Ok(TypedExpr::new(
+                    TypedExprKind::Slice {
+                        value: Box::new(value),
+                        from,
+                        to,
+                    },
+                    type_id,
+                ))
+            }
+            index => {
+                let index = self.check_expr(index, integer)?;
+                Ok(TypedExpr::new(
+                    TypedExprKind::Index {
+                        value: Box::new(value),
+                        index: Box::new(index),
+                    },
+                    el_type_id,
+                ))
+            }
+        }
+    }
+
+    /// Checks both branches, so they must have the same type even if only one
+    /// of them will ever run. Without an `else` the branch must be `unit`.
+    fn check_if_else(
+        &mut self,
+        if_else: IfElse,
+        expected_type: Option<TypeId>,
+    ) -> Result<TypedExpr, RuntimeError> {
+        let condition =
+            self.check_expr(*if_else.condition, Some(PrimitiveType::Boolean.type_id()))?;
+
+        let if_expr = self.check_expr(*if_else.if_expr, expected_type)?;
+        let else_expr = match if_else.else_expr {
+            Some(else_expr) => {
+                let else_expr = self.check_expr(*else_expr, expected_type)?;
+                self.expect_type(else_expr.type_id, Some(if_expr.type_id))?;
+                Some(Box::new(else_expr))
+            }
+            None => {
+                self.expect_type(if_expr.type_id, Some(PrimitiveType::Unit.type_id()))?;
+                None
+            }
+        };
+
+        let type_id = if else_expr.is_some() {
+            if_expr.type_id
+        } else {
+            PrimitiveType::Unit.type_id()
+        };
+        Ok(TypedExpr::new(
+            TypedExprKind::IfElse {
+                condition: Box::new(condition),
+                if_expr: Box::new(if_expr),
+                else_expr,
+            },
+            type_id,
+        ))
+    }
+
+    /// Checks the statements of a block. The block has the type of its last
+    /// statement, which is the only one checked against the `expected_type`.
+    fn check_block(
+        &mut self,
+        block: Vec<Stmt>,
+        expected_type: Option<TypeId>,
+    ) -> Result<TypedExpr, RuntimeError> {
+        let len = block.len();
+        let mut type_id = PrimitiveType::Unit.type_id();
+        let mut stmts = Vec::with_capacity(len);
+        for (i, stmt) in block.into_iter().enumerate() {
+            let last = i + 1 == len;
+            let stmt = match stmt {
+                Stmt::Expr(expr) => {
+                    let expr = self.check_expr(expr, expected_type.filter(|_| last))?;
+                    type_id = expr.type_id;
+                    TypedStmt::Expr(expr)
+                }
+                Stmt::Item(_) if last => {
+                    return Err(ErrorKind::Internal(
+                        "An item declaration cannot have a type.".to_string(),
+                    )
+                    .into())
+                }
+                stmt => {
+                    type_id = PrimitiveType::Unit.type_id();
+                    self.check_stmt(stmt)?
+                }
+            };
+            stmts.push(stmt);
+        }
+
+        Ok(TypedExpr::new(TypedExprKind::Block(stmts), type_id))
+    }
+
+    /// Checks a closure. The types of its arguments come from the function
+    /// type it is expected to have, or are inferred from its body.
+    fn check_closure(
+        &mut self,
+        closure: Closure,
+        expected_type: Option<TypeId>,
+    ) -> Result<TypedExpr, RuntimeError> {
+        let args = if closure.args.is_empty() {
+            vec![unit_arg()]
+        } else {
+            closure.args
+        };
+
+        let expected_function = expected_type
+            .map(|type_id| self.prune(type_id))
+            .and_then(|type_id| self.type_registry.get_resolved_type_from_id(type_id))
+            .and_then(|ty| {
+                ty.as_function()
+                    .map(|function| (function.arg_types.clone(), function.ret_type))
+            });
+        let (arg_types, ret_type) = match expected_function {
+            Some((arg_types, _)) if args.len() != arg_types.len() => {
+                return Err(ErrorKind::ArgumentCount {
+                    expected: arg_types.len(),
+                    found: args.len(),
+                }
+                .into())
+            }
+            Some(function) => function,
+            None => (
+                args.iter().map(|arg| self.arg_type(&arg.ident)).collect(),
+                self.fresh_type_variable(),
+            ),
+        };
+
+        let closure = self.check_function_body(args, &arg_types, *closure.block, ret_type)?;
+        let type_id = self
+            .type_registry
+            .insert_or_get_function_type(arg_types, ret_type);
+        Ok(TypedExpr::new(closure, type_id))
+    }
+
+    /// The type of an argument whose type is to be inferred. `()` and the
+    /// empty name of `let f () = ..` are the `unit` argument.
+    pub(super) fn arg_type(&mut self, arg: &str) -> TypeId {
+        match arg {
+            "" | "()" => PrimitiveType::Unit.type_id(),
+            _ => self.fresh_type_variable(),
+        }
+    }
+
+    /// Checks the body of a function with its arguments in scope, returning
+    /// it as a closure.
+    pub(super) fn check_function_body(
+        &mut self,
+        args: Vec<Arg>,
+        arg_types: &[TypeId],
+        body: Expression,
+        ret_type: TypeId,
+    ) -> Result<TypedExprKind, RuntimeError> {
+        let names = args.iter().zip(arg_types).map(|(arg, type_id)| {
+            let declaration = Declaration {
+                mutable: arg.mutable,
+                deferred: false,
+                span: Some(arg.span.clone()),
+            };
+            (arg.ident.clone(), *type_id, declaration)
+        });
+        let (body, captures) =
+            self.with_function_scope(names, |checker| checker.check_expr(body, Some(ret_type)));
+        Ok(TypedExprKind::Closure {
+            args: args.into_iter().map(|arg| arg.ident).collect(),
+            captures,
+            body: Rc::new(body?),
+        })
+    }
+}
+
+/// The error for a construct the interpreter cannot run yet.
+pub(super) fn unsupported(construct: &str) -> ErrorKind {
+    ErrorKind::Unsupported {
+        construct: construct.to_string(),
+    }
+}
+
+/// The `unit` argument of a function declared without arguments.
+pub(super) fn unit_arg() -> Arg {
+    Arg {
+        ident: "()".to_string(),
+        mutable: false,
+        span: Span::default(),
+    }
+}
@@ -0,0 +1,484 @@
+use std::{collections::HashMap, rc::Rc};
+
+use parser::stmt::Span;
+
+use super::TypeChecker;
+use crate::{
+    error::{ErrorKind, RuntimeError},
+    r#type::{PrimitiveType, Type, TypeId, TypeLayout},
+    typed::{TypedExpr, TypedExprKind, TypedLet, TypedStmt},
+};
+
+/// A requirement on the type of an operand that can only be checked once the
+/// type is known, like the operands of `+` having to be numbers.
+pub(super) struct Constraint {
+    pub type_id: TypeId,
+    pub kind: ConstraintKind,
+    pub span: Option<Span>,
+}
+
+pub(super) enum ConstraintKind {
+    Numeric,
+    Ordered,
+    CastTo(TypeId),
+}
+
+enum UnifyError {
+    Mismatch,
+    /// Binding the type variable would make the type contain itself.
+    Infinite {
+        var: TypeId,
+        ty: TypeId,
+    },
+}
+
+impl TypeChecker<'_> {
+    fn get_type(&self, type_id: TypeId) -> Rc<Type> {
+        self.type_registry
+            .get_resolved_type_from_id(type_id)
+            .unwrap_or_else(|| panic!("Type with id {type_id} not found"))
+    }
+
+    pub(super) fn fresh_type_variable(&mut self) -> TypeId {
+        self.type_registry.insert_type_variable()
+    }
+
+    /// Follows aliases and the type variables that have been inferred, up to
+    /// the first type that is not one of them.
+    pub(super) fn prune(&self, mut type_id: TypeId) -> TypeId {
+        loop {
+            type_id = self.get_type(type_id).type_id;
+            match self.substitution.get(&type_id) {
+                Some(inferred) => type_id = *inferred,
+                None => return type_id,
+            }
+        }
+    }
+
+    /// Replaces every inferred type variable in a type, including the ones
+    /// in the arguments of functions and elements of arrays.
+    pub(super) fn zonk(&mut self, type_id: TypeId) -> TypeId {
+        let type_id = self.prune(type_id);
+        match &self.get_type(type_id).layout {
+            TypeLayout::Function(function) => {
+                let arg_types = function
+                    .arg_types
+                    .iter()
+                    .map(|arg_type| self.zonk(*arg_type))
+                    .collect::<Vec<_>>();
+                let ret_type = self.zonk(function.ret_type);
+                self.type_registry
+                    .insert_or_get_function_type(arg_types, ret_type)
+            }
+            TypeLayout::Array(el_type_id) => {
+                let el_type_id = self.zonk(*el_type_id);
+                self.array_type(el_type_id)
+            }
+            TypeLayout::Reference { type_id, mutable } => {
+                let type_id = self.zonk(*type_id);
+                self.reference_type(type_id, *mutable)
+            }
+            _ => type_id,
+        }
+    }
+
+    pub(super) fn array_type(&mut self, el_type_id: TypeId) -> TypeId {
+        self.type_registry
+            .insert_or_get_array_type_for_type(el_type_id)
+            .unwrap_or_else(|| panic!("Type with id {el_type_id} not found"))
+    }
+
+    pub(super) fn reference_type(&mut self, type_id: TypeId, mutable: bool) -> TypeId {
+        self.type_registry
+            .insert_or_get_reference_type(type_id, mutable)
+            .unwrap_or_else(|| panic!("Type with id {type_id} not found"))
+    }
+
+    /// The name of a type to show in errors, with what is known of its type
+    /// variables filled in.
+    pub(super) fn type_name(&mut self, type_id: TypeId) -> String {
+        let type_id = self.zonk(type_id);
+        self.type_registry.type_name(type_id)
+    }
+
+    fn unify(&mut self, t1: TypeId, t2: TypeId) -> Result<(), UnifyError> {
+        let t1 = self.prune(t1);
+        let t2 = self.prune(t2);
+        if t1 == t2 {
+            return Ok(());
+        }
+
+        let (ty1, ty2) = (self.get_type(t1), self.get_type(t2));
+        match (&ty1.layout, &ty2.layout) {
+            (TypeLayout::Variable, _) => self.bind(t1, t2),
+            (_, TypeLayout::Variable) => self.bind(t2, t1),
+            (TypeLayout::Function(f1), TypeLayout::Function(f2))
+                if f1.arg_types.len() == f2.arg_types.len() =>
+            {
+                for (a1, a2) in f1.arg_types.iter().zip(&f2.arg_types) {
+                    self.unify(*a1, *a2)?;
+                }
+                self.unify(f1.ret_type, f2.ret_type)
+            }
+            (TypeLayout::Array(el1), TypeLayout::Array(el2)) => self.unify(*el1, *el2),
+            (
+                TypeLayout::Reference {
+                    type_id: t1,
+                    mutable: m1,
+                },
+                TypeLayout::Reference {
+                    type_id: t2,
+                    mutable: m2,
+                },
+            ) if m1 == m2 => self.unify(*t1, *t2),
+            _ => Err(UnifyError::Mismatch),
+        }
+    }
+
+    fn bind(&mut self, var: TypeId, type_id: TypeId) -> Result<(), UnifyError> {
+        if self.occurs(var, type_id) {
+            return Err(UnifyError::Infinite { var, ty: type_id });
+        }
+        self.substitution.insert(var, type_id);
+        Ok(())
+    }
+
+    fn occurs(&self, var: TypeId, type_id: TypeId) -> bool {
+        let type_id = self.prune(type_id);
+        match &self.get_type(type_id).layout {
+            TypeLayout::Function(function) => {
+                function
+                    .arg_types
+                    .iter()
+                    .any(|arg_type| self.occurs(var, *arg_type))
+                    || self.occurs(var, function.ret_type)
+            }
+            TypeLayout::Array(el_type_id)
+            | TypeLayout::Reference {
+                type_id: el_type_id,
+                ..
+            } => self.occurs(var, *el_type_id),
+            _ => type_id == var,
+        }
+    }
+
+    /// Fails unless `type_id` can be the `expected_type`, if there is one,
+    /// inferring the type variables of both as needed.
+    pub(super) fn expect_type(
+        &mut self,
+        type_id: TypeId,
+        expected_type: Option<TypeId>,
+    ) -> Result<TypeId, RuntimeError> {
+        if let Some(expected_type) = expected_type {
+            self.unify(type_id, expected_type)
+                .map_err(|err| match err {
+                    UnifyError::Mismatch => ErrorKind::MismatchedTypes {
+                        expected: self.type_name(expected_type),
+                        found: self.type_name(type_id),
+                    },
+                    UnifyError::Infinite { var, ty } => self.infinite_type(var, ty),
+                })?;
+        }
+        Ok(type_id)
+    }
+
+    /// Fails unless both operands of an operation can have the same type.
+    pub(super) fn expect_same_operands(
+        &mut self,
+        lhs: TypeId,
+        rhs: TypeId,
+    ) -> Result<(), RuntimeError> {
+        self.unify(lhs, rhs).map_err(|err| match err {
+            UnifyError::Mismatch => ErrorKind::MismatchedOperands {
+                lhs: self.type_name(lhs),
+                rhs: self.type_name(rhs),
+            },
+            UnifyError::Infinite { var, ty } => self.infinite_type(var, ty),
+        })?;
+        Ok(())
+    }
+
+    fn infinite_type(&mut self, var: TypeId, ty: TypeId) -> ErrorKind {
+        ErrorKind::InfiniteType {
+            var: self.type_registry.type_name(var),
+            ty: self.type_name(ty),
+        }
+    }
+
+    fn free_type_variables(&self, type_id: TypeId, vars: &mut Vec<TypeId>) {
+        let type_id = self.prune(type_id);
+        match &self.get_type(type_id).layout {
+            TypeLayout::Variable if !vars.contains(&type_id) => vars.push(type_id),
+            TypeLayout::Function(function) => {
+                for arg_type in &function.arg_types {
+                    self.free_type_variables(*arg_type, vars);
+                }
+                self.free_type_variables(function.ret_type, vars);
+            }
+            TypeLayout::Array(el_type_id)
+            | TypeLayout::Reference {
+                type_id: el_type_id,
+                ..
+            } => self.free_type_variables(*el_type_id, vars),
+            _ => {}
+        }
+    }
+
+    pub(super) fn has_type_variables(&self, type_id: TypeId) -> bool {
+        let mut vars = Vec::new();
+        self.free_type_variables(type_id, &mut vars);
+        !vars.is_empty()
+    }
+
+    /// Turns the type variables of `type_id` that no name in scope refers to
+    /// into generic types, so every use of the name can give them a different
+    /// type.
+    pub(super) fn generalize(&mut self, type_id: TypeId) -> Result<TypeId, RuntimeError> {
+        Ok(self.generalize_group(&[type_id])?[0])
+    }
+
+    /// Generalizes the types of names declared together, like the functions
+    /// of a `let rec`, which can share type variables.
+    pub(super) fn generalize_group(
+        &mut self,
+        type_ids: &[TypeId],
+    ) -> Result<Vec<TypeId>, RuntimeError> {
+        let mut env_vars = Vec::new();
+        for scope in &self.scopes {
+            for (scope_type_id, _) in scope.names.values() {
+                self.free_type_variables(*scope_type_id, &mut env_vars);
+            }
+        }
+        let generalizable = |checker: &Self| {
+            let mut vars = Vec::new();
+            for type_id in type_ids {
+                checker.free_type_variables(*type_id, &mut vars);
+            }
+            vars.retain(|var| !env_vars.contains(var));
+            vars
+        };
+
+        // A generic type could be anything, so the ones that must be numbers
+        // or ordered become `int` instead.
+        let vars = generalizable(self);
+        self.solve_constraints(|var| vars.contains(&var))?;
+
+        for (index, var) in generalizable(self).into_iter().enumerate() {
+            let generic = self.type_registry.insert_or_get_generic_type(index);
+            self.substitution.insert(var, generic);
+        }
+        Ok(type_ids.iter().map(|type_id| self.zonk(*type_id)).collect())
+    }
+
+    /// Gives the generic types of the type of a name fresh type variables,
+    /// for a new use of the name.
+    pub(super) fn instantiate(&mut self, type_id: TypeId) -> TypeId {
+        let type_id = self.zonk(type_id);
+        self.replace_generics(type_id, &mut HashMap::new())
+    }
+
+    fn replace_generics(&mut self, type_id: TypeId, vars: &mut HashMap<TypeId, TypeId>) -> TypeId {
+        match &self.get_type(type_id).layout {
+            TypeLayout::Generic => *vars
+                .entry(type_id)
+                .or_insert_with(|| self.type_registry.insert_type_variable()),
+            TypeLayout::Function(function) => {
+                let arg_types = function
+                    .arg_types
+                    .iter()
+                    .map(|arg_type| self.replace_generics(*arg_type, vars))
+                    .collect::<Vec<_>>();
+                let ret_type = self.replace_generics(function.ret_type, vars);
+                self.type_registry
+                    .insert_or_get_function_type(arg_types, ret_type)
+            }
+            TypeLayout::Array(el_type_id) => {
+                let el_type_id = self.replace_generics(*el_type_id, vars);
+                self.array_type(el_type_id)
+            }
+            TypeLayout::Reference { type_id, mutable } => {
+                let type_id = self.replace_generics(*type_id, vars);
+                self.reference_type(type_id, *mutable)
+            }
+            _ => type_id,
+        }
+    }
+
+    /// Checks the constraints whose types are known. Those whose types are
+    /// still type variables accepted by `default` become `int`, the others
+    /// are left for later.
+    pub(super) fn solve_constraints(
+        &mut self,
+        default: impl Fn(TypeId) -> bool,
+    ) -> Result<(), RuntimeError> {
+        let constraints = std::mem::take(&mut self.constraints);
+        for constraint in constraints {
+            let mut type_id = self.prune(constraint.type_id);
+            if self.get_type(type_id).is_variable() {
+                if !default(type_id) {
+                    self.constraints.push(constraint);
+                    continue;
+                }
+                self.substitution
+                    .insert(type_id, PrimitiveType::Integer.type_id());
+                type_id = PrimitiveType::Integer.type_id();
+            }
+            self.check_constraint(&constraint.kind, type_id)
+                .map_err(|err| match &constraint.span {
+                    Some(span) => RuntimeError::from(err).with_span(span),
+                    None => err.into(),
+                })?;
+        }
+        Ok(())
+    }
+
+    /// Checks a constraint on a type, now or once the type is inferred.
+    pub(super) fn constrain(
+        &mut self,
+        type_id: TypeId,
+        kind: ConstraintKind,
+        span: Option<Span>,
+    ) -> Result<(), ErrorKind> {
+        let type_id = self.prune(type_id);
+        if self.get_type(type_id).is_variable() {
+            self.constraints.push(Constraint {
+                type_id,
+                kind,
+                span,
+            });
+            Ok(())
+        } else {
+            self.check_constraint(&kind, type_id)
+        }
+    }
+
+    fn check_constraint(
+        &mut self,
+        kind: &ConstraintKind,
+        type_id: TypeId,
+    ) -> Result<(), ErrorKind> {
+        let ty = self.get_type(type_id);
+        match kind {
+            ConstraintKind::Numeric if !ty.is_numeric() => Err(ErrorKind::NotNumeric {
+                found: ty.ident.clone(),
+            }),
+            ConstraintKind::Ordered if !ty.is_numeric() && !ty.is_char() => {
+                Err(ErrorKind::NotOrdered {
+                    found: ty.ident.clone(),
+                })
+            }
+            ConstraintKind::CastTo(to) => {
+                let to = self.get_type(*to);
+                if super::cast::is_valid_cast(&ty, &to) {
+                    Ok(())
+                } else {
+                    Err(ErrorKind::InvalidCast {
+                        from: ty.ident.clone(),
+                        to: to.ident.clone(),
+                    })
+                }
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks what is left to check once a whole program has been checked,
+    /// and fills in the inferred types of a statement. Statements must be
+    /// finished in order, as they can initialize the names of the ones before.
+    pub fn finish_stmt(&mut self, stmt: &mut TypedStmt) -> Result<(), RuntimeError> {
+        self.solve_constraints(|_| true)?;
+        self.finish_typed_stmt(stmt);
+        match stmt {
+            TypedStmt::Let(l) => self.expect_inferred(l)?,
+            TypedStmt::LetRec(lets) => lets.iter().try_for_each(|l| self.expect_inferred(l))?,
+            _ => {}
+        }
+        self.check_initialization(stmt)
+    }
+
+    /// Fails if the type of a name declared at the top level is not fully
+    /// known, as nothing that comes after it can give it a type anymore.
+    fn expect_inferred(&self, l: &TypedLet) -> Result<(), RuntimeError> {
+        if self.has_type_variables(l.type_id) {
+            return Err(ErrorKind::TypeAnnotationRequired {
+                subject: l.ident.clone(),
+            }
+            .into());
+        }
+        Ok(())
+    }
+
+    fn finish_typed_stmt(&mut self, stmt: &mut TypedStmt) {
+        match stmt {
+            TypedStmt::Item(_) => {}
+            TypedStmt::Let(l) => self.finish_let(l),
+            TypedStmt::LetRec(lets) => {
+                for l in lets {
+                    self.finish_let(l);
+                }
+            }
+            TypedStmt::Expr(expr) => self.finish_expr(expr),
+        }
+    }
+
+    fn finish_let(&mut self, l: &mut TypedLet) {
+        l.type_id = self.zonk(l.type_id);
+        if let Some(expr) = &mut l.expr {
+            self.finish_expr(expr);
+        }
+    }
+
+    fn finish_expr(&mut self, expr: &mut TypedExpr) {
+        expr.type_id = self.zonk(expr.type_id);
+        match &mut expr.kind {
+            TypedExprKind::Literal(_) | TypedExprKind::Path { .. } => {}
+            TypedExprKind::Field { value, .. }
+            | TypedExprKind::Reference(value)
+            | TypedExprKind::Deref(value) => self.finish_expr(value),
+            TypedExprKind::Operation { lhs, rhs, .. } => {
+                self.finish_expr(lhs);
+                self.finish_expr(rhs);
+            }
+            TypedExprKind::Call { params: exprs, .. }
+            | TypedExprKind::Struct(exprs)
+            | TypedExprKind::Array(exprs) => {
+                for expr in exprs {
+                    self.finish_expr(expr);
+                }
+            }
+            TypedExprKind::Index { value, index } => {
+                self.finish_expr(value);
+                self.finish_expr(index);
+            }
+            TypedExprKind::Slice { value, from, to } => {
+                self.finish_expr(value);
+                for bound in [from, to].into_iter().flatten() {
+                    self.finish_expr(bound);
+                }
+            }
+            TypedExprKind::IfElse {
+                condition,
+                if_expr,
+                else_expr,
+            } => {
+                self.finish_expr(condition);
+                self.finish_expr(if_expr);
+                if let Some(else_expr) = else_expr {
+                    self.finish_expr(else_expr);
+                }
+            }
+            TypedExprKind::Block(stmts) => {
+                for stmt in stmts {
+                    self.finish_typed_stmt(stmt);
+                }
+            }
+            TypedExprKind::Closure { captures, body, .. } => {
+                for capture in captures {
+                    capture.type_id = self.zonk(capture.type_id);
+                }
+                self.finish_expr(Rc::make_mut(body));
+            }
+            TypedExprKind::Cast(expr) => self.finish_expr(expr),
+        }
+    }
+}
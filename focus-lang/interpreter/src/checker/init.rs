@@ -0,0 +1,282 @@
+use std::collections::HashMap;
+
+use parser::stmt::{Operator, Span};
+
+use super::TypeChecker;
+use crate::{
+    error::{ErrorKind, RuntimeError},
+    typed::{Binding, Capture, TypedExpr, TypedExprKind, TypedLet, TypedStmt},
+};
+
+/// Whether a name declared without a value has been assigned one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Initialized {
+    No,
+    /// Only on some of the paths that lead here.
+    Maybe,
+    Yes,
+}
+
+/// A name declared without a value, which is assigned one later.
+#[derive(Clone)]
+pub(super) struct Deferred {
+    pub mutable: bool,
+    pub initialized: Initialized,
+    /// Span of the name where it is declared, if it is in the code being
+    /// checked.
+    pub span: Option<Span>,
+}
+
+/// Finds the uses of names declared without a value that might come before
+/// a value is assigned to them. A name is initialized once it is assigned on
+/// every path that leads to a use, e.g. in both branches of an `if`. A name
+/// that is not mutable can only be assigned on paths where it is not yet.
+///
+/// The analysis follows the scopes of the checker: it knows the names
+/// declared without a value in every scope of the function it is in, by
+/// slot, the innermost scope last.
+struct Initialization {
+    scopes: Vec<HashMap<usize, Deferred>>,
+}
+
+impl Initialization {
+    /// Starts in a scope with the `deferred` names declared so far, like the
+    /// top level.
+    fn new(deferred: HashMap<usize, Deferred>) -> Self {
+        Self {
+            scopes: vec![deferred],
+        }
+    }
+
+    /// The names declared without a value in the outermost scope.
+    fn into_deferred(mut self) -> HashMap<usize, Deferred> {
+        self.scopes.swap_remove(0)
+    }
+
+    fn check_stmt(&mut self, stmt: &TypedStmt) -> Result<(), RuntimeError> {
+        match stmt {
+            TypedStmt::Item(_) => Ok(()),
+            TypedStmt::Let(l) => self.check_let(l),
+            TypedStmt::LetRec(lets) => lets.iter().try_for_each(|l| self.check_let(l)),
+            TypedStmt::Expr(expr) => self.check_expr(expr),
+        }
+    }
+
+    fn check_let(&mut self, l: &TypedLet) -> Result<(), RuntimeError> {
+        match &l.expr {
+            Some(expr) => {
+                self.check_expr(expr)?;
+                self.scope(0).remove(&l.slot);
+            }
+            None => {
+                let deferred = Deferred {
+                    mutable: l.mutable,
+                    initialized: Initialized::No,
+                    span: Some(l.span.clone()),
+                };
+                self.scope(0).insert(l.slot, deferred);
+            }
+        }
+        Ok(())
+    }
+
+    fn check_expr(&mut self, expr: &TypedExpr) -> Result<(), RuntimeError> {
+        match &expr.kind {
+            TypedExprKind::Literal(_) => Ok(()),
+            TypedExprKind::Path { ident, binding } => self.check_use(ident, binding, &expr.span),
+            TypedExprKind::Field { value, .. }
+            | TypedExprKind::Reference(value)
+            | TypedExprKind::Deref(value) => self.check_expr(value),
+            TypedExprKind::Operation {
+                op: Operator::Assignment,
+                lhs,
+                rhs,
+            } => {
+                self.check_expr(rhs)?;
+                match &lhs.kind {
+                    TypedExprKind::Path {
+                        ident,
+                        binding: Binding::Local { depth, slot },
+                    } => self.check_assignment(ident, *depth, *slot, &lhs.span),
+                    _ => self.check_expr(lhs),
+                }
+            }
+            TypedExprKind::Operation {
+                op: Operator::Boolean(_),
+                lhs,
+                rhs,
+            } => {
+                // The right operand might not run.
+                self.check_expr(lhs)?;
+                let before = self.scopes.clone();
+                self.check_expr(rhs)?;
+                self.merge(before);
+                Ok(())
+            }
+            TypedExprKind::Operation { lhs, rhs, .. } => {
+                self.check_expr(lhs)?;
+                self.check_expr(rhs)
+            }
+            TypedExprKind::Call {
+                path,
+                binding,
+                params,
+            } => {
+                self.check_use(path, binding, &expr.span)?;
+                params.iter().try_for_each(|param| self.check_expr(param))
+            }
+            TypedExprKind::Struct(exprs) | TypedExprKind::Array(exprs) => {
+                exprs.iter().try_for_each(|expr| self.check_expr(expr))
+            }
+            TypedExprKind::Index { value, index } => {
+                self.check_expr(value)?;
+                self.check_expr(index)
+            }
+            TypedExprKind::Slice { value, from, to } => {
+                self.check_expr(value)?;
+                [from, to]
+                    .into_iter()
+                    .flatten()
+                    .try_for_each(|bound| self.check_expr(bound))
+            }
+            TypedExprKind::IfElse {
+                condition,
+                if_expr,
+                else_expr,
+            } => {
+                self.check_expr(condition)?;
+                let before = self.scopes.clone();
+                self.check_expr(if_expr)?;
+                let after_if = std::mem::replace(&mut self.scopes, before);
+                if let Some(else_expr) = else_expr {
+                    self.check_expr(else_expr)?;
+                }
+                self.merge(after_if);
+                Ok(())
+            }
+            TypedExprKind::Block(stmts) => {
+                self.scopes.push(HashMap::new());
+                let result = stmts.iter().try_for_each(|stmt| self.check_stmt(stmt));
+                self.scopes.pop();
+                result
+            }
+            TypedExprKind::Closure { captures, body, .. } => {
+                self.check_captures(captures)?;
+                // The arguments and the captured names of a function are
+                // always initialized.
+                Initialization::new(HashMap::new()).check_expr(body)
+            }
+            TypedExprKind::Cast(expr) => self.check_expr(expr),
+        }
+    }
+
+    /// Checks that the names a function captures are initialized when it is
+    /// created, as it might use them whenever it is called.
+    fn check_captures(&mut self, captures: &[Capture]) -> Result<(), RuntimeError> {
+        captures
+            .iter()
+            .try_for_each(|capture| self.check_use(&capture.ident, &capture.binding, &None))
+    }
+
+    fn check_use(
+        &mut self,
+        ident: &str,
+        binding: &Binding,
+        span: &Option<Span>,
+    ) -> Result<(), RuntimeError> {
+        let Binding::Local { depth, slot } = binding else {
+            return Ok(());
+        };
+        match self.scope(*depth).get(slot) {
+            Some(deferred) if deferred.initialized != Initialized::Yes => {
+                let err = RuntimeError::from(ErrorKind::PossiblyUninitialized {
+                    name: ident.to_string(),
+                });
+                Err(with_name_span(err, ident, span))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Initializes a name declared without a value, which can only be done
+    /// once if it is not mutable.
+    fn check_assignment(
+        &mut self,
+        ident: &str,
+        depth: usize,
+        slot: usize,
+        span: &Option<Span>,
+    ) -> Result<(), RuntimeError> {
+        let Some(deferred) = self.scope(depth).get_mut(&slot) else {
+            return Ok(());
+        };
+        if !deferred.mutable && deferred.initialized != Initialized::No {
+            let err = RuntimeError::from(ErrorKind::AssignToImmutable {
+                name: ident.to_string(),
+                declared_at: deferred.span.clone(),
+            });
+            return Err(with_name_span(err, ident, span));
+        }
+        deferred.initialized = Initialized::Yes;
+        Ok(())
+    }
+
+    /// Merges the state of another path that leads to the same place.
+    fn merge(&mut self, other: Vec<HashMap<usize, Deferred>>) {
+        for (scope, other) in self.scopes.iter_mut().zip(other) {
+            for (slot, deferred) in scope.iter_mut() {
+                if other
+                    .get(slot)
+                    .is_some_and(|other| other.initialized != deferred.initialized)
+                {
+                    deferred.initialized = Initialized::Maybe;
+                }
+            }
+        }
+    }
+
+    fn scope(&mut self, depth: usize) -> &mut HashMap<usize, Deferred> {
+        let index = self.scopes.len() - 1 - depth;
+        &mut self.scopes[index]
+    }
+}
+
+/// Points an error at the name a path starts with.
+fn with_name_span(err: RuntimeError, ident: &str, span: &Option<Span>) -> RuntimeError {
+    match span {
+        Some(span) => err.with_span(&(span.start..span.start + ident.len())),
+        None => err,
+    }
+}
+
+impl TypeChecker<'_> {
+    /// Checks that a statement of the top level only uses names once they
+    /// are initialized.
+    pub(super) fn check_initialization(&mut self, stmt: &TypedStmt) -> Result<(), RuntimeError> {
+        let mut initialization = Initialization::new(std::mem::take(&mut self.deferred_globals));
+        let result = initialization.check_stmt(stmt);
+        self.deferred_globals = initialization.into_deferred();
+
+        let lets = match stmt {
+            TypedStmt::Let(l) => std::slice::from_ref(l),
+            TypedStmt::LetRec(lets) => lets.as_slice(),
+            _ => &[],
+        };
+        for l in lets {
+            self.global_slots.insert(l.ident.clone(), l.slot);
+        }
+        result
+    }
+
+    /// The names of the top level declared without a value, with whether
+    /// they are initialized after the statements finished so far.
+    pub fn deferred_globals(&self) -> HashMap<String, Initialized> {
+        self.global_slots
+            .iter()
+            .filter_map(|(ident, slot)| {
+                let deferred = self.deferred_globals.get(slot)?;
+                Some((ident.clone(), deferred.initialized))
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,472 @@
+mod cast;
+mod expr;
+mod infer;
+mod init;
+mod operation;
+mod reference;
+mod stmt;
+mod r#struct;
+
+#[cfg(test)]
+mod tests;
+
+use std::{collections::HashMap, rc::Rc};
+
+use diagnostic::Suggestion;
+use parser::stmt::{Path, Span};
+
+use infer::Constraint;
+use init::Deferred;
+pub use init::Initialized;
+
+use crate::{
+    error::{ErrorKind, RuntimeError},
+    module::{find_module_value, Module, ModuleDefinition},
+    r#type::{Type, TypeId, TypeRegistry},
+    suggestion::best_match,
+    typed::{Binding, Capture, TypedExpr, TypedExprKind},
+};
+
+/// Checks the types of a program before it runs, turning it into a tree of
+/// [`TypedStmt`](crate::typed::TypedStmt)s the interpreter can evaluate
+/// without checking any types again.
+///
+/// Both branches of an `if` and the bodies of functions are checked where
+/// they are written, whether they end up running or not.
+pub struct TypeChecker<'a> {
+    type_registry: &'a mut TypeRegistry,
+    modules: &'a HashMap<String, Rc<Module>>,
+    /// The names in scope, the innermost scope last. The first scope holds
+    /// the names defined at the top level.
+    scopes: Vec<Scope>,
+    /// The types inferred for type variables so far.
+    substitution: HashMap<TypeId, TypeId>,
+    /// Constraints on type variables, checked once the variables are inferred.
+    constraints: Vec<Constraint>,
+    /// The names of the top level declared without a value, by slot, after
+    /// the statements finished so far.
+    deferred_globals: HashMap<usize, Deferred>,
+    /// The slot of each name of the top level after the statements finished
+    /// so far.
+    global_slots: HashMap<String, usize>,
+}
+
+/// The names declared in a scope, with their types and slots. The scope of
+/// a function also has the names it captures from the scopes around it.
+#[derive(Default)]
+struct Scope {
+    names: HashMap<String, (TypeId, usize)>,
+    /// How the name in each slot is declared.
+    declarations: Vec<Declaration>,
+    is_function: bool,
+    captures: Vec<Capture>,
+}
+
+/// How a name is declared, which tells if it can be assigned to.
+#[derive(Clone, Default)]
+struct Declaration {
+    mutable: bool,
+    /// Whether the name is declared without a value. It can be assigned one
+    /// once even if it is not mutable.
+    deferred: bool,
+    /// Span of the name where it is declared, if it is in the code being
+    /// checked.
+    span: Option<Span>,
+}
+
+/// A name defined at the top level by the code run before.
+pub struct Global {
+    pub ident: String,
+    pub type_id: TypeId,
+    pub mutable: bool,
+    /// For a name declared without a value, whether it has been assigned one
+    /// since.
+    pub initialized: Option<Initialized>,
+}
+
+impl<'a> TypeChecker<'a> {
+    /// Creates a checker that knows the `globals` defined so far, which take
+    /// the slots of the top level in the order they are given.
+    pub fn new(
+        type_registry: &'a mut TypeRegistry,
+        modules: &'a HashMap<String, Rc<Module>>,
+        globals: impl IntoIterator<Item = Global>,
+    ) -> Self {
+        let mut globals_scope = Scope::default();
+        let mut global_slots = HashMap::new();
+        let mut deferred_globals = HashMap::new();
+        for global in globals {
+            let declaration = Declaration {
+                mutable: global.mutable,
+                deferred: global.initialized.is_some(),
+                span: None,
+            };
+            let slot = globals_scope.declare(global.ident.clone(), global.type_id, declaration);
+            global_slots.insert(global.ident, slot);
+            if let Some(initialized) = global.initialized {
+                deferred_globals.insert(
+                    slot,
+                    Deferred {
+                        mutable: global.mutable,
+                        initialized,
+                        span: None,
+                    },
+                );
+            }
+        }
+        Self {
+            type_registry,
+            modules,
+            scopes: vec![globals_scope],
+            substitution: HashMap::new(),
+            constraints: Vec::new(),
+            deferred_globals,
+            global_slots,
+        }
+    }
+
+    /// Declares a name in the innermost scope, returning its slot.
+    fn declare(&mut self, ident: &str, type_id: TypeId, declaration: Declaration) -> usize {
+        self.innermost_scope()
+            .declare(ident.to_string(), type_id, declaration)
+    }
+
+    /// Removes a name from the innermost scope, returning its slot so it can
+    /// be declared again in the same slot.
+    fn undeclare(&mut self, ident: &str) -> usize {
+        let (_, slot) = self
+            .innermost_scope()
+            .names
+            .remove(ident)
+            .expect("Name to remove not declared.");
+        slot
+    }
+
+    fn declare_in_slot(&mut self, ident: &str, type_id: TypeId, slot: usize) {
+        self.innermost_scope()
+            .names
+            .insert(ident.to_string(), (type_id, slot));
+    }
+
+    fn innermost_scope(&mut self) -> &mut Scope {
+        self.scopes
+            .last_mut()
+            .expect("No scope to declare a name in.")
+    }
+
+    /// Finds what a name used in the innermost scope refers to. A name
+    /// declared outside of the innermost function is captured by it, and by
+    /// every function in between.
+    fn find_local(&mut self, ident: &str) -> Option<(TypeId, Binding)> {
+        self.find_local_in(self.scopes.len() - 1, ident)
+    }
+
+    fn find_local_in(&mut self, mut scope: usize, ident: &str) -> Option<(TypeId, Binding)> {
+        let mut depth = 0;
+        loop {
+            let Scope {
+                names, is_function, ..
+            } = &self.scopes[scope];
+            if let Some((type_id, slot)) = names.get(ident) {
+                return Some((*type_id, Binding::Local { depth, slot: *slot }));
+            }
+            if *is_function || scope == 0 {
+                break;
+            }
+            scope -= 1;
+            depth += 1;
+        }
+
+        let captures = &self.scopes[scope].captures;
+        if let Some(index) = captures.iter().position(|capture| capture.ident == ident) {
+            return Some((captures[index].type_id, Binding::Capture { index }));
+        }
+        let (type_id, binding) = self.find_local_in(scope.checked_sub(1)?, ident)?;
+        let captures = &mut self.scopes[scope].captures;
+        captures.push(Capture {
+            ident: ident.to_string(),
+            type_id,
+            binding,
+        });
+        Some((
+            type_id,
+            Binding::Capture {
+                index: captures.len() - 1,
+            },
+        ))
+    }
+
+    /// Finds how a name used in the innermost scope is declared.
+    fn find_declaration(&self, ident: &str) -> Option<&Declaration> {
+        self.scopes.iter().rev().find_map(|scope| {
+            let (_, slot) = scope.names.get(ident)?;
+            scope.declarations.get(*slot)
+        })
+    }
+
+    fn local_names(&self) -> impl Iterator<Item = &str> {
+        self.scopes
+            .iter()
+            .flat_map(|scope| scope.names.keys().map(String::as_str))
+    }
+
+    /// Runs `check` in the scope of a function with the given arguments,
+    /// returning the names the function captures.
+    fn with_function_scope<T>(
+        &mut self,
+        args: impl IntoIterator<Item = (String, TypeId, Declaration)>,
+        check: impl FnOnce(&mut Self) -> T,
+    ) -> (T, Vec<Capture>) {
+        let mut scope = Scope {
+            is_function: true,
+            ..Default::default()
+        };
+        for (ident, type_id, declaration) in args {
+            scope.declare(ident, type_id, declaration);
+        }
+        self.scopes.push(scope);
+        let result = check(self);
+        let scope = self.scopes.pop().expect("Scope pushed above.");
+        (result, scope.captures)
+    }
+
+    /// Runs `check` in the scope of a block, whose names are not in scope
+    /// anymore once it ends.
+    fn with_block_scope<T>(&mut self, check: impl FnOnce(&mut Self) -> T) -> T {
+        self.scopes.push(Scope::default());
+        let result = check(self);
+        self.scopes.pop();
+        result
+    }
+
+    /// Checks a local, or a field of a local like `point.x`, which is lowered
+    /// to a field of the path before it. Fields can only be accessed on values
+    /// whose type is already known.
+    fn check_path(&mut self, path: Path) -> Result<TypedExpr, RuntimeError> {
+        let mut path_parts = path.path.split('.');
+        let root = path_parts
+            .next()
+            .ok_or(ErrorKind::Internal("Invalid path.".to_string()))?;
+
+        let Some((type_id, binding)) = self.find_local(root) else {
+            return Err(RuntimeError::from(ErrorKind::UnknownName {
+                name: root.to_string(),
+            })
+            .with_suggestion(suggest(
+                root,
+                path.span.start,
+                self.local_names(),
+                "a local with a similar name exists",
+            )));
+        };
+        let type_id = self.instantiate(type_id);
+        let mut expr = TypedExpr {
+            kind: TypedExprKind::Path {
+                ident: root.to_string(),
+                binding,
+            },
+            type_id,
+            span: Some(path.span.start..path.span.start + root.len()),
+        };
+        let mut offset = path.span.start + root.len() + 1;
+
+        for p in path_parts {
+            let subject = || path.path[..offset - path.span.start - 1].to_string();
+            expr = self.check_field(expr, p, offset, subject)?;
+            offset += p.len() + 1;
+            expr.span = Some(path.span.start..offset - 1);
+        }
+
+        Ok(expr)
+    }
+
+    /// Lowers a field of a value, like the `x` in `point.x` or `ps[0].x`.
+    /// `start` is where the name of the field starts, and `subject` names the
+    /// value if its type is not known yet.
+    fn check_field(
+        &mut self,
+        expr: TypedExpr,
+        ident: &str,
+        start: usize,
+        subject: impl FnOnce() -> String,
+    ) -> Result<TypedExpr, RuntimeError> {
+        let expr = self.auto_deref(expr);
+        let type_id = self.prune(expr.type_id);
+        let value_type = self
+            .type_registry
+            .get_resolved_type_from_id(type_id)
+            .ok_or(ErrorKind::Internal(format!(
+                "Type with id {type_id} not found."
+            )))?;
+        if value_type.is_variable() {
+            return Err(ErrorKind::TypeAnnotationRequired { subject: subject() }.into());
+        }
+        let fields = value_type.as_struct().ok_or(ErrorKind::NotAStruct {
+            name: value_type.ident.clone(),
+        })?;
+
+        let Some((offset, field)) = fields.iter().enumerate().find(|(_, f)| f.ident == ident)
+        else {
+            return Err(RuntimeError::from(ErrorKind::UnknownField {
+                ty: value_type.ident.clone(),
+                field: ident.to_string(),
+            })
+            .with_suggestion(suggest(
+                ident,
+                start,
+                fields.iter().map(|field| field.ident.as_str()),
+                "a field with a similar name exists",
+            )));
+        };
+        Ok(TypedExpr::new(
+            TypedExprKind::Field {
+                value: Box::new(expr),
+                offset,
+            },
+            field.type_id,
+        ))
+    }
+
+    /// Finds the type of a function to call and what its name refers to,
+    /// either a local or a name defined in a module like
+    /// `Std.U8.wrapping_add`. `span` is the span of the call,
+    /// which starts with the path.
+    fn resolve_callee_type(
+        &mut self,
+        path: &str,
+        span: &Span,
+    ) -> Result<(TypeId, Binding), RuntimeError> {
+        if let Some((type_id, binding)) = self.find_local(path) {
+            return Ok((self.instantiate(type_id), binding));
+        }
+
+        find_module_value(self.modules, path)
+            .map(|(_, type_id)| {
+                (
+                    self.instantiate(type_id),
+                    Binding::ModuleItem(path.to_string()),
+                )
+            })
+            .ok_or_else(|| {
+                RuntimeError::from(ErrorKind::UnknownName {
+                    name: path.to_string(),
+                })
+                .with_suggestion(self.suggest_callee(path, span.start))
+            })
+    }
+
+    /// Suggests a fix for the first part of a callee path that is not found,
+    /// from the locals and modules for the root and from the definitions of
+    /// the enclosing module for the other parts.
+    fn suggest_callee(&self, path: &str, start: usize) -> Option<Suggestion> {
+        let mut path_parts = path.split('.');
+        let root = path_parts.next()?;
+        let Some(mut module) = self.modules.get(root).cloned() else {
+            return suggest(
+                root,
+                start,
+                self.local_names()
+                    .chain(self.modules.keys().map(String::as_str)),
+                "a local or module with a similar name exists",
+            );
+        };
+
+        let mut offset = start + root.len() + 1;
+        for part in path_parts {
+            match module.get_definition_from_partial_path(&mut [part].into_iter()) {
+                Some(ModuleDefinition::Module(inner)) => module = inner,
+                Some(_) => return None,
+                None => {
+                    return suggest(
+                        part,
+                        offset,
+                        module.definition_names(),
+                        "a definition with a similar name exists",
+                    )
+                }
+            }
+            offset += part.len() + 1;
+        }
+        None
+    }
+
+    /// Gets the type a type annotation names.
+    fn resolve_type(&mut self, ty: &parser::Type) -> Result<Rc<Type>, RuntimeError> {
+        self.type_registry
+            .get_type_from_expr(ty)
+            .ok_or_else(|| self.unknown_type_error(ty))
+    }
+
+    /// The error for a type annotation that names a type that is not found,
+    /// at the first such name in it.
+    fn unknown_type_error(&self, ty: &parser::Type) -> RuntimeError {
+        match self.unknown_type_name(ty) {
+            Some((name, span)) => self.unknown_type_name_error(name, span),
+            None => ErrorKind::UnknownType {
+                name: ty.to_string(),
+            }
+            .into(),
+        }
+    }
+
+    /// The first name in a type annotation that is not the name of a type.
+    fn unknown_type_name<'t>(&self, ty: &'t parser::Type) -> Option<(&'t str, &'t Span)> {
+        match ty {
+            parser::Type::Unit => None,
+            parser::Type::Name(name, span) => self
+                .type_registry
+                .get_type_id_from_name(name)
+                .is_none()
+                .then_some((name, span)),
+            parser::Type::Array(ty) | parser::Type::Reference { ty, .. } => {
+                self.unknown_type_name(ty)
+            }
+            parser::Type::Function(function) => function
+                .args
+                .iter()
+                .chain([&*function.ret])
+                .find_map(|ty| self.unknown_type_name(ty)),
+        }
+    }
+
+    /// The error for a name that is not the name of a type, which suggests a
+    /// type with a similar name.
+    fn unknown_type_name_error(&self, name: &str, span: &Span) -> RuntimeError {
+        let suggestion = suggest(
+            name,
+            span.start,
+            self.type_registry.type_names(),
+            "a type with a similar name exists",
+        );
+        RuntimeError::from(ErrorKind::UnknownType {
+            name: name.to_string(),
+        })
+        .with_span(span)
+        .with_suggestion(suggestion)
+    }
+}
+
+impl Scope {
+    fn declare(&mut self, ident: String, type_id: TypeId, declaration: Declaration) -> usize {
+        let slot = self.declarations.len();
+        self.names.insert(ident, (type_id, slot));
+        self.declarations.push(declaration);
+        slot
+    }
+}
+
+/// Suggests replacing `name`, which starts at byte `start`, with the closest
+/// of the `candidates`.
+fn suggest<'a>(
+    name: &str,
+    start: usize,
+    candidates: impl IntoIterator<Item = &'a str>,
+    message: &str,
+) -> Option<Suggestion> {
+    let replacement = best_match(name, candidates)?;
+    Some(Suggestion::new(
+        start..start + name.len(),
+        replacement,
+        message,
+    ))
+}
@@ -0,0 +1,164 @@
+use parser::{
+    op::ComparisonOperator,
+    stmt::{Expression, Operation, Operator, Span},
+};
+
+use super::{infer::ConstraintKind, TypeChecker};
+use crate::{
+    error::{ErrorKind, RuntimeError},
+    r#type::{PrimitiveType, TypeId},
+    typed::{Binding, TypedExpr, TypedExprKind},
+};
+
+impl TypeChecker<'_> {
+    pub(super) fn check_operation(
+        &mut self,
+        operation: Operation,
+        expected_type: Option<TypeId>,
+    ) -> Result<TypedExpr, RuntimeError> {
+        let assignment = matches!(
+            operation.op,
+            Operator::Assignment | Operator::CompoundAssignment(_)
+        );
+        if assignment && !is_place(&operation.lhs) {
+            return Err(ErrorKind::InvalidAssignmentTarget {
+                expr: operation.lhs.name().to_string(),
+            }
+            .into());
+        }
+
+        // Only arithmetic operations produce a value of their operands' type.
+        let operand_type = match operation.op {
+            Operator::Arithmetic(_) => expected_type,
+            _ => None,
+        };
+        let lhs = self.check_expr(*operation.lhs, operand_type)?;
+        if assignment {
+            let initializes = operation.op == Operator::Assignment;
+            self.check_mutable(&lhs, initializes)?;
+        }
+        let rhs = self.check_expr(*operation.rhs, operand_type)?;
+        self.expect_same_operands(lhs.type_id, rhs.type_id)?;
+
+        let type_id = self.resolve_operation_type(&operation.op, lhs.type_id, operation.span)?;
+        Ok(TypedExpr::new(
+            TypedExprKind::Operation {
+                op: operation.op,
+                lhs: Box::new(lhs),
+                rhs: Box::new(rhs),
+            },
+            type_id,
+        ))
+    }
+
+    /// Checks that a place can be assigned to: the name it starts with and
+    /// every field on the way must be mutable. A local declared without a
+    /// value can be `initialized` without being mutable, which is checked to
+    /// happen once with the rest of its initialization.
+    pub(super) fn check_mutable(
+        &mut self,
+        place: &TypedExpr,
+        initializes: bool,
+    ) -> Result<(), RuntimeError> {
+        let err = match &place.kind {
+            TypedExprKind::Path { ident, binding } => {
+                let declaration = self.find_declaration(ident).cloned().unwrap_or_default();
+                let local = matches!(binding, Binding::Local { .. });
+                if declaration.mutable || (declaration.deferred && initializes && local) {
+                    return Ok(());
+                }
+                ErrorKind::AssignToImmutable {
+                    name: ident.clone(),
+                    declared_at: declaration.span,
+                }
+            }
+            TypedExprKind::Field { value, offset } => {
+                self.check_mutable(value, false)?;
+                let type_id = self.prune(value.type_id);
+                let value_type = self
+                    .type_registry
+                    .get_resolved_type_from_id(type_id)
+                    .ok_or(ErrorKind::Internal(format!(
+                        "Type with id {type_id} not found."
+                    )))?;
+                let field = value_type
+                    .as_struct()
+                    .and_then(|fields| fields.get(*offset))
+                    .ok_or(ErrorKind::Internal("Expected struct field.".to_string()))?;
+                if field.mutable {
+                    return Ok(());
+                }
+                ErrorKind::AssignToImmutable {
+                    name: format!("field {} of {}", field.ident, value_type.ident),
+                    declared_at: Some(field.span.clone()),
+                }
+            }
+            TypedExprKind::Index { value, .. } => return self.check_mutable(value, false),
+            TypedExprKind::Deref(reference) => {
+                let type_id = self.prune(reference.type_id);
+                let ty = self
+                    .type_registry
+                    .get_resolved_type_from_id(type_id)
+                    .ok_or(ErrorKind::Internal(format!(
+                        "Type with id {type_id} not found."
+                    )))?;
+                if ty.as_reference().is_some_and(|(_, mutable)| mutable) {
+                    return Ok(());
+                }
+                ErrorKind::AssignThroughReference {
+                    ty: ty.ident.clone(),
+                }
+            }
+            _ => return Ok(()),
+        };
+        let err = RuntimeError::from(err);
+        Err(match &place.span {
+            Some(span) => err.with_span(span),
+            None => err,
+        })
+    }
+
+    /// Resolves the type of an operation from the type shared by its operands.
+    /// Operands whose type is not known yet must get a type the operation
+    /// works on once it is inferred.
+    fn resolve_operation_type(
+        &mut self,
+        op: &Operator,
+        operand_type_id: TypeId,
+        span: Span,
+    ) -> Result<TypeId, RuntimeError> {
+        let boolean = PrimitiveType::Boolean.type_id();
+        match op {
+            Operator::Arithmetic(_) => {
+                self.constrain(operand_type_id, ConstraintKind::Numeric, Some(span))?;
+                Ok(operand_type_id)
+            }
+            Operator::Comparison(ComparisonOperator::Equal | ComparisonOperator::NotEqual) => {
+                Ok(boolean)
+            }
+            Operator::Comparison(_) => {
+                self.constrain(operand_type_id, ConstraintKind::Ordered, Some(span))?;
+                Ok(boolean)
+            }
+            Operator::Boolean(_) => {
+                self.expect_type(operand_type_id, Some(boolean))?;
+                Ok(boolean)
+            }
+            Operator::Assignment => Ok(PrimitiveType::Unit.type_id()),
+            Operator::CompoundAssignment(_) => {
+                self.constrain(operand_type_id, ConstraintKind::Numeric, Some(span))?;
+                Ok(PrimitiveType::Unit.type_id())
+            }
+        }
+    }
+}
+
+/// Checks if an expression can be assigned to, like a path or an index.
+pub(super) fn is_place(expr: &Expression) -> bool {
+    match expr {
+        Expression::Path(_) | Expression::Deref(_) => true,
+        Expression::Index(index) => !matches!(*index.index, Expression::Range(_)),
+        Expression::Field(field) => is_place(&field.value),
+        _ => false,
+    }
+}
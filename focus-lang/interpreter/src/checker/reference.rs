@@ -0,0 +1,85 @@
+use parser::stmt::{Deref, Expression, Reference};
+
+use super::{operation::is_place, TypeChecker};
+use crate::{
+    error::{ErrorKind, RuntimeError},
+    r#type::TypeId,
+    typed::{TypedExpr, TypedExprKind},
+};
+
+impl TypeChecker<'_> {
+    /// Checks a reference. A `&mut` reference to a place can be assigned
+    /// through, so the place must be mutable. A reference to any other
+    /// expression refers to a copy of its value.
+    pub(super) fn check_reference(
+        &mut self,
+        reference: Reference,
+        expected_type: Option<TypeId>,
+    ) -> Result<TypedExpr, RuntimeError> {
+        let expected_value_type = expected_type
+            .map(|type_id| self.prune(type_id))
+            .and_then(|type_id| self.type_registry.get_resolved_type_from_id(type_id))
+            .and_then(|ty| ty.as_reference())
+            .map(|(type_id, _)| type_id);
+        let place = is_place(&reference.expr);
+        let value = self.check_expr(*reference.expr, expected_value_type)?;
+        if reference.mutable && place {
+            self.check_mutable(&value, false)?;
+        }
+
+        let type_id = self.reference_type(value.type_id, reference.mutable);
+        Ok(TypedExpr::new(
+            TypedExprKind::Reference(Box::new(value)),
+            type_id,
+        ))
+    }
+
+    /// Checks a dereference. The type of the reference must already be
+    /// known, like the type of a value whose fields are accessed.
+    pub(super) fn check_deref(&mut self, deref: Deref) -> Result<TypedExpr, RuntimeError> {
+        let subject = match &*deref.expr {
+            Expression::Path(path) => path.path.clone(),
+            _ => "the dereferenced value".to_string(),
+        };
+        let reference = self.check_expr(*deref.expr, None)?;
+        let type_id = self.prune(reference.type_id);
+        let ty = self
+            .type_registry
+            .get_resolved_type_from_id(type_id)
+            .ok_or(ErrorKind::Internal(format!(
+                "Type with id {type_id} not found."
+            )))?;
+        match ty.as_reference() {
+            Some((type_id, _)) => Ok(TypedExpr::new(
+                TypedExprKind::Deref(Box::new(reference)),
+                type_id,
+            )),
+            None if ty.is_variable() => Err(ErrorKind::TypeAnnotationRequired { subject }.into()),
+            None => Err(ErrorKind::NotAReference {
+                found: ty.ident.clone(),
+            }
+            .into()),
+        }
+    }
+
+    /// Dereferences a value as long as it is a reference, so fields and
+    /// elements can be accessed through references.
+    pub(super) fn auto_deref(&mut self, mut value: TypedExpr) -> TypedExpr {
+        loop {
+            let type_id = self.prune(value.type_id);
+            let Some((type_id, _)) = self
+                .type_registry
+                .get_resolved_type_from_id(type_id)
+                .and_then(|ty| ty.as_reference())
+            else {
+                return value;
+            };
+            let span = value.span.clone();
+            value = TypedExpr {
+                kind: TypedExprKind::Deref(Box::new(value)),
+                type_id,
+                span,
+            };
+        }
+    }
+}
@@ -0,0 +1,309 @@
+use std::rc::Rc;
+
+use parser::stmt::{Arg, Expression, Item, ItemStmt, LetStmt, Span, Stmt};
+
+use super::{
+    expr::{unit_arg, unsupported},
+    Declaration, TypeChecker,
+};
+use crate::{
+    error::{ErrorKind, RuntimeError},
+    r#type::{FunctionType, PrimitiveType, Type, TypeId},
+    typed::{TypedExpr, TypedExprKind, TypedItem, TypedLet, TypedStmt},
+};
+
+/// A function declared with `let` whose body is still to be checked.
+struct FunctionLet {
+    ident: String,
+    mutable: bool,
+    span: Span,
+    args: Vec<Arg>,
+    type_id: TypeId,
+    arg_types: Vec<TypeId>,
+    ret_type: TypeId,
+    /// The block of the body.
+    body: Expression,
+}
+
+impl TypeChecker<'_> {
+    pub fn check_stmt(&mut self, stmt: Stmt) -> Result<TypedStmt, RuntimeError> {
+        match stmt {
+            Stmt::Item(item) => self.check_item(item).map(TypedStmt::Item),
+            Stmt::Let(l) => self.check_let(l).map(TypedStmt::Let),
+            Stmt::LetRec(bindings) => self.check_let_rec(bindings).map(TypedStmt::LetRec),
+            Stmt::Expr(expr) => self.check_expr(expr, None).map(TypedStmt::Expr),
+        }
+    }
+
+    /// Checks a let. Without a type annotation, the type of the name is
+    /// inferred from its expression, and from its uses for a function. Names
+    /// bound to a function or to another name are generic, so each use can
+    /// give them different types, unless they are mutable, as a value assigned
+    /// later might not be as generic.
+    fn check_let(&mut self, mut l: LetStmt) -> Result<TypedLet, RuntimeError> {
+        let annotation = self.resolve_annotation(l.ty.as_ref())?;
+        let is_function = self.is_function(&l.args, annotation.as_deref());
+
+        match l.expr.take().map(|b| *b) {
+            Some(Expression::Block(mut b, span)) if !is_function => {
+                let expr = match (b.pop(), b.is_empty()) {
+                    (Some(Stmt::Expr(expr)), true) => expr,
+                    (Some(stmt), _) => {
+                        b.push(stmt);
+                        Expression::Block(b, span)
+                    }
+                    (None, _) => unreachable!("Empty block of statements."),
+                };
+                let expr = self.check_expr(expr, annotation.map(|ty| ty.type_id))?;
+                let type_id = match expr.kind {
+                    TypedExprKind::Closure { .. } | TypedExprKind::Path { .. } if !l.mutable => {
+                        self.generalize(expr.type_id)?
+                    }
+                    _ => expr.type_id,
+                };
+                let slot = self.declare(&l.ident, type_id, declaration(&l, false));
+                Ok(TypedLet {
+                    ident: l.ident,
+                    slot,
+                    type_id,
+                    mutable: l.mutable,
+                    span: l.span,
+                    expr: Some(expr),
+                })
+            }
+            Some(Expression::Block(b, span)) if !b.is_empty() => {
+                let function = self.function_let(l, annotation, Expression::Block(b, span))?;
+                let mut lets = self.check_function_lets(vec![function])?;
+                Ok(lets.remove(0))
+            }
+            Some(_) => Err(ErrorKind::Internal("Invalid let declaration.".to_string()).into()),
+            None => {
+                let ty = annotation.ok_or(ErrorKind::TypeAnnotationRequired {
+                    subject: format!("uninitialized name {}", l.ident),
+                })?;
+
+                let slot = self.declare(&l.ident, ty.type_id, declaration(&l, true));
+                Ok(TypedLet {
+                    ident: l.ident,
+                    slot,
+                    type_id: ty.type_id,
+                    mutable: l.mutable,
+                    span: l.span,
+                    expr: None,
+                })
+            }
+        }
+    }
+
+    /// Checks the functions of a `let rec`, which can all call each other.
+    fn check_let_rec(&mut self, bindings: Vec<LetStmt>) -> Result<Vec<TypedLet>, RuntimeError> {
+        let mut functions: Vec<FunctionLet> = Vec::with_capacity(bindings.len());
+        for mut l in bindings {
+            if functions.iter().any(|function| function.ident == l.ident) {
+                return Err(ErrorKind::DuplicateName { name: l.ident }.into());
+            }
+            let annotation = self.resolve_annotation(l.ty.as_ref())?;
+            match l.expr.take().map(|b| *b) {
+                Some(body @ Expression::Block(..))
+                    if self.is_function(&l.args, annotation.as_deref()) =>
+                {
+                    functions.push(self.function_let(l, annotation, body)?);
+                }
+                _ => return Err(ErrorKind::RecursiveValue { name: l.ident }.into()),
+            }
+        }
+        self.check_function_lets(functions)
+    }
+
+    fn resolve_annotation(
+        &mut self,
+        ty: Option<&parser::Type>,
+    ) -> Result<Option<Rc<Type>>, RuntimeError> {
+        ty.map(|ty| self.resolve_type(ty)).transpose()
+    }
+
+    /// Checks if a let declares a function, either with arguments or with a
+    /// function type annotation.
+    fn is_function(&self, args: &[Arg], annotation: Option<&Type>) -> bool {
+        !args.is_empty()
+            || annotation
+                .is_some_and(|ty| self.type_registry.resolve_type(ty).as_function().is_some())
+    }
+
+    /// Gets the type of a function declared with `let` from its annotation,
+    /// or gives it type variables to infer it from its body and uses.
+    fn function_let(
+        &mut self,
+        l: LetStmt,
+        annotation: Option<Rc<Type>>,
+        body: Expression,
+    ) -> Result<FunctionLet, RuntimeError> {
+        let (type_id, arg_types, ret_type) = match annotation {
+            Some(ty) => {
+                let resolved = self.type_registry.resolve_type(&ty);
+                let Some(FunctionType {
+                    arg_types,
+                    ret_type,
+                }) = resolved.as_function()
+                else {
+                    return Err(ErrorKind::MismatchedTypes {
+                        expected: "a function type".to_string(),
+                        found: ty.ident.clone(),
+                    }
+                    .into());
+                };
+                (ty.type_id, arg_types.clone(), *ret_type)
+            }
+            None => {
+                let arg_types = l
+                    .args
+                    .iter()
+                    .map(|arg| self.arg_type(&arg.ident))
+                    .collect::<Vec<_>>();
+                let ret_type = self.fresh_type_variable();
+                let type_id = self
+                    .type_registry
+                    .insert_or_get_function_type(arg_types.clone(), ret_type);
+                (type_id, arg_types, ret_type)
+            }
+        };
+
+        let args = if l.args.is_empty() && arg_types[0] == PrimitiveType::Unit.type_id() {
+            vec![unit_arg()]
+        } else {
+            l.args
+        };
+
+        if args.len() != arg_types.len() {
+            return Err(ErrorKind::ArgumentCount {
+                expected: arg_types.len(),
+                found: args.len(),
+            }
+            .into());
+        }
+
+        Ok(FunctionLet {
+            ident: l.ident,
+            mutable: l.mutable,
+            span: l.span,
+            args,
+            type_id,
+            arg_types,
+            ret_type,
+            body,
+        })
+    }
+
+    /// Checks the bodies of functions declared together. They are all in
+    /// scope in every body, so they can call themselves and each other, but
+    /// only at the types they are being inferred to have. The ones that are
+    /// not mutable are generic once all of them are checked.
+    fn check_function_lets(
+        &mut self,
+        functions: Vec<FunctionLet>,
+    ) -> Result<Vec<TypedLet>, RuntimeError> {
+        for function in &functions {
+            let declaration = Declaration {
+                mutable: function.mutable,
+                deferred: false,
+                span: Some(function.span.clone()),
+            };
+            self.declare(&function.ident, function.type_id, declaration);
+        }
+
+        let mut checked = Vec::with_capacity(functions.len());
+        for function in functions {
+            // The closure spans from the name of the function to the end of
+            // its body.
+            let span = function.span.start..function.body.span().end;
+            let closure = self.check_function_body(
+                function.args,
+                &function.arg_types,
+                function.body,
+                function.ret_type,
+            )?;
+            checked.push(TypedLet {
+                ident: function.ident,
+                slot: 0,
+                type_id: function.type_id,
+                mutable: function.mutable,
+                span: function.span,
+                expr: Some(TypedExpr {
+                    span: Some(span),
+                    ..TypedExpr::new(closure, function.type_id)
+                }),
+            });
+        }
+
+        // The mutable functions are declared first, so the type variables
+        // they share with the others stay free.
+        for l in &mut checked {
+            l.slot = self.undeclare(&l.ident);
+        }
+        for l in checked.iter().filter(|l| l.mutable) {
+            self.declare_in_slot(&l.ident, l.type_id, l.slot);
+        }
+        let type_ids = checked
+            .iter()
+            .filter(|l| !l.mutable)
+            .map(|l| l.type_id)
+            .collect::<Vec<_>>();
+        let mut schemes = self.generalize_group(&type_ids)?.into_iter();
+        for l in checked.iter_mut().filter(|l| !l.mutable) {
+            let scheme = schemes.next().expect("A scheme for every function.");
+            self.declare_in_slot(&l.ident, scheme, l.slot);
+            l.type_id = scheme;
+        }
+        Ok(checked)
+    }
+
+    /// Registers the type declared by an item, so it can be used by the
+    /// statements that follow it.
+    fn check_item(&mut self, item: ItemStmt) -> Result<TypedItem, RuntimeError> {
+        let declared = match item.item {
+            Item::Alias(alias) => {
+                let ident = alias.ident.clone();
+                let path = alias.path.clone();
+                self.type_registry
+                    .insert_alias_type_from_item(alias)
+                    .map(|type_id| (ident, type_id))
+                    .ok_or_else(|| match &path {
+                        Some(path) => self.unknown_type_name_error(&path.path, &path.span),
+                        None => ErrorKind::UnknownType {
+                            name: "()".to_string(),
+                        }
+                        .into(),
+                    })
+            }
+            Item::Struct(struc) => {
+                let ident = struc.ident.clone();
+                let unknown = struc
+                    .fields
+                    .iter()
+                    .find(|field| self.type_registry.get_type_from_expr(&field.ty).is_none())
+                    .map(|field| self.unknown_type_error(&field.ty));
+                self.type_registry
+                    .insert_struct_type_from_item(struc)
+                    .map(|type_id| (ident, type_id))
+                    .ok_or_else(|| unknown.expect("A field has a type that is not found."))
+            }
+            Item::ModuleDeclaration(_) => Err(unsupported("Module declarations").into()),
+            Item::UseDeclaration(_) => Err(unsupported("Use declarations").into()),
+        };
+        let (ident, type_id) = declared.map_err(|err| err.with_span(&item.span))?;
+        Ok(TypedItem {
+            ident,
+            type_id,
+            span: item.span,
+        })
+    }
+}
+
+/// How a let declares its name, `deferred` if it has no value.
+fn declaration(l: &LetStmt, deferred: bool) -> Declaration {
+    Declaration {
+        mutable: l.mutable,
+        deferred,
+        span: Some(l.span.clone()),
+    }
+}
@@ -0,0 +1,83 @@
+use std::collections::HashMap;
+
+use parser::stmt::Struct;
+
+use super::{suggest, TypeChecker};
+use crate::{
+    error::{ErrorKind, RuntimeError},
+    r#type::{Type, TypeLayout},
+    typed::{TypedExpr, TypedExprKind},
+};
+
+impl TypeChecker<'_> {
+    pub(super) fn check_struct(&mut self, r#struct: Struct) -> Result<TypedExpr, RuntimeError> {
+        let ty = self.type_registry.get_type_from_name(&r#struct.path);
+
+        let Some(Type {
+            type_id,
+            layout: TypeLayout::Struct(fields),
+            ..
+        }) = ty.as_deref()
+        else {
+            return Err(self.struct_type_error(&r#struct));
+        };
+
+        // Unknown fields are reported before missing ones, as they are often
+        // a typo of one of the missing fields.
+        let unknown_field = r#struct
+            .fields
+            .iter()
+            .filter(|field| !fields.iter().any(|f| f.ident == field.ident))
+            .min_by(|a, b| a.ident.cmp(&b.ident));
+        if let Some(field) = unknown_field {
+            let missing_fields = fields
+                .iter()
+                .filter(|f| !r#struct.fields.iter().any(|field| field.ident == f.ident))
+                .map(|f| f.ident.as_str());
+            return Err(RuntimeError::from(ErrorKind::UnknownField {
+                ty: r#struct.path.clone(),
+                field: field.ident.clone(),
+            })
+            .with_suggestion(suggest(
+                &field.ident,
+                field.span.start,
+                missing_fields,
+                "a field with a similar name is missing",
+            )));
+        }
+
+        let mut field_values = r#struct
+            .fields
+            .into_iter()
+            .map(|m| (m.ident, m.expr))
+            .collect::<HashMap<_, _>>();
+
+        let mut values = Vec::with_capacity(fields.len());
+        for f in fields.iter() {
+            let expr = field_values
+                .remove(f.ident.as_str())
+                .ok_or(ErrorKind::MissingField {
+                    ty: r#struct.path.clone(),
+                    field: f.ident.clone(),
+                })?;
+            values.push(self.check_expr(*expr, Some(f.type_id))?);
+        }
+
+        Ok(TypedExpr::new(TypedExprKind::Struct(values), *type_id))
+    }
+
+    /// The error for a struct literal whose name is not a struct type.
+    fn struct_type_error(&self, r#struct: &Struct) -> RuntimeError {
+        let name = r#struct.path.clone();
+        if self.type_registry.get_type_id_from_name(&name).is_some() {
+            return ErrorKind::NotAStruct { name }.into();
+        }
+        let suggestion = suggest(
+            &name,
+            r#struct.span.start,
+            self.type_registry.struct_names(),
+            "a struct with a similar name exists",
+        );
+        RuntimeError::from(ErrorKind::UnknownType { name }).with_suggestion(suggestion)
+    }
+}
@@ -0,0 +1,536 @@
+use parser::{stmt::Span, IntegerKind};
+
+use crate::{
+    object::{Unpacked, Value},
+    typed::{Binding, Capture, TypedExpr, TypedExprKind, TypedStmt},
+    ErrorKind, Interpreter, InterpreterError,
+};
+
+fn check_error(interpreter: &mut Interpreter, code: &str) -> ErrorKind {
+    match interpreter.interpret_str(code) {
+        Err(InterpreterError::TypeError(err)) => err.kind,
+        other => panic!("Expected a type error, found {other:?}"),
+    }
+}
+
+#[test]
+fn untaken_branch() {
+    let mut interpreter = Interpreter::new();
+    assert_eq!(
+        check_error(&mut interpreter, "if false then 1 + true else 2"),
+        ErrorKind::MismatchedOperands {
+            lhs: "int".to_string(),
+            rhs: "bool".to_string()
+        }
+    );
+    assert_eq!(
+        check_error(&mut interpreter, "if true then 1 else 'a'"),
+        ErrorKind::MismatchedTypes {
+            expected: "int".to_string(),
+            found: "char".to_string()
+        }
+    );
+}
+
+#[test]
+fn function_body_checked_at_definition() {
+    let mut interpreter = Interpreter::new();
+    assert_eq!(
+        check_error(&mut interpreter, "let f x: (int -> bool) = x + 1"),
+        ErrorKind::MismatchedTypes {
+            expected: "bool".to_string(),
+            found: "int".to_string()
+        }
+    );
+    assert_eq!(
+        check_error(
+            &mut interpreter,
+            "let g h: ((int -> int) -> int) = h 1\ng (fn a b -> a)"
+        ),
+        ErrorKind::ArgumentCount {
+            expected: 1,
+            found: 2
+        }
+    );
+}
+
+#[test]
+fn nothing_runs_before_a_type_error() {
+    let mut interpreter = Interpreter::new();
+    assert_eq!(
+        check_error(&mut interpreter, "let a = 1\nlet b: bool = a"),
+        ErrorKind::MismatchedTypes {
+            expected: "bool".to_string(),
+            found: "int".to_string()
+        }
+    );
+    assert_eq!(
+        check_error(&mut interpreter, "a"),
+        ErrorKind::UnknownName {
+            name: "a".to_string()
+        }
+    );
+}
+
+#[test]
+fn empty_arrays() {
+    let mut interpreter = Interpreter::new();
+    interpreter
+        .interpret_str("let a: [int] = []")
+        .expect("Unable to interpret.");
+    let err = interpreter
+        .interpret_str("a[0]")
+        .expect_err("Index should be out of bounds.");
+    assert!(matches!(
+        err,
+        InterpreterError::RuntimeError(err) if err.kind == ErrorKind::IndexOutOfBounds { index: 0, len: 0 }
+    ));
+    assert_eq!(
+        check_error(&mut interpreter, "let b = []"),
+        ErrorKind::TypeAnnotationRequired {
+            subject: "b".to_string()
+        }
+    );
+    let value = interpreter
+        .interpret_str("let mut c = []\nc = [1, 2]\nc[1]")
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(2));
+}
+
+#[test]
+fn recursion() {
+    let mut interpreter = Interpreter::new();
+    let value = interpreter
+        .interpret_str(
+            r#"
+    let fact n =
+        if n < 2 then 1 else
+            let rest = fact (n - 1)
+            n * rest
+    fact 5
+    "#,
+        )
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(120));
+}
+
+#[test]
+fn generic_functions() {
+    let mut interpreter = Interpreter::new();
+    let value = interpreter
+        .interpret_str(
+            r#"
+    let id x = x
+    let apply f x = f x
+    let a = apply id 1
+    let b = id true
+    if b then a else 0
+    "#,
+        )
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(1));
+}
+
+#[test]
+fn inferred_from_uses() {
+    let mut interpreter = Interpreter::new();
+    let value = interpreter
+        .interpret_str(
+            r#"
+    let twice = fn f x -> f (f x)
+    let inc = fn x -> x + 1u8
+    twice inc 3u8
+    "#,
+        )
+        .expect("Unable to interpret.");
+    assert_eq!(
+        value,
+        Value::from(Unpacked::SizedInteger(5, IntegerKind::U8))
+    );
+
+    // Generic functions only work on numbers of type int.
+    let value = interpreter
+        .interpret_str("let sub x y = x - y\nsub 3 1")
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(2));
+    assert_eq!(
+        check_error(&mut interpreter, "sub 3u8 1u8"),
+        ErrorKind::MismatchedTypes {
+            expected: "int".to_string(),
+            found: "u8".to_string()
+        }
+    );
+}
+
+#[test]
+fn inference_errors() {
+    let mut interpreter = Interpreter::new();
+    assert!(matches!(
+        check_error(&mut interpreter, "let f x = x x"),
+        ErrorKind::InfiniteType { .. }
+    ));
+    assert_eq!(
+        check_error(
+            &mut interpreter,
+            "let id x = x\nlet a = id 1\nlet b = id true\na + b"
+        ),
+        ErrorKind::MismatchedOperands {
+            lhs: "int".to_string(),
+            rhs: "bool".to_string()
+        }
+    );
+    assert_eq!(
+        check_error(&mut interpreter, "let neg x = 0 - x\nneg true"),
+        ErrorKind::MismatchedTypes {
+            expected: "int".to_string(),
+            found: "bool".to_string()
+        }
+    );
+}
+
+#[test]
+fn mutable_bindings_are_not_generic() {
+    let mut interpreter = Interpreter::new();
+    let mismatched = ErrorKind::MismatchedTypes {
+        expected: "int".to_string(),
+        found: "bool".to_string(),
+    };
+    assert_eq!(
+        check_error(
+            &mut interpreter,
+            "let mut f = fn x -> x\nf = fn x -> x + 1\nf true"
+        ),
+        mismatched
+    );
+    assert_eq!(
+        check_error(
+            &mut interpreter,
+            "let rec mut g x = x\ng = fn x -> x + 1\ng true"
+        ),
+        mismatched
+    );
+
+    // A mutable name still has the type inferred from its uses.
+    let value = interpreter
+        .interpret_str("let mut h = fn x -> x\nh = fn x -> x + 1\nh 1")
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(2));
+}
+
+#[test]
+fn unsupported_constructs() {
+    let mut interpreter = Interpreter::new();
+    let cases = [
+        ("let s = \"text\"", "String literals", 8..14),
+        ("let r = 1..3", "Ranges outside of an index", 8..12),
+        (
+            "let a = 1\nlet b = match a\n    | 1 -> 2",
+            "Match expressions",
+            18..38,
+        ),
+        ("let c = for i in [1] do i", "For loops", 8..25),
+        ("module m", "Module declarations", 0..8),
+        ("use Std.U8", "Use declarations", 0..10),
+    ];
+    for (code, construct, span) in cases {
+        let Err(InterpreterError::TypeError(err)) = interpreter.interpret_str(code) else {
+            panic!("Expected {construct} to be rejected.");
+        };
+        assert_eq!(
+            err.kind,
+            ErrorKind::Unsupported {
+                construct: construct.to_string()
+            }
+        );
+        assert_eq!(err.code(), "E0215");
+        assert_eq!(err.span, Some(span), "{construct}");
+    }
+}
+
+#[test]
+fn lowered_bindings() {
+    let mut interpreter = Interpreter::new();
+    let stmts = interpreter
+        .lower_str(
+            r#"
+    type Point = {x: int, y: int}
+    let p = Point {x: 1, y: 2}
+    let f a = a + p.y
+    Std.U8.wrapping_add 1u8 2u8
+    "#,
+        )
+        .expect("Unable to lower.");
+
+    let [TypedStmt::Item(point), TypedStmt::Let(p), TypedStmt::Let(f), TypedStmt::Expr(call)] =
+        stmts.as_slice()
+    else {
+        panic!("Unexpected statements {stmts:?}");
+    };
+    assert_eq!((point.ident.as_str(), point.span.clone()), ("Point", 5..34));
+    assert_eq!(interpreter.type_name(point.type_id), "Point");
+    assert_eq!((p.ident.as_str(), p.slot), ("p", 0));
+    assert_eq!((f.ident.as_str(), f.slot), ("f", 1));
+    assert_eq!(interpreter.type_name(f.type_id), "( int -> int )");
+
+    let Some(TypedExpr {
+        kind: TypedExprKind::Closure { captures, body, .. },
+        span,
+        ..
+    }) = &f.expr
+    else {
+        panic!("Expected a closure, found {:?}", f.expr);
+    };
+    assert_eq!(span, &Some(74..87));
+    let [Capture {
+        ident,
+        binding: Binding::Local { depth: 0, slot: 0 },
+        ..
+    }] = captures.as_slice()
+    else {
+        panic!("Expected f to capture p, found {captures:?}");
+    };
+    assert_eq!(ident, "p");
+    let TypedExprKind::Block(body) = &body.kind else {
+        panic!("Expected a block, found {body:?}");
+    };
+    let [TypedStmt::Expr(TypedExpr {
+        kind: TypedExprKind::Operation { lhs, rhs, .. },
+        ..
+    })] = body.as_slice()
+    else {
+        panic!("Expected an operation, found {body:?}");
+    };
+    assert!(matches!(
+        &lhs.kind,
+        TypedExprKind::Path { ident, binding: Binding::Local { depth: 1, slot: 0 } } if ident == "a"
+    ));
+    let TypedExprKind::Field { value, offset: 1 } = &rhs.kind else {
+        panic!("Expected the field y, found {rhs:?}");
+    };
+    assert_eq!(rhs.span, Some(84..87));
+    assert!(matches!(
+        value.kind,
+        TypedExprKind::Path {
+            binding: Binding::Capture { index: 0 },
+            ..
+        }
+    ));
+
+    let TypedExprKind::Call {
+        binding: Binding::ModuleItem(path),
+        params,
+        ..
+    } = &call.kind
+    else {
+        panic!("Expected a call, found {call:?}");
+    };
+    assert_eq!(path, "Std.U8.wrapping_add");
+    assert_eq!(params[0].span, Some(112..115));
+    assert_eq!(interpreter.type_name(call.type_id), "u8");
+}
+
+#[test]
+fn mutual_recursion() {
+    let mut interpreter = Interpreter::new();
+    let value = interpreter
+        .interpret_str(
+            r#"
+    let rec even n = if n == 0 then true else odd (n - 1)
+    and odd n = if n == 0 then false else even (n - 1)
+    even 10
+    "#,
+        )
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(true));
+
+    let value = interpreter
+        .interpret_str(
+            r#"
+    let parity n =
+        let rec even k = if k == 0 then 0 else odd (k - 1)
+        and odd k = if k == 0 then 1 else even (k - 1)
+        even n
+    parity 7
+    "#,
+        )
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(1));
+
+    assert_eq!(
+        check_error(
+            &mut interpreter,
+            "let ping n = if n == 0 then 0 else pong (n - 1)\nlet pong n = ping n"
+        ),
+        ErrorKind::UnknownName {
+            name: "pong".to_string()
+        }
+    );
+    assert_eq!(
+        check_error(&mut interpreter, "let rec a = 1"),
+        ErrorKind::RecursiveValue {
+            name: "a".to_string()
+        }
+    );
+    assert_eq!(
+        check_error(&mut interpreter, "let rec f x = x\nand f y = y"),
+        ErrorKind::DuplicateName {
+            name: "f".to_string()
+        }
+    );
+}
+
+#[test]
+fn definite_initialization() {
+    let mut interpreter = Interpreter::new();
+    let value = interpreter
+        .interpret_str(
+            r#"
+    let pick b =
+        let a: int
+        if b then a = 1 else a = 2
+        a
+    let c: int
+    let picked = pick false
+    c = picked
+    c + 1
+    "#,
+        )
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(3));
+
+    let uninitialized = |name: &str| ErrorKind::PossiblyUninitialized {
+        name: name.to_string(),
+    };
+    assert_eq!(
+        check_error(&mut interpreter, "let d: int\nif true then d = 1\nd"),
+        uninitialized("d")
+    );
+    assert_eq!(
+        check_error(&mut interpreter, "let e: int\ne = e + 1"),
+        uninitialized("e")
+    );
+    assert_eq!(
+        check_error(&mut interpreter, "let f: int\nlet g () = f"),
+        uninitialized("f")
+    );
+
+    // Names of the top level stay uninitialized until a later run assigns them.
+    interpreter
+        .interpret_str("let h: int")
+        .expect("Unable to interpret.");
+    assert_eq!(check_error(&mut interpreter, "h"), uninitialized("h"));
+    let value = interpreter
+        .interpret_str("h = 4\nh")
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(4));
+    let value = interpreter
+        .interpret_str("h")
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(4));
+}
+
+#[test]
+fn immutable_bindings() {
+    let mut interpreter = Interpreter::new();
+    let immutable = |name: &str, declared_at: Option<Span>| ErrorKind::AssignToImmutable {
+        name: name.to_string(),
+        declared_at,
+    };
+    let Err(InterpreterError::TypeError(err)) = interpreter.interpret_str("let a = 1\na = 2")
+    else {
+        panic!("Expected a type error.");
+    };
+    assert_eq!(err.kind, immutable("a", Some(4..5)));
+    assert_eq!(err.span, Some(10..11));
+
+    assert_eq!(
+        check_error(&mut interpreter, "let b: int\nb = 1\nb = 2"),
+        immutable("b", Some(4..5))
+    );
+    assert_eq!(
+        check_error(&mut interpreter, "let c: int\nif true then c = 1\nc = 2"),
+        immutable("c", Some(4..5))
+    );
+    assert_eq!(
+        check_error(&mut interpreter, "let d: int\nd = 1\nd += 1"),
+        immutable("d", Some(4..5))
+    );
+    assert_eq!(
+        check_error(&mut interpreter, "let f n =\n    n += 1\n    n"),
+        immutable("n", Some(6..7))
+    );
+
+    // Fields need `mut` too, as does the name they are assigned through.
+    interpreter
+        .interpret_str("type Point = {mut x: int, y: int}")
+        .expect("Unable to interpret.");
+    assert_eq!(
+        check_error(&mut interpreter, "let mut p = Point {x: 1, y: 2}\np.y = 3"),
+        immutable("field y of Point", Some(26..27))
+    );
+    assert_eq!(
+        check_error(&mut interpreter, "let q = Point {x: 1, y: 2}\nq.x = 3"),
+        immutable("q", Some(4..5))
+    );
+    let value = interpreter
+        .interpret_str(
+            r#"
+    let mut p = Point {x: 1, y: 2}
+    p.x = 3
+    let add mut n m =
+        n += m
+        n
+    let r = add p.x 4
+    r + p.x
+    "#,
+        )
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(10));
+
+    // Names of the top level keep how they are declared in later runs.
+    let value = interpreter
+        .interpret_str("p.x = 5\np.x")
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(5));
+    assert_eq!(check_error(&mut interpreter, "r = 1"), immutable("r", None));
+}
+
+#[test]
+fn reference_errors() {
+    let mut interpreter = Interpreter::new();
+    assert_eq!(
+        check_error(&mut interpreter, "let a = 1\n*a"),
+        ErrorKind::NotAReference {
+            found: "int".to_string()
+        }
+    );
+    assert_eq!(
+        check_error(&mut interpreter, "let f r = *r"),
+        ErrorKind::TypeAnnotationRequired {
+            subject: "r".to_string()
+        }
+    );
+    assert_eq!(
+        check_error(&mut interpreter, "let mut b = 1\nlet r = &b\n*r = 2"),
+        ErrorKind::AssignThroughReference {
+            ty: "&int".to_string()
+        }
+    );
+    assert_eq!(
+        check_error(&mut interpreter, "let c = 1\nlet r = &mut c"),
+        ErrorKind::AssignToImmutable {
+            name: "c".to_string(),
+            declared_at: Some(4..5)
+        }
+    );
+    assert_eq!(
+        check_error(
+            &mut interpreter,
+            "let mut d = 1\nlet set r: (&mut int -> ()) = *r = 2\nset &d"
+        ),
+        ErrorKind::MismatchedTypes {
+            expected: "&mut int".to_string(),
+            found: "&int".to_string()
+        }
+    );
+}
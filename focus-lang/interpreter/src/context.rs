@@ -1,75 +1,44 @@
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::rc::Rc;
 
 use crate::{module::Module, r#type::TypeId};
 
+/// A name declared at the top level. Its value is on the stack in the slot
+/// the checker gave it, as the top level has no frame.
 #[derive(Clone, Debug)]
 pub struct Local {
+    pub ident: String,
     pub ty: TypeId,
-    pub sp: Option<usize>,
+    /// Whether the name is declared with `mut`, which code checked later
+    /// needs to know.
+    pub mutable: bool,
 }
 
+/// The code being run: the top level or the body of a function. Names are
+/// resolved to slots by the checker, so the context only needs to know where
+/// the variables a function captured are. They follow its arguments in the
+/// frame of the call.
 #[derive(Clone)]
 pub struct Context {
-    locals: HashMap<String, Local>,
-    parent: Option<Rc<RefCell<Context>>>,
     module: Rc<Module>,
+    captures_sp: usize,
 }
 
 impl Context {
-    pub fn new(module: Rc<Module>) -> Self {
+    pub fn new(module: Rc<Module>, captures_sp: usize) -> Self {
         Self {
-            locals: HashMap::default(),
-            parent: None,
             module,
+            captures_sp,
         }
     }
 
-    pub fn with_parent(mut self, parent: Rc<RefCell<Context>>) -> Self {
-        self.parent = Some(parent);
-        self
-    }
-
-    pub fn take_parent(&mut self) -> Option<Rc<RefCell<Context>>> {
-        std::mem::take(&mut self.parent)
-    }
-
-    pub fn parent(&self) -> Option<Rc<RefCell<Context>>> {
-        self.parent.clone()
-    }
-
     pub fn module(&self) -> Rc<Module> {
         self.module.clone()
     }
 
-    pub fn add_local(&mut self, ident: &str, local: Local) {
-        self.locals.insert(ident.to_string(), local);
-    }
-
-    pub fn get_local(&self, ident: &str) -> Option<Local> {
-        self.locals.get(ident).cloned()
-    }
-
-    pub fn find_local(&self, ident: &str) -> Option<Local> {
-        self.locals.get(ident).cloned().or_else(|| {
-            self.parent
-                .as_ref()
-                .map(|p| p.borrow().get_local(ident))
-                .flatten()
-        })
-    }
-
-    pub fn is_in_parent(&self, ident: &str) -> bool {
-        self.parent
-            .as_ref()
-            .is_some_and(|p| p.borrow().is_local(ident))
-    }
-
+    /// The position on the stack of the `index`th variable the function
+    /// being run captured.
     #[inline]
-    pub fn is_local(&self, ident: &str) -> bool {
-        self.get_local(ident).is_some()
-    }
-
-    pub fn capture(&self) -> Self {
-        self.clone()
+    pub fn capture_sp(&self, index: usize) -> usize {
+        self.captures_sp + index
     }
 }
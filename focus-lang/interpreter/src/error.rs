@@ -0,0 +1,733 @@
+use std::{error::Error, fmt::Display, time::Duration};
+
+use diagnostic::{Diagnostic, Suggestion};
+use parser::{op::ArithmeticOperator, stmt::Span, ParserError};
+
+pub enum InterpreterError {
+    ParserError(ParserError),
+    /// The type checker rejected the code, so none of it ran.
+    TypeError(RuntimeError),
+    /// The code failed while it ran.
+    RuntimeError(RuntimeError),
+}
+
+impl From<ParserError> for InterpreterError {
+    fn from(value: ParserError) -> Self {
+        InterpreterError::ParserError(value)
+    }
+}
+
+impl From<RuntimeError> for InterpreterError {
+    fn from(value: RuntimeError) -> Self {
+        InterpreterError::RuntimeError(value)
+    }
+}
+
+impl InterpreterError {
+    pub fn diagnostic(&self) -> Diagnostic {
+        match self {
+            InterpreterError::ParserError(err) => err.diagnostic(),
+            InterpreterError::TypeError(err) | InterpreterError::RuntimeError(err) => {
+                err.diagnostic()
+            }
+        }
+    }
+}
+
+impl Display for InterpreterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            InterpreterError::ParserError(err) => err.fmt(f),
+            InterpreterError::TypeError(err) | InterpreterError::RuntimeError(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::fmt::Debug for InterpreterError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ParserError(err) => f.debug_tuple("ParserError").field(err).finish(),
+            Self::TypeError(err) => f.debug_tuple("TypeError").field(err).finish(),
+            Self::RuntimeError(err) => f.debug_tuple("RuntimeError").field(err).finish(),
+        }
+    }
+}
+
+impl Error for InterpreterError {}
+
+/// An error found while type checking or evaluating code.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RuntimeError {
+    pub kind: ErrorKind,
+    /// Span of the innermost operation, call, index or cast that failed.
+    pub span: Option<Span>,
+    /// Active calls when the error happened, the innermost one last. Only
+    /// the outermost and innermost ones of a deep stack are kept.
+    pub stack_trace: Box<[StackFrame]>,
+    /// Fix for a likely typo, e.g. in the name of a local or a field.
+    pub suggestion: Option<Box<Suggestion>>,
+}
+
+impl RuntimeError {
+    #[inline]
+    pub fn code(&self) -> &'static str {
+        self.kind.code()
+    }
+
+    /// Sets the span of the error unless a more precise one is already set.
+    pub fn with_span(mut self, span: &Span) -> Self {
+        if self.span.is_none() {
+            self.span = Some(span.clone());
+        }
+        self
+    }
+
+    pub fn with_suggestion(mut self, suggestion: Option<Suggestion>) -> Self {
+        self.suggestion = suggestion.map(Box::new);
+        self
+    }
+
+    /// The call sites of the stack trace become secondary labels.
+    pub fn diagnostic(&self) -> Diagnostic {
+        let mut diagnostic = Diagnostic::error(self.kind.to_string()).with_code(self.code());
+        if let Some(span) = &self.span {
+            diagnostic = diagnostic.with_primary(span.clone(), self.kind.label());
+        }
+        for frame in self.stack_trace.iter().rev() {
+            diagnostic = diagnostic.with_secondary(
+                frame.call_site.clone(),
+                format!("in this call to {}{}", frame.function, frame.repeats()),
+            );
+        }
+        let omitted_calls = self
+            .stack_trace
+            .iter()
+            .map(|frame| frame.omitted_calls)
+            .sum::<usize>();
+        if omitted_calls > 0 {
+            diagnostic = diagnostic.with_note(format!(
+                "{omitted_calls} more calls are left out of the stack trace"
+            ));
+        }
+        if let Some((span, label)) = self.kind.related() {
+            diagnostic = diagnostic.with_secondary(span, label);
+        }
+        if let Some(suggestion) = &self.suggestion {
+            diagnostic = diagnostic.with_suggestion(*suggestion.clone());
+        }
+        if let Some(help) = self.kind.help() {
+            diagnostic = diagnostic.with_help(help);
+        }
+        diagnostic
+    }
+}
+
+impl From<ErrorKind> for RuntimeError {
+    fn from(kind: ErrorKind) -> Self {
+        RuntimeError {
+            kind,
+            span: None,
+            stack_trace: Box::default(),
+            suggestion: None,
+        }
+    }
+}
+
+impl Display for RuntimeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "error[{}]: {}", self.code(), self.kind)?;
+        for frame in self.stack_trace.iter().rev() {
+            if frame.omitted_calls > 0 {
+                write!(f, "\n    ... {} more calls", frame.omitted_calls)?;
+            }
+            write!(f, "\n    in {} called at ", frame.function)?;
+            match frame.position {
+                Some((line, column)) => write!(f, "{line}:{column}")?,
+                None => write!(f, "{}..{}", frame.call_site.start, frame.call_site.end)?,
+            }
+            write!(f, "{}", frame.repeats())?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for RuntimeError {}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StackFrame {
+    /// Path of the called function as written at the call site.
+    pub function: String,
+    pub call_site: Span,
+    /// The line and the column of the call site, both counted from 1, if
+    /// the source of the call is known.
+    pub position: Option<(usize, usize)>,
+    /// How many more times the same call was made right inside this one, as
+    /// by a recursion, which the frame stands for too.
+    pub repeated: usize,
+    /// The number of calls made inside this one that are left out of the
+    /// stack trace, as it is too deep.
+    pub omitted_calls: usize,
+}
+
+impl StackFrame {
+    /// The note on how many more times the call was made, put after it.
+    fn repeats(&self) -> String {
+        match self.repeated {
+            0 => String::new(),
+            repeated => format!(", and {repeated} more times"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ErrorKind {
+    UnknownName {
+        name: String,
+    },
+    UnknownField {
+        ty: String,
+        field: String,
+    },
+    UnknownType {
+        name: String,
+    },
+    MissingField {
+        ty: String,
+        field: String,
+    },
+    NotAStruct {
+        name: String,
+    },
+    DuplicateName {
+        name: String,
+    },
+    PossiblyUninitialized {
+        name: String,
+    },
+    AssignToImmutable {
+        name: String,
+        /// Span of the name where it is declared, if it is in the code being
+        /// checked.
+        declared_at: Option<Span>,
+    },
+    MismatchedTypes {
+        expected: String,
+        found: String,
+    },
+    MismatchedOperands {
+        lhs: String,
+        rhs: String,
+    },
+    NotNumeric {
+        found: String,
+    },
+    NotOrdered {
+        found: String,
+    },
+    InvalidCast {
+        from: String,
+        to: String,
+    },
+    NotCallable {
+        name: String,
+        found: String,
+    },
+    ArgumentCount {
+        expected: usize,
+        found: usize,
+    },
+    NotIndexable {
+        found: String,
+    },
+    TypeAnnotationRequired {
+        subject: String,
+    },
+    InvalidAssignmentTarget {
+        expr: String,
+    },
+    InfiniteType {
+        var: String,
+        ty: String,
+    },
+    RecursiveValue {
+        name: String,
+    },
+    NotAReference {
+        found: String,
+    },
+    AssignThroughReference {
+        ty: String,
+    },
+    /// A construct the parser accepts but the interpreter cannot run yet.
+    Unsupported {
+        construct: String,
+    },
+    IntegerOverflow {
+        operator: ArithmeticOperator,
+        lhs: i128,
+        rhs: i128,
+    },
+    DivisionByZero,
+    IndexOutOfBounds {
+        index: i64,
+        len: usize,
+    },
+    NegativeIndex {
+        index: i64,
+        len: usize,
+    },
+    InvalidSlice {
+        from: usize,
+        to: usize,
+        len: usize,
+    },
+    ValueOutOfRange {
+        value: String,
+        ty: String,
+    },
+    CallDepthExceeded {
+        limit: usize,
+    },
+    FuelExhausted {
+        limit: u64,
+    },
+    TimeLimitExceeded {
+        limit: Duration,
+    },
+    HeapObjectsExceeded {
+        limit: usize,
+    },
+    HeapBytesExceeded {
+        limit: usize,
+    },
+    /// A broken invariant of the interpreter rather than of the program.
+    Internal(String),
+}
+
+impl ErrorKind {
+    /// A stable code for the kind of error. Syntax errors reported by the parser
+    /// are in the `E00xx` range, name errors in `E01xx`, type errors in `E02xx`
+    /// and errors found while evaluating in `E03xx`.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ErrorKind::UnknownName { .. } => "E0101",
+            ErrorKind::UnknownField { .. } => "E0102",
+            ErrorKind::UnknownType { .. } => "E0103",
+            ErrorKind::MissingField { .. } => "E0104",
+            ErrorKind::NotAStruct { .. } => "E0105",
+            ErrorKind::DuplicateName { .. } => "E0106",
+            ErrorKind::PossiblyUninitialized { .. } => "E0107",
+            ErrorKind::AssignToImmutable { .. } => "E0108",
+            ErrorKind::MismatchedTypes { .. } => "E0201",
+            ErrorKind::MismatchedOperands { .. } => "E0202",
+            ErrorKind::NotNumeric { .. } => "E0203",
+            ErrorKind::NotOrdered { .. } => "E0204",
+            ErrorKind::InvalidCast { .. } => "E0205",
+            ErrorKind::NotCallable { .. } => "E0206",
+            ErrorKind::ArgumentCount { .. } => "E0207",
+            ErrorKind::NotIndexable { .. } => "E0208",
+            ErrorKind::TypeAnnotationRequired { .. } => "E0209",
+            ErrorKind::InvalidAssignmentTarget { .. } => "E0210",
+            ErrorKind::InfiniteType { .. } => "E0211",
+            ErrorKind::RecursiveValue { .. } => "E0212",
+            ErrorKind::NotAReference { .. } => "E0213",
+            ErrorKind::AssignThroughReference { .. } => "E0214",
+            ErrorKind::Unsupported { .. } => "E0215",
+            ErrorKind::IntegerOverflow { .. } => "E0301",
+            ErrorKind::DivisionByZero => "E0302",
+            ErrorKind::IndexOutOfBounds { .. } => "E0303",
+            ErrorKind::NegativeIndex { .. } => "E0304",
+            ErrorKind::InvalidSlice { .. } => "E0305",
+            ErrorKind::ValueOutOfRange { .. } => "E0306",
+            ErrorKind::CallDepthExceeded { .. } => "E0307",
+            ErrorKind::FuelExhausted { .. } => "E0308",
+            ErrorKind::TimeLimitExceeded { .. } => "E0309",
+            ErrorKind::HeapObjectsExceeded { .. } => "E0310",
+            ErrorKind::HeapBytesExceeded { .. } => "E0311",
+            ErrorKind::Internal(_) => "E0001",
+        }
+    }
+
+    fn label(&self) -> String {
+        match self {
+            ErrorKind::UnknownName { .. } => "not found in this scope".to_string(),
+            ErrorKind::UnknownField { ty, .. } => format!("unknown field of {ty}"),
+            ErrorKind::UnknownType { .. } => "unknown type".to_string(),
+            ErrorKind::MissingField { field, .. } => format!("missing {field}"),
+            ErrorKind::MismatchedTypes { expected, found } => {
+                format!("expected {expected}, found {found}")
+            }
+            ErrorKind::MismatchedOperands { lhs, rhs } => format!("{lhs} and {rhs}"),
+            ErrorKind::NotNumeric { found } | ErrorKind::NotOrdered { found } => {
+                format!("operands of type {found}")
+            }
+            ErrorKind::InvalidCast { from, to } => format!("{from} as {to}"),
+            ErrorKind::NotCallable { found, .. } => format!("not a function, found {found}"),
+            ErrorKind::ArgumentCount { expected, .. } => {
+                format!("expected {expected} arguments")
+            }
+            ErrorKind::InfiniteType { .. } => "infinite type".to_string(),
+            ErrorKind::NotAReference { found } => format!("not a reference, found {found}"),
+            ErrorKind::AssignThroughReference { ty } => format!("through a reference of type {ty}"),
+            ErrorKind::PossiblyUninitialized { .. } => "possibly uninitialized".to_string(),
+            ErrorKind::AssignToImmutable { .. } => "not mutable".to_string(),
+            ErrorKind::Unsupported { .. } => "not supported yet".to_string(),
+            ErrorKind::IntegerOverflow { .. } => "overflows".to_string(),
+            ErrorKind::DivisionByZero => "the divisor is zero".to_string(),
+            ErrorKind::IndexOutOfBounds { .. }
+            | ErrorKind::NegativeIndex { .. }
+            | ErrorKind::InvalidSlice { .. } => "out of bounds".to_string(),
+            ErrorKind::CallDepthExceeded { .. } => "exceeds the maximum call depth".to_string(),
+            ErrorKind::FuelExhausted { .. }
+            | ErrorKind::TimeLimitExceeded { .. }
+            | ErrorKind::HeapObjectsExceeded { .. }
+            | ErrorKind::HeapBytesExceeded { .. } => "stopped here".to_string(),
+            _ => String::new(),
+        }
+    }
+
+    /// Another place in the code the error is about, like the declaration of
+    /// a name.
+    fn related(&self) -> Option<(Span, String)> {
+        match self {
+            ErrorKind::AssignToImmutable {
+                name,
+                declared_at: Some(span),
+            } => Some((
+                span.clone(),
+                format!("{name} is declared here without `mut`"),
+            )),
+            _ => None,
+        }
+    }
+
+    fn help(&self) -> Option<String> {
+        match self {
+            ErrorKind::MismatchedOperands { rhs, .. } => Some(format!(
+                "convert one of the operands with `as`, e.g. `a as {rhs}`"
+            )),
+            ErrorKind::TypeAnnotationRequired { .. } => {
+                Some("add a type annotation, e.g. `let a: [int] = []`".to_string())
+            }
+            ErrorKind::PossiblyUninitialized { name } => Some(format!(
+                "assign a value to {name} on every path before it is used"
+            )),
+            ErrorKind::AssignThroughReference { .. } => {
+                Some("take the reference with `&mut` to assign through it".to_string())
+            }
+            ErrorKind::AssignToImmutable { .. } => {
+                Some("declare it with `mut` to assign to it, e.g. `let mut a = 1`".to_string())
+            }
+            ErrorKind::IntegerOverflow { .. } => Some(
+                "use a wider integer type, or a wrapping or saturating function from Std"
+                    .to_string(),
+            ),
+            ErrorKind::CallDepthExceeded { .. } => Some(
+                "make the recursive call the last thing the function does, so that it replaces the call it is in"
+                    .to_string(),
+            ),
+            ErrorKind::Internal(_) => {
+                Some("this is a bug in the interpreter, please report it".to_string())
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Display for ErrorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ErrorKind::UnknownName { name } => write!(f, "Unknown name {name}."),
+            ErrorKind::UnknownField { ty, field } => {
+                write!(f, "Type {ty} has no field named {field}.")
+            }
+            ErrorKind::UnknownType { name } => write!(f, "Unknown type {name}."),
+            ErrorKind::MissingField { ty, field } => {
+                write!(f, "Missing field {field} of struct {ty}.")
+            }
+            ErrorKind::NotAStruct { name } => write!(f, "{name} is not a struct."),
+            ErrorKind::DuplicateName { name } => write!(f, "{name} is declared more than once."),
+            ErrorKind::PossiblyUninitialized { name } => {
+                write!(f, "{name} is used before it is initialized on every path.")
+            }
+            ErrorKind::AssignToImmutable { name, .. } => {
+                write!(f, "Cannot assign to {name}, as it is not mutable.")
+            }
+            ErrorKind::MismatchedTypes { expected, found } => {
+                write!(f, "Mismatched types. Expected {expected}, found {found}.")
+            }
+            ErrorKind::MismatchedOperands { lhs, rhs } => write!(
+                f,
+                "Operands of an operation must have the same type, found {lhs} and {rhs}."
+            ),
+            ErrorKind::NotNumeric { found } => write!(
+                f,
+                "Arithmetic operations require numeric operands, found {found}."
+            ),
+            ErrorKind::NotOrdered { found } => write!(
+                f,
+                "Only numbers and char values can be ordered, found {found}."
+            ),
+            ErrorKind::InvalidCast { from, to } => write!(f, "Cannot cast {from} to {to}."),
+            ErrorKind::NotCallable { name, found } => {
+                write!(f, "{name} of type {found} cannot be called.")
+            }
+            ErrorKind::ArgumentCount { expected, found } => write!(
+                f,
+                "Invalid number of arguments in function call. Expected {expected} while {found} were passed."
+            ),
+            ErrorKind::NotIndexable { found } => {
+                write!(f, "Value of type {found} cannot be indexed.")
+            }
+            ErrorKind::TypeAnnotationRequired { subject } => {
+                write!(f, "You need to declare the type of {subject}.")
+            }
+            ErrorKind::InvalidAssignmentTarget { expr } => {
+                write!(f, "Invalid left hand side expression {expr}.")
+            }
+            ErrorKind::InfiniteType { var, ty } => {
+                write!(f, "Type {var} cannot be {ty}, as it would contain itself.")
+            }
+            ErrorKind::RecursiveValue { name } => {
+                write!(f, "Only functions can be declared with let rec, {name} is not one.")
+            }
+            ErrorKind::NotAReference { found } => {
+                write!(f, "Value of type {found} cannot be dereferenced.")
+            }
+            ErrorKind::AssignThroughReference { ty } => write!(
+                f,
+                "Cannot assign through a reference of type {ty}, as it is not mutable."
+            ),
+            ErrorKind::Unsupported { construct } => {
+                write!(f, "{construct} are not supported yet.")
+            }
+            ErrorKind::IntegerOverflow { operator, lhs, rhs } => match operator {
+                ArithmeticOperator::Add => {
+                    write!(f, "Integer overflow while adding {lhs} and {rhs}.")
+                }
+                ArithmeticOperator::Sub => {
+                    write!(f, "Integer overflow while subtracting {rhs} from {lhs}.")
+                }
+                ArithmeticOperator::Mul => {
+                    write!(f, "Integer overflow while multiplying {lhs} by {rhs}.")
+                }
+                ArithmeticOperator::Div | ArithmeticOperator::Mod => {
+                    write!(f, "Integer overflow while dividing {lhs} by {rhs}.")
+                }
+            },
+            ErrorKind::DivisionByZero => write!(f, "Division by zero."),
+            ErrorKind::IndexOutOfBounds { index, len } => write!(
+                f,
+                "Index {index} is out of bounds for array of length {len}."
+            ),
+            ErrorKind::NegativeIndex { index, len } => write!(
+                f,
+                "Negative index {index} is not allowed for array of length {len}."
+            ),
+            ErrorKind::InvalidSlice { from, to, .. } if from > to => {
+                write!(f, "Slice start {from} is greater than slice end {to}.")
+            }
+            ErrorKind::InvalidSlice { from, to, len } => write!(
+                f,
+                "Slice {from}..{to} is out of bounds for array of length {len}."
+            ),
+            ErrorKind::ValueOutOfRange { value, ty } => {
+                write!(f, "{value} cannot be represented as {ty}.")
+            }
+            ErrorKind::CallDepthExceeded { limit } => {
+                write!(f, "Calls nested more than {limit} deep.")
+            }
+            ErrorKind::FuelExhausted { limit } => {
+                write!(f, "Ran out of fuel after {limit} steps.")
+            }
+            ErrorKind::TimeLimitExceeded { limit } => {
+                write!(f, "Ran for longer than the time limit of {limit:?}.")
+            }
+            ErrorKind::HeapObjectsExceeded { limit } => {
+                write!(f, "More than {limit} values alive on the heap.")
+            }
+            ErrorKind::HeapBytesExceeded { limit } => {
+                write!(f, "Values on the heap take more than {limit} bytes.")
+            }
+            ErrorKind::Internal(message) => f.write_str(message),
+        }
+    }
+}
+
+/// Returns the long explanation of an error code, including the syntax
+/// errors reported by the parser.
+pub fn explain(code: &str) -> Option<&'static str> {
+    let explanation = match code {
+        "E0001" => "The interpreter reached a state that should not be possible. This is a bug in the interpreter rather than in the program.",
+        "E0101" => {
+            "A name was used that is not defined in the current scope.
+
+    let a = b + 1
+
+Define `b` with `let` before using it, or check the spelling of the name."
+        }
+        "E0102" => {
+            "A field was accessed that the struct does not declare.
+
+    type Point = {x: float, y: float}
+    let p = Point {x: 0.0, y: 0.0}
+    p.z"
+        }
+        "E0103" => "A type name was used that has not been declared with `type`.",
+        "E0104" => {
+            "A struct was created without a value for one of its fields. Every field
+must be initialised.
+
+    type Point = {x: float, y: float}
+    let p = Point {x: 0.0}"
+        }
+        "E0105" => "A struct was expected, e.g. to access a field or to create a value, but the name refers to something else.",
+        "E0106" => {
+            "The same name is declared twice by the bindings of one `let rec`.
+
+    let rec f x = x
+    and f y = y"
+        }
+        "E0107" => {
+            "A name declared without a value was used before a value was assigned to
+it on every path that leads to the use.
+
+    let a: int
+    if b then a = 1
+    a + 1
+
+Assigning it in both branches of an `if` initializes it. A function cannot
+capture a name that might not be initialized yet."
+        }
+        "E0108" => {
+            "A name, argument or field that is not declared with `mut` was assigned to.
+
+    let a = 1
+    a = 2
+
+Declare it with `mut` to allow assigning to it:
+
+    let mut a = 1
+    type Point = {mut x: float, y: float}
+
+A name declared without a value can be assigned once without `mut`."
+        }
+        "E0201" => {
+            "A value has a different type than the one that was expected, e.g. an
+argument of a function call or the initialiser of an annotated `let`.
+
+    let a: u8 = 1
+
+Integer literals without a suffix are of type int. Write `1u8` or cast the
+value with `as`."
+        }
+        "E0202" => {
+            "Both operands of an operation must have the same type. Numbers are never
+converted implicitly.
+
+    let a = 1.0 + 1
+
+Cast one of the operands, e.g. `1.0 + 1 as float`."
+        }
+        "E0203" => "Arithmetic operations only work on numeric values: int, float, the sized integers, bigint and rational.",
+        "E0204" => "Only numbers and char values can be compared with <, <=, > and >=.",
+        "E0205" => {
+            "The value cannot be cast to the requested type. Numbers can be cast to any
+other numeric type, and integers to and from char.
+
+    let a = true as int"
+        }
+        "E0206" => "A value that is not a function was called.",
+        "E0207" => "The parameters of a function or closure do not match the arguments of its type annotation, or of the function type it is expected to have. Calls never have the wrong number of arguments, as calling a function with fewer applies it partially.",
+        "E0208" => "Only arrays can be indexed.",
+        "E0209" => {
+            "The type of a name could not be inferred from its value or its uses, e.g.
+an empty array that is never filled, and must be written out.
+
+    let a = []
+
+Fields can only be accessed on values whose type is known, so the type of a
+function argument whose fields are used must be written out too.
+
+    let norm p: (Point -> float) = p.x * p.x + p.y * p.y"
+        }
+        "E0210" => "Only names, fields and array elements can be assigned to.",
+        "E0211" => {
+            "A value would need a type that contains itself, like a function that is
+passed to itself.
+
+    let f x = x x"
+        }
+        "E0212" => {
+            "A binding of a `let rec` is not a function. Only functions can refer to
+themselves or to each other, as a value would be used before it exists.
+
+    let rec a = a + 1"
+        }
+        "E0213" => {
+            "A value that is not a reference was dereferenced with `*`.
+
+    let a = 1
+    *a + 1"
+        }
+        "E0214" => {
+            "A value was assigned through a `&` reference, which only allows reading
+the value it refers to. Take the reference with `&mut` instead:
+
+    let mut a = 1
+    let r = &mut a
+    *r = 2"
+        }
+        "E0215" => {
+            "The code uses a construct the parser accepts but the interpreter cannot run
+yet: string literals, ranges outside of an index, `match`, `for`, `module` and
+`use`. Ranges can be used to slice arrays:
+
+    let a = [1, 2, 3]
+    let b = a[1..]"
+        }
+        "E0301" => {
+            "The result of an integer operation does not fit in its type. Integer
+arithmetic is checked and never wraps silently.
+
+    let a = 255u8 + 1u8
+
+Use a wider type, or the wrapping and saturating functions in Std, e.g.
+`Std.U8.wrapping_add 255u8 1u8`."
+        }
+        "E0302" => "An integer, bigint or rational value was divided by zero. Dividing a float by zero results in an infinity or NaN instead.",
+        "E0303" => {
+            "An array was indexed past its end.
+
+    let a = [1, 2]
+    a[2]"
+        }
+        "E0304" => "An array was indexed with a negative number.",
+        "E0305" => "The bounds of a slice are out of the array, or the start of the slice is after its end.",
+        "E0306" => {
+            "A value was cast to a type that cannot represent it.
+
+    let a = 300 as u8"
+        }
+        "E0307" => {
+            "More calls were in progress at once than the interpreter allows, which
+usually means a recursion that does not end or goes too deep. A call that
+is the last thing a function does replaces the call it is in, so it does
+not count:
+
+    let rec count n acc: (int -> int -> int) =
+        if n == 0 then acc else count (n - 1) (acc + 1)"
+        }
+        "E0308" => {
+            "The code took more steps than the fuel given to the interpreter allows,
+which usually means a recursion that does not end.
+
+    let rec forever n: (int -> int) = forever (n + 1)"
+        }
+        "E0309" => "The code ran for longer than the time limit given to the interpreter.",
+        "E0310" => "More structs, arrays, functions and other values were alive on the heap at once than the interpreter allows.",
+        "E0311" => "The values on the heap took more memory at once than the interpreter allows.",
+        _ => return parser::explain(code),
+    };
+    Some(explanation)
+}
@@ -0,0 +1,489 @@
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    rc::{Rc, Weak},
+};
+
+use num_bigint::BigInt;
+use num_rational::BigRational;
+
+use crate::{
+    error::ErrorKind,
+    limits::Limits,
+    object::{CapturedName, Function, Object, PartialApplication, Reference, Unpacked, Value},
+};
+
+/// The number of values the heap tracks before it first collects them on
+/// its own.
+const INITIAL_THRESHOLD: usize = 1024;
+
+/// The values on the heap that can refer to other values: structs, arrays,
+/// functions, the cells of captured names, references and partial
+/// applications.
+///
+/// They are reference counted, which frees them as soon as nothing refers to
+/// them, unless they refer to each other in a cycle, like a recursive
+/// function that captures itself. The heap keeps track of all of them to
+/// find and free such cycles.
+///
+/// The big integers and rationals the code computes are tracked as well, to
+/// count the bytes of their digits, but they cannot refer to other values so
+/// they are never collected.
+pub struct Heap {
+    values: Vec<Tracked>,
+    /// The number of tracked values at which they are collected on their own.
+    threshold: usize,
+    numbers: Vec<TrackedNumber>,
+    /// The number of tracked numbers at which the freed ones are forgotten.
+    number_threshold: usize,
+    /// The bytes the tracked values and numbers took when they were last
+    /// counted, plus the ones allocated since.
+    bytes: usize,
+    collections: usize,
+    collected: usize,
+}
+
+/// Statistics about the values on the heap.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct HeapStats {
+    /// The number of structs and arrays alive.
+    pub objects: usize,
+    /// The number of functions defined in the language alive.
+    pub functions: usize,
+    /// The number of cells of captured or referenced names alive.
+    pub cells: usize,
+    /// The number of references alive. Copies of a reference share it.
+    pub references: usize,
+    /// The number of partial applications alive.
+    pub partial_applications: usize,
+    /// The bytes the values alive take, as estimated from their sizes,
+    /// including the digits of the big integers and rationals computed.
+    pub bytes: usize,
+    /// The number of collections so far.
+    pub collections: usize,
+    /// The number of values freed by collections so far.
+    pub collected: usize,
+}
+
+enum Tracked {
+    Object(Weak<RefCell<Object>>),
+    Function(Weak<RefCell<Function>>),
+    Cell(Weak<RefCell<Value>>),
+    Reference(Weak<Reference>),
+    PartialApplication(Weak<PartialApplication>),
+}
+
+enum TrackedNumber {
+    BigInteger(Weak<BigInt>),
+    Rational(Weak<BigRational>),
+}
+
+/// A tracked value kept alive while it is being collected.
+enum HeapValue {
+    Object(Rc<RefCell<Object>>),
+    Function(Rc<RefCell<Function>>),
+    Cell(Rc<RefCell<Value>>),
+    Reference(Rc<Reference>),
+    PartialApplication(Rc<PartialApplication>),
+}
+
+impl Default for Heap {
+    fn default() -> Self {
+        Self {
+            values: Vec::new(),
+            threshold: INITIAL_THRESHOLD,
+            numbers: Vec::new(),
+            number_threshold: INITIAL_THRESHOLD,
+            bytes: 0,
+            collections: 0,
+            collected: 0,
+        }
+    }
+}
+
+impl Heap {
+    pub fn alloc_object(&mut self, object: Object) -> Rc<RefCell<Object>> {
+        self.bytes += object_bytes(&object);
+        let object = Rc::new(RefCell::new(object));
+        self.values.push(Tracked::Object(Rc::downgrade(&object)));
+        object
+    }
+
+    pub fn alloc_function(&mut self, function: Function) -> Rc<RefCell<Function>> {
+        self.bytes += function_bytes(&function);
+        let function = Rc::new(RefCell::new(function));
+        self.values
+            .push(Tracked::Function(Rc::downgrade(&function)));
+        function
+    }
+
+    pub fn alloc_cell(&mut self, value: Value) -> Rc<RefCell<Value>> {
+        self.bytes += rc_bytes::<RefCell<Value>>(0);
+        let cell = Rc::new(RefCell::new(value));
+        self.values.push(Tracked::Cell(Rc::downgrade(&cell)));
+        cell
+    }
+
+    pub fn alloc_reference(&mut self, reference: Reference) -> Rc<Reference> {
+        self.bytes += rc_bytes::<Reference>(0);
+        let reference = Rc::new(reference);
+        self.values
+            .push(Tracked::Reference(Rc::downgrade(&reference)));
+        reference
+    }
+
+    pub fn alloc_partial_application(
+        &mut self,
+        partial: PartialApplication,
+    ) -> Rc<PartialApplication> {
+        self.bytes += partial_application_bytes(&partial);
+        let partial = Rc::new(partial);
+        self.values
+            .push(Tracked::PartialApplication(Rc::downgrade(&partial)));
+        partial
+    }
+
+    /// Tracks the value if it is a big integer or a rational, adding the
+    /// bytes of its digits, and returns whether it is one.
+    #[inline]
+    pub fn track_number(&mut self, value: &Value) -> bool {
+        if !value.is_big_number() {
+            return false;
+        }
+        let number = match &*value.view() {
+            Unpacked::BigInteger(integer) => {
+                self.bytes += big_integer_bytes(integer);
+                TrackedNumber::BigInteger(Rc::downgrade(integer))
+            }
+            Unpacked::Rational(rational) => {
+                self.bytes += rational_bytes(rational);
+                TrackedNumber::Rational(Rc::downgrade(rational))
+            }
+            _ => unreachable!("Value is a big number."),
+        };
+        self.numbers.push(number);
+        true
+    }
+
+    /// Collects the values if the heap has grown enough since the last
+    /// collection. It must only be called when no value on the heap is
+    /// borrowed, like between statements.
+    pub fn maybe_collect(&mut self) {
+        if self.values.len() >= self.threshold {
+            self.collect();
+            self.threshold = INITIAL_THRESHOLD.max(self.values.len() * 2);
+        }
+        if self.numbers.len() >= self.number_threshold {
+            self.numbers.retain(TrackedNumber::is_alive);
+            self.number_threshold = INITIAL_THRESHOLD.max(self.numbers.len() * 2);
+        }
+    }
+
+    /// Checks that the values alive are within the limits. Values are only
+    /// counted, after collecting the cycles between them, once more of them
+    /// were allocated since they were last counted than the limits allow, as
+    /// the ones allocated since might have been freed already.
+    ///
+    /// Like collecting, it must only be called when no value on the heap is
+    /// borrowed, like between two instructions.
+    pub fn check_limits(&mut self, limits: &Limits) -> Result<(), ErrorKind> {
+        let objects_exceeded = |heap: &Self| {
+            limits
+                .max_heap_objects
+                .filter(|limit| heap.values.len() > *limit)
+        };
+        let bytes_exceeded =
+            |heap: &Self| limits.max_heap_bytes.filter(|limit| heap.bytes > *limit);
+        if objects_exceeded(self).is_none() && bytes_exceeded(self).is_none() {
+            return Ok(());
+        }
+
+        self.collect();
+        self.numbers.retain(TrackedNumber::is_alive);
+        self.bytes = self.live_bytes();
+        if let Some(limit) = objects_exceeded(self) {
+            return Err(ErrorKind::HeapObjectsExceeded { limit });
+        }
+        if let Some(limit) = bytes_exceeded(self) {
+            return Err(ErrorKind::HeapBytesExceeded { limit });
+        }
+        Ok(())
+    }
+
+    /// Frees the values only kept alive by cycles of references between
+    /// them, returning how many were freed.
+    ///
+    /// A value is in use when something other than the values on the heap
+    /// refers to it, like the stack or the interpreter itself, which is when
+    /// it is referred to more times than the other values on the heap do.
+    /// Every value reachable from one in use is in use too. The others are
+    /// cleared, which breaks their cycles so they are freed.
+    pub fn collect(&mut self) -> usize {
+        self.values.retain(Tracked::is_alive);
+        // Holding every value keeps them alive while they are counted, and
+        // adds one to each of their counts.
+        let values = self
+            .values
+            .iter()
+            .filter_map(Tracked::upgrade)
+            .collect::<Vec<_>>();
+        let indices = values
+            .iter()
+            .enumerate()
+            .map(|(i, value)| (value.address(), i))
+            .collect::<HashMap<_, _>>();
+
+        let mut references = vec![0; values.len()];
+        let mut addresses = Vec::new();
+        for value in &values {
+            value.references(&mut addresses);
+            for address in addresses.drain(..) {
+                if let Some(i) = indices.get(&address) {
+                    references[*i] += 1;
+                }
+            }
+        }
+
+        let mut in_use = values
+            .iter()
+            .zip(&references)
+            .map(|(value, references)| value.strong_count() - 1 > *references)
+            .collect::<Vec<_>>();
+        let mut pending = (0..values.len()).filter(|i| in_use[*i]).collect::<Vec<_>>();
+        while let Some(i) = pending.pop() {
+            values[i].references(&mut addresses);
+            for address in addresses.drain(..) {
+                if let Some(&j) = indices.get(&address) {
+                    if !in_use[j] {
+                        in_use[j] = true;
+                        pending.push(j);
+                    }
+                }
+            }
+        }
+
+        let mut collected = 0;
+        for (value, in_use) in values.iter().zip(in_use) {
+            if !in_use {
+                value.clear();
+                collected += 1;
+            }
+        }
+        drop(values);
+        self.values.retain(Tracked::is_alive);
+
+        self.collections += 1;
+        self.collected += collected;
+        collected
+    }
+
+    pub fn stats(&self) -> HeapStats {
+        let mut stats = HeapStats {
+            collections: self.collections,
+            collected: self.collected,
+            ..Default::default()
+        };
+        for value in self.values.iter().filter_map(Tracked::upgrade) {
+            match value {
+                HeapValue::Object(_) => stats.objects += 1,
+                HeapValue::Function(_) => stats.functions += 1,
+                HeapValue::Cell(_) => stats.cells += 1,
+                HeapValue::Reference(_) => stats.references += 1,
+                HeapValue::PartialApplication(_) => stats.partial_applications += 1,
+            }
+        }
+        stats.bytes = self.live_bytes();
+        stats
+    }
+
+    /// The bytes the values and numbers alive take.
+    fn live_bytes(&self) -> usize {
+        let values = self
+            .values
+            .iter()
+            .filter_map(Tracked::upgrade)
+            .map(|value| value.bytes());
+        let numbers = self.numbers.iter().filter_map(TrackedNumber::bytes);
+        values.chain(numbers).sum()
+    }
+}
+
+impl TrackedNumber {
+    fn is_alive(&self) -> bool {
+        match self {
+            TrackedNumber::BigInteger(integer) => integer.strong_count() > 0,
+            TrackedNumber::Rational(rational) => rational.strong_count() > 0,
+        }
+    }
+
+    /// The bytes the number takes, if it is still alive.
+    fn bytes(&self) -> Option<usize> {
+        match self {
+            TrackedNumber::BigInteger(integer) => {
+                integer.upgrade().map(|integer| big_integer_bytes(&integer))
+            }
+            TrackedNumber::Rational(rational) => {
+                rational.upgrade().map(|rational| rational_bytes(&rational))
+            }
+        }
+    }
+}
+
+impl Tracked {
+    fn is_alive(&self) -> bool {
+        match self {
+            Tracked::Object(object) => object.strong_count() > 0,
+            Tracked::Function(function) => function.strong_count() > 0,
+            Tracked::Cell(cell) => cell.strong_count() > 0,
+            Tracked::Reference(reference) => reference.strong_count() > 0,
+            Tracked::PartialApplication(partial) => partial.strong_count() > 0,
+        }
+    }
+
+    fn upgrade(&self) -> Option<HeapValue> {
+        match self {
+            Tracked::Object(object) => object.upgrade().map(HeapValue::Object),
+            Tracked::Function(function) => function.upgrade().map(HeapValue::Function),
+            Tracked::Cell(cell) => cell.upgrade().map(HeapValue::Cell),
+            Tracked::Reference(reference) => reference.upgrade().map(HeapValue::Reference),
+            Tracked::PartialApplication(partial) => {
+                partial.upgrade().map(HeapValue::PartialApplication)
+            }
+        }
+    }
+}
+
+impl HeapValue {
+    fn address(&self) -> *const () {
+        match self {
+            HeapValue::Object(object) => Rc::as_ptr(object) as *const (),
+            HeapValue::Function(function) => Rc::as_ptr(function) as *const (),
+            HeapValue::Cell(cell) => Rc::as_ptr(cell) as *const (),
+            HeapValue::Reference(reference) => Rc::as_ptr(reference) as *const (),
+            HeapValue::PartialApplication(partial) => Rc::as_ptr(partial) as *const (),
+        }
+    }
+
+    /// The bytes the value takes, as estimated from its size.
+    fn bytes(&self) -> usize {
+        match self {
+            HeapValue::Object(object) => object_bytes(&object.borrow()),
+            HeapValue::Function(function) => function_bytes(&function.borrow()),
+            HeapValue::Cell(_) => rc_bytes::<RefCell<Value>>(0),
+            HeapValue::Reference(_) => rc_bytes::<Reference>(0),
+            HeapValue::PartialApplication(partial) => partial_application_bytes(partial),
+        }
+    }
+
+    fn strong_count(&self) -> usize {
+        match self {
+            HeapValue::Object(object) => Rc::strong_count(object),
+            HeapValue::Function(function) => Rc::strong_count(function),
+            HeapValue::Cell(cell) => Rc::strong_count(cell),
+            HeapValue::Reference(reference) => Rc::strong_count(reference),
+            HeapValue::PartialApplication(partial) => Rc::strong_count(partial),
+        }
+    }
+
+    /// Adds the addresses of the values on the heap this one refers to.
+    fn references(&self, addresses: &mut Vec<*const ()>) {
+        match self {
+            HeapValue::Object(object) => object
+                .borrow()
+                .values()
+                .iter()
+                .for_each(|value| value.heap_references(addresses)),
+            HeapValue::Function(function) => {
+                for name in &function.borrow().captured_names {
+                    addresses.push(Rc::as_ptr(&name.value) as *const ());
+                }
+            }
+            HeapValue::Cell(cell) => cell.borrow().heap_references(addresses),
+            HeapValue::Reference(reference) => {
+                addresses.push(Rc::as_ptr(&reference.value) as *const ())
+            }
+            HeapValue::PartialApplication(partial) => {
+                partial.function.heap_references(addresses);
+                for value in &partial.args {
+                    value.heap_references(addresses);
+                }
+            }
+        }
+    }
+
+    /// Drops the values this one refers to. References and partial
+    /// applications cannot be changed, but they are never part of a cycle on
+    /// their own as they are created from values that exist already.
+    fn clear(&self) {
+        match self {
+            HeapValue::Object(object) => {
+                let values = std::mem::take(&mut object.borrow_mut().values);
+                drop(values);
+            }
+            HeapValue::Function(function) => {
+                let names = std::mem::take(&mut function.borrow_mut().captured_names);
+                drop(names);
+            }
+            HeapValue::Cell(cell) => {
+                let value = cell.replace(Value::UNIT);
+                drop(value);
+            }
+            HeapValue::Reference(_) | HeapValue::PartialApplication(_) => {}
+        }
+    }
+}
+
+/// The bytes of a value of type `T` behind an `Rc`, with its counts, and
+/// `extra` bytes it owns.
+fn rc_bytes<T>(extra: usize) -> usize {
+    2 * size_of::<usize>() + size_of::<T>() + extra
+}
+
+fn object_bytes(object: &Object) -> usize {
+    rc_bytes::<RefCell<Object>>(object.values.len() * size_of::<Value>())
+}
+
+/// The digits of a big integer are stored as 64 bit words.
+fn digit_bytes(integer: &BigInt) -> usize {
+    integer.bits().div_ceil(64) as usize * size_of::<u64>()
+}
+
+fn big_integer_bytes(integer: &BigInt) -> usize {
+    rc_bytes::<BigInt>(digit_bytes(integer))
+}
+
+fn rational_bytes(rational: &BigRational) -> usize {
+    rc_bytes::<BigRational>(digit_bytes(rational.numer()) + digit_bytes(rational.denom()))
+}
+
+fn function_bytes(function: &Function) -> usize {
+    rc_bytes::<RefCell<Function>>(function.captured_names.len() * size_of::<CapturedName>())
+}
+
+fn partial_application_bytes(partial: &PartialApplication) -> usize {
+    rc_bytes::<PartialApplication>(partial.args.len() * size_of::<Value>())
+}
+
+impl Value {
+    /// Adds the address of the value on the heap this one refers to, if any.
+    fn heap_references(&self, addresses: &mut Vec<*const ()>) {
+        match &*self.view() {
+            Unpacked::Ref(cell) => addresses.push(Rc::as_ptr(cell) as *const ()),
+            Unpacked::Reference(reference) => addresses.push(Rc::as_ptr(reference) as *const ()),
+            Unpacked::Object(object) => addresses.push(Rc::as_ptr(object) as *const ()),
+            Unpacked::Function(function) => addresses.push(Rc::as_ptr(function) as *const ()),
+            Unpacked::PartialApplication(partial) => {
+                addresses.push(Rc::as_ptr(partial) as *const ())
+            }
+            Unpacked::Unit
+            | Unpacked::Boolean(_)
+            | Unpacked::Char(_)
+            | Unpacked::Integer(_)
+            | Unpacked::SizedInteger(..)
+            | Unpacked::BigInteger(_)
+            | Unpacked::Rational(_)
+            | Unpacked::Float(_)
+            | Unpacked::NativeFunction(_) => {}
+        }
+    }
+}
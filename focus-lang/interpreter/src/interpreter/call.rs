@@ -1,100 +1,173 @@
-use std::{cell::RefCell, rc::Rc};
-
-use parser::stmt::Call;
+use std::cell::RefCell;
 
+use super::vm::{CallFrame, Vm};
 use crate::{
-    context::{Context, Local},
-    object::Value,
-    r#type::FunctionType,
+    context::Context,
+    error::{ErrorKind, RuntimeError},
+    object::{Function, PartialApplication, Unpacked, Value},
     Interpreter,
 };
 
 impl Interpreter {
-    pub(super) fn interpret_call(&mut self, call: Call) -> Result<Value, String> {
-        let function_name = self.context.borrow().find_local(&call.path).ok_or(format!(
-            "Unable to get value from stack for path {}.",
-            call.path
-        ))?;
-
-        let value = self
-            .stack
-            .get_value(function_name.sp.unwrap())
-            .ok_or(format!(
-                "Unable to get value from stack for path {}.",
-                call.path
-            ))?;
-
-        let func_ty = self.type_registry.get_type_from_id(function_name.ty);
+    /// Calls the function below the `argc` arguments on top of the stack.
+    /// Returns whether a function defined in the language was entered, whose
+    /// chunk is run next.
+    pub(super) fn call(&mut self, vm: &mut Vm, argc: usize) -> Result<bool, RuntimeError> {
+        let callee = self.stack.peek(argc).clone();
+        if let Some(function) = callee.as_function() {
+            if function.borrow().prototype.arity == argc {
+                self.enter(vm, function, argc, Vec::new())?;
+                return Ok(true);
+            }
+        }
+        let args = self.stack.pop_values(argc);
+        let callee = self.stack.pop_value().deref_value();
+        self.apply(vm, callee, args)
+    }
 
-        let FunctionType {
-            arg_types,
-            ret_type,
-        } = func_ty
-            .as_deref()
-            .map(|ty| ty.as_function())
-            .flatten()
-            .ok_or("Unexpected type for function".to_string())?;
+    /// Calls the function below the `argc` arguments on top of the stack in
+    /// place of the function being run, whose frames are dropped. Calls that
+    /// do not take exactly the arguments of a function defined in the
+    /// language are made as usual.
+    pub(super) fn tail_call(&mut self, vm: &mut Vm, argc: usize) -> Result<bool, RuntimeError> {
+        let callee = self.stack.peek(argc).clone();
+        let (Some(function), Some(call)) = (callee.as_function(), vm.calls.last_mut()) else {
+            return self.call(vm, argc);
+        };
+        if function.borrow().prototype.arity != argc {
+            return self.call(vm, argc);
+        }
+        // The callee of the call being replaced is below its frame.
+        self.stack.replace_frames(call.frames, 1, argc + 1);
+        self.switch_to(&function.borrow(), argc);
+        let chunk = std::mem::replace(&mut vm.chunk, function.borrow().prototype.chunk.clone());
+        call.tail_call = Some((chunk, std::mem::replace(&mut vm.ip, 0)));
+        Ok(true)
+    }
 
-        self.stack.push_frame();
-        let (parent_context, expr) = match value.deref_value() {
-            Value::Function(function) => {
-                let function = function.borrow();
-                let mut ctx_mut = function.context.borrow_mut();
+    /// Applies a function to arguments. With fewer arguments than the
+    /// function takes, the result is a partial application waiting for the
+    /// others. With more, the extra ones are applied to the function it
+    /// returns, once it returns if it is defined in the language.
+    ///
+    /// The call ends once the value is pushed, unless a function defined in
+    /// the language was entered, which this returns.
+    fn apply(
+        &mut self,
+        vm: &mut Vm,
+        mut callee: Value,
+        mut args: Vec<Value>,
+    ) -> Result<bool, RuntimeError> {
+        loop {
+            let (function, mut applied) = match callee.unpack() {
+                Unpacked::PartialApplication(partial) => {
+                    (partial.function.clone(), partial.args.clone())
+                }
+                function => (Value::from(function), Vec::new()),
+            };
+            applied.append(&mut args);
 
-                if function.args.len() != call.params.len() {
-                    return Err(format!("Invalid number of arguments in function call. Expected {} while {} were passed.",
-                    function.args.len(),
-                    call.params.len()));
+            let arity = match &*function.view() {
+                Unpacked::Function(function) => function.borrow().prototype.arity,
+                Unpacked::NativeFunction(native) => native.arity,
+                _ => {
+                    return Err(ErrorKind::Internal("Callee is not a function.".to_string()).into())
                 }
+            };
+            if applied.len() < arity {
+                let partial = self.heap.alloc_partial_application(PartialApplication {
+                    function,
+                    args: applied,
+                });
+                self.stack.push_value(Value::from(partial));
+                return Ok(false);
+            }
 
-                for (i, arg) in function.args.iter().enumerate() {
-                    let expr = call.params[i].clone();
-                    let expr_type_id = self.resolve_expr_type(&expr, Some(arg_types[i]))?;
-                    let value = self.interpret_expression(expr)?;
-                    if !self
-                        .type_registry
-                        .are_types_equal(arg_types[i], expr_type_id)?
-                    {
-                        return Err(format!("Unexpected type for argument {i}"));
+            args = applied.split_off(arity);
+            match function.unpack() {
+                Unpacked::Function(function) => {
+                    self.stack.push_value(Value::from(function.clone()));
+                    for value in applied {
+                        self.stack.push_value(value);
                     }
-                    let sp = self.stack.push_value(value);
-                    ctx_mut.add_local(
-                        &arg,
-                        Local {
-                            ty: expr_type_id,
-                            sp: Some(sp),
-                        },
-                    );
+                    self.enter(vm, &function, arity, args)?;
+                    return Ok(true);
                 }
-
-                for name in &function.captured_names {
-                    let sp = self.stack.push_value(Value::Ref(name.value.clone()));
-                    ctx_mut.add_local(
-                        &name.ident,
-                        Local {
-                            sp: Some(sp),
-                            ty: name.type_id,
-                        },
-                    );
+                Unpacked::NativeFunction(native) => {
+                    let value =
+                        (native.function)(applied.into_iter().map(Value::deref_value).collect())?;
+                    if args.is_empty() {
+                        self.stack.push_value(value);
+                        return Ok(false);
+                    }
+                    callee = value.deref_value();
                 }
-                (function.context.clone(), function.expr.clone())
+                _ => unreachable!("Callee checked to be a function."),
             }
-            _ => return Err("Expected a function or closure for call.".to_string()),
-        };
-        let parent_module = parent_context.borrow().module();
-        let inner_context = Rc::new(RefCell::new(
-            Context::new(parent_module).with_parent(parent_context),
-        ));
-        let previous_context = std::mem::replace(&mut self.context, inner_context);
+        }
+    }
 
-        let resolved_ret_type = self.resolve_expr_type(&expr, Some(*ret_type))?;
-        self.type_registry
-            .are_types_equal(resolved_ret_type, *ret_type)?;
+    /// Enters a function defined in the language, called with the `argc`
+    /// arguments on top of the stack, unless as many calls as the limits
+    /// allow are in progress already.
+    fn enter(
+        &mut self,
+        vm: &mut Vm,
+        function: &RefCell<Function>,
+        argc: usize,
+        pending: Vec<Value>,
+    ) -> Result<(), RuntimeError> {
+        let limit = self.limits.max_call_depth;
+        if vm.calls.len() >= limit {
+            return Err(ErrorKind::CallDepthExceeded { limit }.into());
+        }
+        let function = function.borrow();
+        let frames = self.stack.frames();
+        let previous_context = self.switch_to(&function, argc);
+        let chunk = std::mem::replace(&mut vm.chunk, function.prototype.chunk.clone());
+        vm.calls.push(CallFrame {
+            chunk,
+            ip: std::mem::replace(&mut vm.ip, 0),
+            pending,
+            previous_context,
+            frames,
+            tail_call: None,
+        });
+        Ok(())
+    }
 
-        let value = self.interpret_expression(expr)?;
+    /// Pushes the frame of a call of a function with the `argc` arguments on
+    /// top of the stack, which holds its arguments in the first slots and the
+    /// variables it captured after them, and switches to its context.
+    /// Returns the context switched from.
+    fn switch_to(&mut self, function: &Function, argc: usize) -> Context {
+        self.stack.push_call_frame(argc);
+        let context = Context::new(function.module.clone(), self.stack.len());
+        for name in &function.captured_names {
+            self.stack.push_value(Value::from(name.value.clone()));
+        }
+        std::mem::replace(&mut self.context, context)
+    }
 
-        self.context = previous_context;
+    /// Returns from the chunk being run with the value on top of the stack,
+    /// which is the value of the run once no call is left to return to.
+    pub(super) fn return_value(&mut self, vm: &mut Vm) -> Result<Option<Value>, RuntimeError> {
+        let value = self.stack.pop_value();
+        let Some(call) = vm.calls.pop() else {
+            return Ok(Some(value));
+        };
+        // The callee is below the frame of the call.
         self.stack.pop_frame();
-        Ok(value)
+        self.stack.pop_value();
+        self.context = call.previous_context;
+        vm.chunk = call.chunk;
+        vm.ip = call.ip;
+
+        if call.pending.is_empty() {
+            self.stack.push_value(value);
+        } else {
+            self.apply(vm, value.deref_value(), call.pending)?;
+        }
+        Ok(None)
     }
 }
@@ -0,0 +1,104 @@
+use std::rc::Rc;
+
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::{FromPrimitive, ToPrimitive};
+
+use crate::{
+    error::{ErrorKind, RuntimeError},
+    object::{Unpacked, Value},
+    r#type::{TypeId, TypeLayout},
+    Interpreter,
+};
+
+impl Interpreter {
+    /// Converts a value to the type with `type_id`. The cast is known to be
+    /// valid, but the value may not fit in the new type.
+    pub(super) fn cast_value(&self, value: Value, type_id: TypeId) -> Result<Value, RuntimeError> {
+        let ty = self
+            .type_registry
+            .get_resolved_type_from_id(type_id)
+            .ok_or(ErrorKind::Internal(format!(
+                "Type with id {type_id} not found."
+            )))?;
+
+        let value = value.deref_value();
+        let out_of_range = |value: String| ErrorKind::ValueOutOfRange {
+            value,
+            ty: ty.ident.clone(),
+        };
+
+        if let Some(kind) = ty.integer_kind() {
+            return match value.clone().unpack() {
+                Unpacked::Float(f) => {
+                    let truncated = f.trunc();
+                    if truncated >= kind.min() as f64 && truncated < (kind.max() + 1) as f64 {
+                        Ok(Value::integer(truncated as i128, kind).unwrap())
+                    } else {
+                        Err(out_of_range(f.to_string()))
+                    }
+                }
+                Unpacked::Char(c) => {
+                    Value::integer(c as i128, kind).ok_or_else(|| out_of_range(format!("{c:?}")))
+                }
+                _ => match Self::truncate_to_big_integer(&value) {
+                    Some(i) => i
+                        .to_i128()
+                        .and_then(|i| Value::integer(i, kind))
+                        .ok_or_else(|| out_of_range(i.to_string())),
+                    None => Ok(value),
+                },
+            }
+            .map_err(RuntimeError::from);
+        }
+
+        match (value.clone().unpack(), &ty.layout) {
+            (Unpacked::Float(f), TypeLayout::BigInteger) => BigInt::from_f64(f.trunc())
+                .map(|i| Value::from(Rc::new(i)))
+                .ok_or_else(|| out_of_range(f.to_string())),
+            (Unpacked::Float(f), TypeLayout::Rational) => BigRational::from_float(f)
+                .map(|r| Value::from(Rc::new(r)))
+                .ok_or_else(|| out_of_range(f.to_string())),
+            (Unpacked::Rational(r), TypeLayout::Float) => r
+                .to_f64()
+                .map(Value::from)
+                .ok_or_else(|| out_of_range(r.to_string())),
+            (Unpacked::Rational(_), TypeLayout::Rational) => Ok(value),
+            (_, TypeLayout::BigInteger | TypeLayout::Rational | TypeLayout::Float) => {
+                match Self::truncate_to_big_integer(&value) {
+                    Some(i) => match ty.layout {
+                        TypeLayout::BigInteger => Ok(Value::from(Rc::new(i))),
+                        TypeLayout::Rational => {
+                            Ok(Value::from(Rc::new(BigRational::from_integer(i))))
+                        }
+                        _ => i
+                            .to_f64()
+                            .map(Value::from)
+                            .ok_or_else(|| out_of_range(i.to_string())),
+                    },
+                    None => Ok(value),
+                }
+            }
+            (_, TypeLayout::Char) => match value.as_integer() {
+                Some((i, _)) => u32::try_from(i)
+                    .ok()
+                    .and_then(char::from_u32)
+                    .map(Value::from)
+                    .ok_or_else(|| out_of_range(i.to_string())),
+                None => Ok(value),
+            },
+            _ => Ok(value),
+        }
+        .map_err(RuntimeError::from)
+    }
+
+    /// Converts an integer of any kind to a `bigint`, truncating rationals
+    /// towards zero.
+    fn truncate_to_big_integer(value: &Value) -> Option<BigInt> {
+        match &*value.view() {
+            Unpacked::BigInteger(i) => Some(i.as_ref().clone()),
+            Unpacked::Rational(r) => Some(r.trunc().to_integer()),
+            _ => value.as_integer().map(|(i, _)| BigInt::from(i)),
+        }
+    }
+}
@@ -1,25 +1,45 @@
-use std::{cell::RefCell, rc::Rc};
-
-use parser::stmt::Closure;
+use std::rc::Rc;
 
 use crate::{
-    object::{Function, Value},
+    bytecode::Prototype,
+    error::{ErrorKind, RuntimeError},
+    object::{CapturedName, Function, Value},
     Interpreter,
 };
 
 impl Interpreter {
-    pub(super) fn interpret_closure(&mut self, closure: Closure) -> Result<Value, String> {
-        let args = if closure.args.is_empty() {
-            vec!["()".to_string()]
-        } else {
-            closure.args
-        };
+    /// Creates a function with the variables it captures. A variable moves to
+    /// the heap the first time a function captures it, and the scope it is
+    /// declared in keeps using it from there, so the scope and every function
+    /// capturing it share it.
+    pub(super) fn create_closure(
+        &mut self,
+        prototype: &Rc<Prototype>,
+    ) -> Result<Value, RuntimeError> {
+        let captured_names = prototype
+            .captures
+            .iter()
+            .map(|capture| {
+                let sp = self.resolve_binding(&capture.binding)?;
+                let value = self
+                    .stack
+                    .box_value(sp, &mut self.heap)
+                    .ok_or(ErrorKind::Internal(format!(
+                        "Unable to get value from stack for local {}.",
+                        capture.ident
+                    )))?;
+                Ok(CapturedName {
+                    ident: capture.ident.clone(),
+                    value,
+                    type_id: capture.type_id,
+                })
+            })
+            .collect::<Result<Vec<_>, RuntimeError>>()?;
 
-        Ok(Value::Function(Rc::new(RefCell::new(Function {
-            context: self.context.clone(),
-            expr: *closure.block,
-            captured_names: Vec::new(),
-            args,
-        }))))
+        Ok(Value::from(self.heap.alloc_function(Function {
+            module: self.context.module(),
+            prototype: prototype.clone(),
+            captured_names,
+        })))
     }
 }
@@ -0,0 +1,101 @@
+use crate::{
+    error::{ErrorKind, RuntimeError},
+    object::{Object, Unpacked, Value, ValueRef},
+    Interpreter,
+};
+
+impl Interpreter {
+    /// Gets an element of an array.
+    pub(super) fn index_value(&self, value: Value, index: Value) -> Result<Value, RuntimeError> {
+        let i = Self::index_of(index)?;
+        let value = value.deref_value();
+        let Some(object) = value.as_object() else {
+            return Err(ErrorKind::Internal("Value cannot be indexed.".to_string()).into());
+        };
+        let object = object.borrow();
+        let i = Self::check_index(i, object.values().len())?;
+        Ok(object.values()[i].clone().deref_value())
+    }
+
+    /// Resolves an element of the array in a place, so it can be read or used
+    /// as the left hand side of an assignment.
+    pub(super) fn resolve_index(
+        &self,
+        place: ValueRef,
+        index: Value,
+    ) -> Result<ValueRef, RuntimeError> {
+        let i = Self::index_of(index)?;
+        let value = self.read_place(&place);
+        let Some(object) = value.as_object() else {
+            return Err(ErrorKind::Internal("Value cannot be indexed.".to_string()).into());
+        };
+        let len = object.borrow().values().len();
+        Ok(place.project(Self::check_index(i, len)?))
+    }
+
+    /// Checks a bound of a slice of the array `depth` values below it on the
+    /// stack.
+    pub(super) fn check_slice_bound(&self, depth: usize) -> Result<(), RuntimeError> {
+        let len = Self::array_len(self.stack.peek(depth))?;
+        match self.stack.peek(0).clone().deref_value().unpack() {
+            Unpacked::Integer(i) if i < 0 => Err(ErrorKind::NegativeIndex { index: i, len }.into()),
+            Unpacked::Integer(_) => Ok(()),
+            _ => Err(ErrorKind::Internal("Unexpected value for slice bound.".to_string()).into()),
+        }
+    }
+
+    /// Copies the elements `from..to` of an array into a new array. The
+    /// bounds are known not to be negative.
+    pub(super) fn slice_value(
+        &mut self,
+        value: Value,
+        from: Option<Value>,
+        to: Option<Value>,
+    ) -> Result<Value, RuntimeError> {
+        let value = value.deref_value();
+        let Some(object) = value.as_object() else {
+            return Err(ErrorKind::Internal("Value cannot be indexed.".to_string()).into());
+        };
+        let object = object.borrow();
+        let len = object.values().len();
+        let bound =
+            |value: Option<Value>, default: usize| match value.map(|v| v.deref_value().unpack()) {
+                Some(Unpacked::Integer(i)) => i as usize,
+                _ => default,
+            };
+        let (from, to) = (bound(from, 0), bound(to, len));
+        if from > to || to > len {
+            return Err(ErrorKind::InvalidSlice { from, to, len }.into());
+        }
+        Ok(Value::from(self.heap.alloc_object(Object {
+            values: object.values()[from..to].to_vec(),
+            type_id: object.type_id(),
+        })))
+    }
+
+    fn index_of(index: Value) -> Result<i64, RuntimeError> {
+        match index.deref_value().unpack() {
+            Unpacked::Integer(i) => Ok(i),
+            _ => Err(
+                ErrorKind::Internal("Unexpected value for indexing operation.".to_string()).into(),
+            ),
+        }
+    }
+
+    fn array_len(value: &Value) -> Result<usize, RuntimeError> {
+        match value.clone().deref_value().as_object() {
+            Some(object) => Ok(object.borrow().values().len()),
+            None => Err(ErrorKind::Internal("Value cannot be indexed.".to_string()).into()),
+        }
+    }
+
+    pub(super) fn check_index(i: i64, len: usize) -> Result<usize, ErrorKind> {
+        if i < 0 {
+            Err(ErrorKind::NegativeIndex { index: i, len })
+        } else if i as usize >= len {
+            Err(ErrorKind::IndexOutOfBounds { index: i, len })
+        } else {
+            Ok(i as usize)
+        }
+    }
+}
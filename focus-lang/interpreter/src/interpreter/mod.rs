@@ -1,225 +1,293 @@
-mod block;
+mod call;
+mod cast;
 mod closure;
-mod literal;
+mod index;
 mod operation;
-mod resolve_type;
-mod stmt;
-mod r#struct;
+mod reference;
+mod vm;
 
-mod call;
-mod expr;
 #[cfg(test)]
 mod tests;
 #[cfg(target_arch = "wasm32")]
 mod wasm;
 
 use std::collections::HashMap;
-use std::{cell::RefCell, collections::HashSet, rc::Rc};
+use std::rc::Rc;
 
-use crate::context::Context;
+use crate::bytecode::compile_stmt;
+use crate::checker::{Global, Initialized, TypeChecker};
+use crate::context::{Context, Local};
+use crate::error::{ErrorKind, InterpreterError, RuntimeError};
+use crate::heap::{Heap, HeapStats};
+use crate::limits::{Budget, Limits};
 use crate::module::Module;
-use crate::object::Value;
-use crate::object::ValueRef;
-use crate::r#type::TypeRegistry;
+use crate::object::{Value, ValueRef};
+use crate::r#type::{TypeId, TypeRegistry};
 use crate::stack::ValueStack;
+use crate::stdlib;
+use crate::typed::{Binding, TypedStmt};
 use parser::{
-    stmt::{Expression, Stmt},
+    stmt::{Span, Stmt},
     Parser, ParserError, ParserErrorInfo,
 };
 
 #[cfg(target_arch = "wasm32")]
 use wasm_bindgen::prelude::wasm_bindgen;
 
-use crate::object::Function;
-
-pub enum InterpreterError {
-    ParserError(ParserError),
-}
-
-impl From<ParserError> for InterpreterError {
-    fn from(value: ParserError) -> Self {
-        InterpreterError::ParserError(value)
-    }
-}
-
 #[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
 pub struct Interpreter {
     stack: ValueStack,
-    context: Rc<RefCell<Context>>,
+    heap: Heap,
+    context: Context,
+    /// The names of the top level, in the order of their slots. A name
+    /// shadowed by a later one keeps its slot.
+    globals: Vec<Local>,
     type_registry: TypeRegistry,
     modules: HashMap<String, Rc<Module>>,
+    /// The places being assigned to or referenced, see [`ValueRef`].
+    places: Vec<ValueRef>,
+    /// The names of the top level declared without a value, with whether
+    /// they have been assigned one since.
+    deferred_globals: HashMap<String, Initialized>,
+    limits: Limits,
+    /// What is left of the limits for the code being run.
+    budget: Budget,
+}
+
+/// A type checked statement of the top level.
+struct CheckedStmt {
+    stmt: TypedStmt,
+    defined_at: Span,
+    /// The names of the top level declared without a value, with whether
+    /// they are initialized once the statement has run.
+    deferred_globals: HashMap<String, Initialized>,
 }
 
 #[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
 impl Interpreter {
     pub fn new() -> Self {
         let module = Rc::new(Module::new("Main"));
+        let mut type_registry = TypeRegistry::new();
+        let std = Rc::new(stdlib::std_module(&mut type_registry));
         Interpreter {
             stack: Default::default(),
-            context: Rc::new(RefCell::new(Context::new(module.clone()))),
-            type_registry: TypeRegistry::new(),
-            modules: HashMap::from([("Main".to_string(), module)]),
+            heap: Heap::default(),
+            context: Context::new(module.clone(), 0),
+            globals: Vec::new(),
+            type_registry,
+            modules: HashMap::from([("Main".to_string(), module), ("Std".to_string(), std)]),
+            places: Vec::new(),
+            deferred_globals: HashMap::new(),
+            limits: Limits::default(),
+            budget: Budget::new(Limits::default()),
         }
     }
 }
 
+impl Default for Interpreter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Interpreter {
-    pub fn interpret_str(&mut self, code: &str) -> Result<Value, String> {
+    /// Interprets every statement in `code`. All of them are type checked
+    /// before any of them runs, so a type error anywhere means nothing runs,
+    /// which is returned as [`InterpreterError::TypeError`]. Otherwise they
+    /// run until the first error.
+    pub fn interpret_str(&mut self, code: &str) -> Result<Value, InterpreterError> {
+        let checked_stmts = self.check_str(code)?;
+        self.budget = Budget::new(self.limits);
+        let mut value = Value::UNIT;
+        for checked in checked_stmts {
+            value = self
+                .execute_top_level(&checked.stmt, Some(code))
+                .map_err(|err| err.with_span(&checked.defined_at))?;
+            self.deferred_globals = checked.deferred_globals;
+            self.heap.maybe_collect();
+        }
+        Ok(value)
+    }
+
+    /// Type checks every statement in `code` without running any of them, and
+    /// returns them lowered to the [typed tree](crate::typed). The names they
+    /// declare are not defined afterwards, but the types they declare are.
+    pub fn lower_str(&mut self, code: &str) -> Result<Vec<TypedStmt>, InterpreterError> {
+        let checked_stmts = self.check_str(code)?;
+        Ok(checked_stmts
+            .into_iter()
+            .map(|checked| checked.stmt)
+            .collect())
+    }
+
+    /// Gets the name of a type of the typed tree, like `int` or `( 'a -> 'a )`.
+    pub fn type_name(&self, type_id: TypeId) -> String {
+        self.type_registry.type_name(type_id)
+    }
+
+    /// Parses and type checks every statement in `code`.
+    fn check_str(&mut self, code: &str) -> Result<Vec<CheckedStmt>, InterpreterError> {
         let mut parser = Parser::new(code, None);
-        let mut stmt;
-        let mut value = Ok(Value::Unit);
+        let mut stmts = Vec::new();
         loop {
-            stmt = parser.parse_error_details();
-            match stmt {
-                Ok(stmt) => value = self.interpret_stmt(stmt.stmt),
+            match parser.parse_error_details() {
+                Ok(stmt) => stmts.push(stmt),
                 Err(ParserError {
                     info: ParserErrorInfo::Eof,
                     ..
                 }) => break,
-                Err(e) => return Err(e.to_string()),
+                Err(e) => return Err(e.into()),
             }
         }
-        value
+
+        let mut checker = self.type_checker();
+        let mut typed_stmts = Vec::with_capacity(stmts.len());
+        for stmt in stmts {
+            let typed_stmt = checker
+                .check_stmt(stmt.stmt)
+                .map_err(|err| InterpreterError::TypeError(err.with_span(&stmt.defined_at)))?;
+            typed_stmts.push((typed_stmt, stmt.defined_at));
+        }
+        // Types can be inferred from uses in later statements, so they are
+        // only filled in once every statement is checked.
+        typed_stmts
+            .into_iter()
+            .map(|(mut stmt, defined_at)| {
+                checker
+                    .finish_stmt(&mut stmt)
+                    .map_err(|err| InterpreterError::TypeError(err.with_span(&defined_at)))?;
+                Ok(CheckedStmt {
+                    stmt,
+                    defined_at,
+                    deferred_globals: checker.deferred_globals(),
+                })
+            })
+            .collect()
     }
 
-    fn resolve_path(&mut self, path: String) -> Result<ValueRef, String> {
-        let mut path_parts = path.split('.');
-        let root = path_parts.next().ok_or("Invalid path.".to_string())?;
+    /// Type checks a statement and runs it.
+    pub fn interpret_stmt(&mut self, stmt: Stmt) -> Result<Value, InterpreterError> {
+        let mut checker = self.type_checker();
+        let mut stmt = checker
+            .check_stmt(stmt)
+            .map_err(InterpreterError::TypeError)?;
+        checker
+            .finish_stmt(&mut stmt)
+            .map_err(InterpreterError::TypeError)?;
+        let deferred_globals = checker.deferred_globals();
+        self.budget = Budget::new(self.limits);
+        let value = self.execute_top_level(&stmt, None)?;
+        self.deferred_globals = deferred_globals;
+        self.heap.maybe_collect();
+        Ok(value)
+    }
 
-        let local = self
-            .context
-            .borrow()
-            .find_local(&root)
-            .ok_or(format!("Unknown path {path}."))?;
+    /// Frees the values on the heap that are only kept alive by cycles of
+    /// references between them, like a recursive function defined in another
+    /// function, returning how many were freed. This also happens on its own
+    /// between statements once the heap has grown enough.
+    pub fn collect_garbage(&mut self) -> usize {
+        self.heap.collect()
+    }
 
-        let mut value = self.stack.get_value(local.sp.unwrap()).unwrap();
-        let mut value_type = self.type_registry.get_type_from_id(local.ty).unwrap();
-        let mut value_ref = ValueRef::StackRef {
-            sp: local.sp.unwrap(),
+    pub fn heap_stats(&self) -> HeapStats {
+        self.heap.stats()
+    }
+
+    pub fn limits(&self) -> Limits {
+        self.limits
+    }
+
+    /// Sets the limits for the code run from now on.
+    pub fn set_limits(&mut self, limits: Limits) {
+        self.limits = limits;
+    }
+
+    /// A type checker that knows the names defined by the code run so far,
+    /// in the slots they take on the stack.
+    fn type_checker(&mut self) -> TypeChecker<'_> {
+        let globals = self.globals.iter().map(|local| Global {
+            ident: local.ident.clone(),
             type_id: local.ty,
+            mutable: local.mutable,
+            initialized: self.deferred_globals.get(&local.ident).copied(),
+        });
+        TypeChecker::new(&mut self.type_registry, &self.modules, globals)
+    }
+
+    /// Compiles a statement of the top level, parsed from `source` if it is
+    /// known, to bytecode and runs it. Its names are defined once it
+    /// succeeds. If it fails, the values it pushed are dropped, so the names
+    /// of the top level stay in the slots the checker gives them.
+    fn execute_top_level(
+        &mut self,
+        stmt: &TypedStmt,
+        source: Option<&str>,
+    ) -> Result<Value, RuntimeError> {
+        let chunk = compile_stmt(stmt, source, &self.modules)?;
+        let len = self.stack.len();
+        let result = self.run(Rc::new(chunk));
+        if result.is_err() {
+            self.stack.truncate(len);
+            return result;
+        }
+
+        let lets = match stmt {
+            TypedStmt::Let(l) => std::slice::from_ref(l),
+            TypedStmt::LetRec(lets) => lets.as_slice(),
+            _ => &[],
         };
+        for l in lets {
+            debug_assert_eq!(
+                l.slot,
+                self.globals.len(),
+                "Slot of {} not in line.",
+                l.ident
+            );
+            self.globals.push(Local {
+                ident: l.ident.clone(),
+                ty: l.type_id,
+                mutable: l.mutable,
+            });
+        }
+        result
+    }
 
-        for p in path_parts {
-            let fields = value_type.as_struct().ok_or("Invalid path.".to_string())?;
-
-            let (offset, field) = fields
-                .iter()
-                .enumerate()
-                .find(|f| f.1.ident == p)
-                .ok_or(format!("Invalid field name {p}"))?;
-
-            let field_type = self
-                .type_registry
-                .get_type_from_id(field.type_id)
-                .expect(&format!("Type with id {} not found", field.type_id));
-
-            match value {
-                Value::Object(object) => {
-                    value = object.borrow().get_value(offset).unwrap();
-                    value_type = field_type;
-                    value_ref = ValueRef::ObjectRef {
-                        object: object.clone(),
-                        index: offset,
-                        type_id: value_type.type_id,
-                    }
-                }
-                _ => return Err("Expected struct, found another type.".to_string()),
+    /// Finds the position on the stack of the value a local or a captured
+    /// name refers to.
+    fn resolve_binding(&self, binding: &Binding) -> Result<usize, RuntimeError> {
+        match binding {
+            Binding::Local { depth, slot } => Ok(self.stack.local_sp(*depth, *slot)),
+            Binding::Capture { index } => Ok(self.context.capture_sp(*index)),
+            Binding::ModuleItem(path) => {
+                Err(ErrorKind::Internal(format!("{path} is not a local.")).into())
             }
         }
+    }
 
-        Ok(value_ref)
-    }
-
-    fn find_names_to_capture(&mut self, closure: &Function) -> Vec<String> {
-        let mut names_referenced = HashSet::new();
-        let mut names_defined = closure.args.iter().cloned().collect::<HashSet<_>>();
-        self.get_referenced_names_in_expr(&closure.expr, &mut names_referenced, &mut names_defined);
-        names_referenced.into_iter().collect()
-    }
-
-    fn get_referenced_names_in_stmt(
-        &self,
-        stmt: &Stmt,
-        names: &mut HashSet<String>,
-        defined: &mut HashSet<String>,
-    ) {
-        match stmt {
-            Stmt::Item(_) => {}
-            Stmt::Let(l) => {
-                defined.insert(l.ident.clone());
-                l.expr
-                    .as_ref()
-                    .map(|v| self.get_referenced_names_in_expr(&v, names, defined));
-            }
-            Stmt::Expr(expr) => self.get_referenced_names_in_expr(expr, names, defined),
+    /// Reads the value of a place. A variable captured by a function is read
+    /// from the heap, so the value is never a reference to it.
+    fn read_place(&self, place: &ValueRef) -> Value {
+        match place {
+            ValueRef::StackRef { sp, path } => self
+                .stack
+                .get_value(*sp)
+                .expect("Unable to find registered local.")
+                .get_at(path),
+            ValueRef::Reference(reference) => reference.get(),
         }
     }
 
-    fn get_referenced_names_in_expr(
-        &self,
-        expr: &Expression,
-        names: &mut HashSet<String>,
-        defined: &mut HashSet<String>,
-    ) {
-        match expr {
-            Expression::Literal(_) => {}
-            Expression::Path(path) => {
-                if !defined.contains(path) && self.context.borrow().is_local(path) {
-                    names.insert(path.clone());
-                }
-            }
-            Expression::Operation(operation) => {
-                self.get_referenced_names_in_expr(&operation.lhs, names, defined);
-                self.get_referenced_names_in_expr(&operation.rhs, names, defined);
-            }
-            Expression::Call(_) => {}
-            Expression::Struct(_) => {}
-            Expression::Range(range) => {
-                range
-                    .from
-                    .as_ref()
-                    .map(|v| self.get_referenced_names_in_expr(&v, names, defined));
-                range
-                    .to
-                    .as_ref()
-                    .map(|v| self.get_referenced_names_in_expr(&v, names, defined));
-            }
-            Expression::Array(array) => {
-                for v in array {
-                    self.get_referenced_names_in_expr(&v, names, defined);
-                }
-            }
-            Expression::Index(index) => {
-                self.get_referenced_names_in_expr(&index.index, names, defined);
-            }
-            Expression::IfElse(if_else) => {
-                self.get_referenced_names_in_expr(&if_else.condition, names, defined);
-                self.get_referenced_names_in_expr(&if_else.if_expr, names, defined);
-                if_else
-                    .else_expr
-                    .as_ref()
-                    .map(|v| self.get_referenced_names_in_expr(&v, names, defined));
-            }
-            Expression::Match(m) => {
-                self.get_referenced_names_in_expr(&m.match_expr, names, defined);
-                for m in &m.branches {
-                    self.get_referenced_names_in_expr(&m.expr, names, defined);
-                    m.if_expr
-                        .as_ref()
-                        .map(|v| self.get_referenced_names_in_expr(&v, names, defined));
-                    self.get_referenced_names_in_expr(&m.match_expr, names, defined);
-                }
-            }
-            Expression::For(f) => {
-                self.get_referenced_names_in_expr(&f.in_expr, names, defined);
-                self.get_referenced_names_in_expr(&f.block, names, defined);
-            }
-            Expression::Block(block) => {
-                for stmt in block {
-                    self.get_referenced_names_in_stmt(stmt, names, defined);
-                }
-            }
-            Expression::Closure(_) => {}
+    /// Assigns a value to a place, copying the structs and arrays on the way
+    /// to it that are shared with other values.
+    fn write_place(&mut self, place: ValueRef, value: Value) {
+        match place {
+            ValueRef::StackRef { sp, path } => self
+                .stack
+                .get_value_mut(sp)
+                .expect("Unable to find registered local.")
+                .set_at(&path, value, &mut self.heap),
+            ValueRef::Reference(reference) => reference.set(value, &mut self.heap),
         }
     }
 }
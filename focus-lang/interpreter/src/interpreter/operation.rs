@@ -1,80 +1,78 @@
-use parser::{
-    op::{ArithmeticOperator, BooleanOperator, ComparisonOperator},
-    stmt::{Expression, Operation, Operator},
-};
+use std::cmp::Ordering;
+
+use parser::op::{ArithmeticOperator, BooleanOperator, ComparisonOperator};
 
 use crate::{
-    object::{Value, ValueRef},
-    Interpreter,
+    error::ErrorKind,
+    object::{Unpacked, Value},
 };
 
-impl Interpreter {
-    pub(super) fn interpret_operation(&mut self, operation: Operation) -> Result<Value, String> {
-        let t1 = self.resolve_expr_type(&operation.lhs, None)?;
-        let lhs = self.interpret_expression(*operation.lhs.clone())?;
-        let t2 = self.resolve_expr_type(&operation.rhs, None)?;
-        let rhs = self.interpret_expression(*operation.rhs)?;
-
-        if !self.type_registry.are_types_equal(t1, t2)? {
-            return Err("Invalid type in operation".to_string());
+#[inline]
+pub(super) fn arithmetic(
+    operator: &ArithmeticOperator,
+    lhs: Value,
+    rhs: Value,
+) -> Result<Value, ErrorKind> {
+    // `int` is the most common case, which skips the checks of the others.
+    // Overflows and divisions by zero fall through for their errors.
+    if let (Some(l), Some(r)) = (lhs.as_immediate_integer(), rhs.as_immediate_integer()) {
+        let value = match operator {
+            ArithmeticOperator::Add => l.checked_add(r),
+            ArithmeticOperator::Sub => l.checked_sub(r),
+            ArithmeticOperator::Mul => l.checked_mul(r),
+            ArithmeticOperator::Div => l.checked_div(r),
+            ArithmeticOperator::Mod => l.checked_rem(r),
+        };
+        if let Some(value) = value {
+            return Ok(Value::from(value));
         }
+    }
+    match operator {
+        ArithmeticOperator::Add => lhs.add(rhs),
+        ArithmeticOperator::Sub => lhs.sub(rhs),
+        ArithmeticOperator::Mul => lhs.mul(rhs),
+        ArithmeticOperator::Div => lhs.div(rhs),
+        ArithmeticOperator::Mod => lhs.rem(rhs),
+    }
+}
 
-        match operation.op {
-            Operator::Arithmetic(operator) => match operator {
-                ArithmeticOperator::Add => {
-                    Ok(lhs.add(rhs).ok_or("Unable to add values.".to_string())?)
-                }
-                ArithmeticOperator::Sub => Ok(lhs
-                    .sub(rhs)
-                    .ok_or("Unable to subtract values.".to_string())?),
-                ArithmeticOperator::Mul => Ok(lhs
-                    .mul(rhs)
-                    .ok_or("Unable to multiply values.".to_string())?),
-                ArithmeticOperator::Div => {
-                    Ok(lhs.div(rhs).ok_or("Unable to divide values.".to_string())?)
-                }
-                ArithmeticOperator::Mod => todo!(),
-            },
-            Operator::Comparison(comparison) => match comparison {
-                ComparisonOperator::Equal => Ok(Value::Boolean(lhs.are_equal(&rhs))),
-                ComparisonOperator::NotEqual => Ok(Value::Boolean(!lhs.are_equal(&rhs))),
-                ComparisonOperator::Greater => todo!(),
-                ComparisonOperator::Less => todo!(),
-                ComparisonOperator::GreaterEqual => todo!(),
-                ComparisonOperator::LessEqual => todo!(),
-            },
-            Operator::Boolean(boolean) => match boolean {
-                BooleanOperator::Or => match (lhs, rhs) {
-                    (Value::Boolean(false), Value::Boolean(false)) => Ok(Value::Boolean(false)),
-                    _ => Ok(Value::Boolean(true)),
-                },
-                BooleanOperator::And => match (lhs, rhs) {
-                    (Value::Boolean(true), Value::Boolean(true)) => Ok(Value::Boolean(true)),
-                    _ => Ok(Value::Boolean(false)),
-                },
-            },
-            Operator::Assignment => {
-                let Expression::Path(path) = *operation.lhs else {
-                    return Err(format!(
-                        "Invalid left hand side expression {}.",
-                        operation.lhs.name()
-                    ));
-                };
-
-                let value_ref = self.resolve_path(path)?;
-
-                match value_ref {
-                    ValueRef::StackRef { sp, .. } => {
-                        self.stack.set_value(sp, rhs);
-                    }
-                    ValueRef::ObjectRef { object, index, .. } => {
-                        object.borrow_mut().set_value(index, rhs);
-                    }
-                }
-
-                Ok(Value::Unit)
-            }
-            Operator::CompoundAssignment(_) => todo!(),
+#[inline]
+pub(super) fn comparison(
+    operator: &ComparisonOperator,
+    lhs: &Value,
+    rhs: &Value,
+) -> Result<bool, ErrorKind> {
+    let ordering = match (lhs.as_immediate_integer(), rhs.as_immediate_integer()) {
+        (Some(l), Some(r)) => Some(l.cmp(&r)),
+        _ => match operator {
+            ComparisonOperator::Equal => return Ok(lhs.are_equal(rhs)),
+            ComparisonOperator::NotEqual => return Ok(!lhs.are_equal(rhs)),
+            _ => lhs.compare(rhs)?,
+        },
+    };
+    Ok(match operator {
+        ComparisonOperator::Equal => matches!(ordering, Some(Ordering::Equal)),
+        ComparisonOperator::NotEqual => !matches!(ordering, Some(Ordering::Equal)),
+        ComparisonOperator::Greater => matches!(ordering, Some(Ordering::Greater)),
+        ComparisonOperator::Less => matches!(ordering, Some(Ordering::Less)),
+        ComparisonOperator::GreaterEqual => {
+            matches!(ordering, Some(Ordering::Greater | Ordering::Equal))
         }
+        ComparisonOperator::LessEqual => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+    })
+}
+
+/// Both operands are evaluated before the operator is applied, so neither
+/// of them is skipped.
+pub(super) fn boolean(operator: &BooleanOperator, lhs: Value, rhs: Value) -> Value {
+    match operator {
+        BooleanOperator::Or => match (lhs.unpack(), rhs.unpack()) {
+            (Unpacked::Boolean(false), Unpacked::Boolean(false)) => Value::from(false),
+            _ => Value::from(true),
+        },
+        BooleanOperator::And => match (lhs.unpack(), rhs.unpack()) {
+            (Unpacked::Boolean(true), Unpacked::Boolean(true)) => Value::from(true),
+            _ => Value::from(false),
+        },
     }
 }
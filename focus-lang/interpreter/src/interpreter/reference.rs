@@ -0,0 +1,36 @@
+use std::rc::Rc;
+
+use crate::{
+    error::{ErrorKind, RuntimeError},
+    object::{Reference, Unpacked, Value, ValueRef},
+    Interpreter,
+};
+
+impl Interpreter {
+    /// Creates a reference to a place. The value of the name the place starts
+    /// with is moved to the heap, like a captured name, so the reference can
+    /// outlive the scope of the name. A temporary place already is on the
+    /// heap.
+    pub(super) fn resolve_reference(&mut self, place: ValueRef) -> Result<Reference, RuntimeError> {
+        match place {
+            ValueRef::StackRef { sp, path } => {
+                let value = self
+                    .stack
+                    .box_value(sp, &mut self.heap)
+                    .ok_or(ErrorKind::Internal(
+                        "Unable to get value from stack.".to_string(),
+                    ))?;
+                Ok(Reference { value, path })
+            }
+            ValueRef::Reference(reference) => Ok(reference),
+        }
+    }
+}
+
+/// Gets the place a reference refers to.
+pub(super) fn resolve_deref(reference: Value) -> Result<Reference, RuntimeError> {
+    match reference.deref_value().unpack() {
+        Unpacked::Reference(reference) => Ok(Rc::unwrap_or_clone(reference)),
+        _ => Err(ErrorKind::Internal("Value is not a reference.".to_string()).into()),
+    }
+}
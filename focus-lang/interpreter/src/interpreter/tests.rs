@@ -1,4 +1,20 @@
-use crate::{object::Value, Interpreter};
+use std::time::Duration;
+
+use diagnostic::Renderer;
+use parser::{op::ArithmeticOperator, IntegerKind};
+
+use crate::{
+    explain,
+    object::{Unpacked, Value},
+    ErrorKind, Interpreter, InterpreterError, Limits, StackFrame,
+};
+
+fn error_kind(err: InterpreterError) -> ErrorKind {
+    match err {
+        InterpreterError::TypeError(err) | InterpreterError::RuntimeError(err) => err.kind,
+        InterpreterError::ParserError(err) => panic!("Unexpected parser error: {err}"),
+    }
+}
 
 #[test]
 fn add() {
@@ -11,7 +27,7 @@ fn add() {
         "#,
         )
         .expect("Unable to interpret.");
-    assert_eq!(value, Value::Integer(5));
+    assert_eq!(value, Value::from(5));
 }
 
 #[test]
@@ -21,12 +37,14 @@ fn assign() {
         .interpret_str(
             r#"
         type Point = {x: int, y: int}
-        let a = Point {x: 1, y: 2}
-        a = 2
+        let mut a = Point {x: 1, y: 2}
+        let b = Point {x: 5, y: 6}
+        a = b
+        a.x
         "#,
         )
         .expect("Unable to interpret.");
-    assert_eq!(value, Value::Integer(5));
+    assert_eq!(value, Value::from(5));
 }
 
 #[test]
@@ -40,7 +58,7 @@ fn function_w_closure_arg() {
         "#,
         )
         .expect("Unable to interpret function.");
-    assert_eq!(value, Value::Integer(4))
+    assert_eq!(value, Value::from(4))
 }
 
 #[test]
@@ -50,7 +68,7 @@ fn closure_w_captured_local() {
         .interpret_str(
             r#"
         let a: (() -> (() -> int)) = 
-            let index = 0
+            let mut index = 0
             fn -> 
                 index = index + 1
                 index
@@ -61,7 +79,7 @@ fn closure_w_captured_local() {
         )
         .expect("Unable to interpret.");
     println!("{value:?}");
-    assert_eq!(value.deref_value(), Value::Integer(2));
+    assert_eq!(value.deref_value(), Value::from(2));
 }
 
 #[test]
@@ -80,7 +98,7 @@ fn complex_type_path() {
         )
         .expect("Unable to interpret.");
     println!("{value:?}");
-    assert_eq!(value.deref_value(), Value::Integer(1));
+    assert_eq!(value.deref_value(), Value::from(1));
 }
 
 #[test]
@@ -94,5 +112,1618 @@ fn array() {
     "#,
         )
         .expect("Unable to interpret.");
-    assert_eq!(value, Value::Integer(2));
+    assert_eq!(value, Value::from(2));
+}
+
+#[test]
+fn index_assign() {
+    let mut interpreter = Interpreter::new();
+    let value = interpreter
+        .interpret_str(
+            r#"
+    let mut grid = [[1, 2], [3, 4]]
+    grid[1][0] = grid[0][1] + 5
+    grid[1][0]
+    "#,
+        )
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(7));
+}
+
+#[test]
+fn index_fields() {
+    let mut interpreter = Interpreter::new();
+    let value = interpreter
+        .interpret_str(
+            r#"
+    type Point = {mut x: int, mut ys: [int]}
+    let mut ps = [Point {x: 1, ys: [2, 3]}, Point {x: 4, ys: [5, 6]}]
+    ps[1].x = ps[0].x + 10
+    ps[0].ys[1] = ps[1].ys[0]
+    ps[1].x + ps[0].ys[1]
+    "#,
+        )
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(16));
+}
+
+#[test]
+fn index_evaluation_order() {
+    let mut interpreter = Interpreter::new();
+    // The array is read before the index changes it.
+    let value = interpreter
+        .interpret_str(
+            r#"
+    let mut xs = [1, 2]
+    let first u: (() -> int) =
+        xs[0] = 5
+        0
+    xs[first ()] + xs[0]
+    "#,
+        )
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(6));
+}
+
+#[test]
+fn array_slice() {
+    let mut interpreter = Interpreter::new();
+    let value = interpreter
+        .interpret_str(
+            r#"
+    let a = [1, 2, 3, 4]
+    let mut b = a[1..3]
+    b[0] = 10
+    a[1] + b[0] + a[2..][1]
+    "#,
+        )
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(16));
+}
+
+#[test]
+fn index_out_of_bounds() {
+    let mut interpreter = Interpreter::new();
+    let err = interpreter
+        .interpret_str(
+            r#"
+    let mut a = [1, 2]
+    a[2] = 3
+    "#,
+        )
+        .expect_err("Index should be out of bounds.");
+    assert_eq!(
+        error_kind(err),
+        ErrorKind::IndexOutOfBounds { index: 2, len: 2 }
+    );
+
+    let err = interpreter
+        .interpret_str("a[1..3]")
+        .expect_err("Slice should be out of bounds.");
+    assert_eq!(
+        error_kind(err),
+        ErrorKind::InvalidSlice {
+            from: 1,
+            to: 3,
+            len: 2
+        }
+    );
+}
+
+#[test]
+fn negative_index() {
+    let mut interpreter = Interpreter::new();
+    let err = interpreter
+        .interpret_str(
+            r#"
+    let a = [1, 2]
+    let i = 0 - 1
+    a[i]
+    "#,
+        )
+        .expect_err("Negative index should fail.");
+    assert_eq!(
+        error_kind(err),
+        ErrorKind::NegativeIndex { index: -1, len: 2 }
+    );
+}
+
+#[test]
+fn compound_assignment() {
+    let mut interpreter = Interpreter::new();
+    let value = interpreter
+        .interpret_str(
+            r#"
+    type Point = {mut x: int, y: int}
+    let mut p = Point {x: 1, y: 2}
+    let mut a = [1, 2, 3]
+    let mut i = 10
+    i += 5
+    i -= 1
+    i *= 3
+    i /= 2
+    i %= 8
+    p.x += i
+    a[2] *= p.x
+    a[2]
+    "#,
+        )
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(18));
+}
+
+#[test]
+fn comparisons() {
+    let mut interpreter = Interpreter::new();
+    let value = interpreter
+        .interpret_str(
+            r#"
+    let a = 1 < 2 && 2 <= 2 && 3 > 2 && 3 >= 3
+    let b = 1.5 < 0.5 || 'a' > 'b'
+    a && b == false
+    "#,
+        )
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(true));
+
+    let err = interpreter
+        .interpret_str("true < false")
+        .expect_err("Booleans should not be ordered.");
+    assert_eq!(
+        error_kind(err),
+        ErrorKind::NotOrdered {
+            found: "bool".to_string()
+        }
+    );
+}
+
+#[test]
+fn modulo() {
+    let mut interpreter = Interpreter::new();
+    let value = interpreter
+        .interpret_str("(0 - 7) % 3")
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(-1));
+    let value = interpreter
+        .interpret_str("7.5 % 2.0")
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(1.5));
+}
+
+#[test]
+fn integer_overflow() {
+    let mut interpreter = Interpreter::new();
+    let err = interpreter
+        .interpret_str("9223372036854775807 + 1")
+        .expect_err("Addition should overflow.");
+    assert_eq!(
+        error_kind(err),
+        ErrorKind::IntegerOverflow {
+            operator: ArithmeticOperator::Add,
+            lhs: i64::MAX as i128,
+            rhs: 1
+        }
+    );
+}
+
+#[test]
+fn division_by_zero() {
+    let mut interpreter = Interpreter::new();
+    let err = interpreter
+        .interpret_str("1 / 0")
+        .expect_err("Division by zero should fail.");
+    assert_eq!(error_kind(err), ErrorKind::DivisionByZero);
+    let err = interpreter
+        .interpret_str("1 % 0")
+        .expect_err("Division by zero should fail.");
+    assert_eq!(error_kind(err), ErrorKind::DivisionByZero);
+    let value = interpreter
+        .interpret_str("1.0 / 0.0")
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(f64::INFINITY));
+}
+
+#[test]
+fn numeric_casts() {
+    let mut interpreter = Interpreter::new();
+    let value = interpreter
+        .interpret_str(
+            r#"
+    let a = 3
+    a as float / 2.0 + 'a' as int as float
+    "#,
+        )
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(98.5));
+
+    let value = interpreter
+        .interpret_str("(0.0 - 2.7) as int")
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(-2));
+
+    let err = interpreter
+        .interpret_str("1.0 + a")
+        .expect_err("Implicit conversion should fail.");
+    assert_eq!(
+        error_kind(err),
+        ErrorKind::MismatchedOperands {
+            lhs: "float".to_string(),
+            rhs: "int".to_string()
+        }
+    );
+
+    let err = interpreter
+        .interpret_str("true as int")
+        .expect_err("Booleans cannot be cast.");
+    assert_eq!(
+        error_kind(err),
+        ErrorKind::InvalidCast {
+            from: "bool".to_string(),
+            to: "int".to_string()
+        }
+    );
+}
+
+#[test]
+fn sized_integers() {
+    let mut interpreter = Interpreter::new();
+    let value = interpreter
+        .interpret_str(
+            r#"
+    let a: byte = 200u8
+    let b: u8 = 55u8
+    a + b
+    "#,
+        )
+        .expect("Unable to interpret.");
+    assert_eq!(
+        value,
+        Value::from(Unpacked::SizedInteger(255, IntegerKind::U8))
+    );
+
+    let err = interpreter
+        .interpret_str("a + b + 1u8")
+        .expect_err("Addition should overflow.");
+    assert_eq!(
+        error_kind(err),
+        ErrorKind::IntegerOverflow {
+            operator: ArithmeticOperator::Add,
+            lhs: 255,
+            rhs: 1
+        }
+    );
+
+    let err = interpreter
+        .interpret_str("0u32 - 1u32")
+        .expect_err("Subtraction should overflow.");
+    assert_eq!(
+        error_kind(err),
+        ErrorKind::IntegerOverflow {
+            operator: ArithmeticOperator::Sub,
+            lhs: 0,
+            rhs: 1
+        }
+    );
+
+    let err = interpreter
+        .interpret_str("a + 1")
+        .expect_err("Integer kinds should not mix.");
+    assert_eq!(
+        error_kind(err),
+        ErrorKind::MismatchedOperands {
+            lhs: "u8".to_string(),
+            rhs: "int".to_string()
+        }
+    );
+
+    let value = interpreter
+        .interpret_str("18446744073709551615u64 > 1u64")
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(true));
+}
+
+#[test]
+fn sized_integer_casts() {
+    let mut interpreter = Interpreter::new();
+    let value = interpreter
+        .interpret_str("200u8 as int + 1")
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(201));
+
+    let value = interpreter
+        .interpret_str("(0 - 128) as i8")
+        .expect("Unable to interpret.");
+    assert_eq!(
+        value,
+        Value::from(Unpacked::SizedInteger(-128, IntegerKind::I8))
+    );
+
+    let value = interpreter
+        .interpret_str("'a' as byte")
+        .expect("Unable to interpret.");
+    assert_eq!(
+        value,
+        Value::from(Unpacked::SizedInteger(97, IntegerKind::U8))
+    );
+
+    let value = interpreter
+        .interpret_str("2.9 as u16")
+        .expect("Unable to interpret.");
+    assert_eq!(
+        value,
+        Value::from(Unpacked::SizedInteger(2, IntegerKind::U16))
+    );
+
+    let err = interpreter
+        .interpret_str("300 as u8")
+        .expect_err("Cast should be out of range.");
+    assert_eq!(
+        error_kind(err),
+        ErrorKind::ValueOutOfRange {
+            value: "300".to_string(),
+            ty: "u8".to_string()
+        }
+    );
+
+    let err = interpreter
+        .interpret_str("(0 - 1) as u64")
+        .expect_err("Cast should be out of range.");
+    assert_eq!(
+        error_kind(err),
+        ErrorKind::ValueOutOfRange {
+            value: "-1".to_string(),
+            ty: "u64".to_string()
+        }
+    );
+}
+
+#[test]
+fn std_integer_arithmetic() {
+    let mut interpreter = Interpreter::new();
+    let value = interpreter
+        .interpret_str("Std.U8.wrapping_add 250u8 10u8")
+        .expect("Unable to interpret.");
+    assert_eq!(
+        value,
+        Value::from(Unpacked::SizedInteger(4, IntegerKind::U8))
+    );
+
+    let value = interpreter
+        .interpret_str("Std.I8.wrapping_mul 100i8 3i8")
+        .expect("Unable to interpret.");
+    assert_eq!(
+        value,
+        Value::from(Unpacked::SizedInteger(44, IntegerKind::I8))
+    );
+
+    let value = interpreter
+        .interpret_str("Std.U8.saturating_sub 5u8 10u8")
+        .expect("Unable to interpret.");
+    assert_eq!(
+        value,
+        Value::from(Unpacked::SizedInteger(0, IntegerKind::U8))
+    );
+
+    let value = interpreter
+        .interpret_str("Std.I64.saturating_mul 9223372036854775807 2")
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(i64::MAX));
+
+    let value = interpreter
+        .interpret_str("Std.U8.checked_add 250u8 5u8")
+        .expect("Unable to interpret.");
+    assert_eq!(
+        value,
+        Value::from(Unpacked::SizedInteger(255, IntegerKind::U8))
+    );
+
+    let value = interpreter
+        .interpret_str("Std.I16.checked_mul (0i16 - 128i16) 256i16")
+        .expect("Unable to interpret.");
+    assert_eq!(
+        value,
+        Value::from(Unpacked::SizedInteger(-32768, IntegerKind::I16))
+    );
+
+    let err = interpreter
+        .interpret_str("Std.U8.checked_add 250u8 10u8")
+        .expect_err("Addition should overflow.");
+    assert_eq!(
+        error_kind(err),
+        ErrorKind::IntegerOverflow {
+            operator: ArithmeticOperator::Add,
+            lhs: 250,
+            rhs: 10
+        }
+    );
+
+    let err = interpreter
+        .interpret_str("Std.U32.checked_sub 1u32 2u32")
+        .expect_err("Subtraction should overflow.");
+    assert_eq!(
+        error_kind(err),
+        ErrorKind::IntegerOverflow {
+            operator: ArithmeticOperator::Sub,
+            lhs: 1,
+            rhs: 2
+        }
+    );
+
+    let err = interpreter
+        .interpret_str("Std.I64.checked_mul 9223372036854775807 2")
+        .expect_err("Multiplication should overflow.");
+    assert_eq!(
+        error_kind(err),
+        ErrorKind::IntegerOverflow {
+            operator: ArithmeticOperator::Mul,
+            lhs: i64::MAX as i128,
+            rhs: 2
+        }
+    );
+
+    let err = interpreter
+        .interpret_str("Std.U8.wrapping_add 1 2")
+        .expect_err("Arguments should be type checked.");
+    assert_eq!(
+        error_kind(err),
+        ErrorKind::MismatchedTypes {
+            expected: "u8".to_string(),
+            found: "int".to_string()
+        }
+    );
+}
+
+#[test]
+fn big_integers() {
+    let mut interpreter = Interpreter::new();
+    let value = interpreter
+        .interpret_str(
+            r#"
+    let a: bigint = 9223372036854775807 as bigint
+    a * a + 1n
+    "#,
+        )
+        .expect("Unable to interpret.");
+    assert_eq!(
+        format!("{value:?}"),
+        "BigInteger(85070591730234615847396907784232501250)"
+    );
+
+    let value = interpreter
+        .interpret_str("(0n - 7n) / 2n == 0n - 3n && 7n % 2n == 1n && a > 1n")
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(true));
+
+    let err = interpreter
+        .interpret_str("a / 0n")
+        .expect_err("Division by zero should fail.");
+    assert_eq!(error_kind(err), ErrorKind::DivisionByZero);
+
+    let value = interpreter
+        .interpret_str("a as int")
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(i64::MAX));
+
+    let err = interpreter
+        .interpret_str("(a + 1n) as int")
+        .expect_err("Cast should be out of range.");
+    assert_eq!(
+        error_kind(err),
+        ErrorKind::ValueOutOfRange {
+            value: "9223372036854775808".to_string(),
+            ty: "int".to_string()
+        }
+    );
+}
+
+#[test]
+fn rationals() {
+    let mut interpreter = Interpreter::new();
+    let value = interpreter
+        .interpret_str("0.1r + 0.2r == 0.3r")
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(true));
+
+    let value = interpreter
+        .interpret_str("1r / 3r")
+        .expect("Unable to interpret.");
+    assert_eq!(format!("{value:?}"), "Rational(1/3)");
+
+    let value = interpreter
+        .interpret_str("(12.50r * 3r) as bigint")
+        .expect("Unable to interpret.");
+    assert_eq!(format!("{value:?}"), "BigInteger(37)");
+
+    let value = interpreter
+        .interpret_str("(5 as rational / 4r) as float")
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(1.25));
+
+    let value = interpreter
+        .interpret_str("0.5 as rational < 0.75r")
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(true));
+
+    let err = interpreter
+        .interpret_str("1r + 1n")
+        .expect_err("Numeric types should not mix.");
+    assert_eq!(
+        error_kind(err),
+        ErrorKind::MismatchedOperands {
+            lhs: "rational".to_string(),
+            rhs: "bigint".to_string()
+        }
+    );
+}
+
+#[test]
+fn error_spans() {
+    let mut interpreter = Interpreter::new();
+    let err = match interpreter.interpret_str("1 + 2 / 0") {
+        Err(InterpreterError::RuntimeError(err)) => err,
+        other => panic!("Expected a runtime error, found {other:?}"),
+    };
+    assert_eq!(err.code(), "E0302");
+    assert_eq!(err.span, Some(4..9));
+    assert!(err.stack_trace.is_empty());
+}
+
+#[test]
+fn error_stack_traces() {
+    let mut interpreter = Interpreter::new();
+    interpreter
+        .interpret_str("let f x: (int -> int) = x / 0")
+        .expect("Unable to interpret.");
+    interpreter
+        .interpret_str("let g x: (int -> int) = f x")
+        .expect("Unable to interpret.");
+    let err = match interpreter.interpret_str("g 1") {
+        Err(InterpreterError::RuntimeError(err)) => err,
+        other => panic!("Expected a runtime error, found {other:?}"),
+    };
+    assert_eq!(err.kind, ErrorKind::DivisionByZero);
+    assert_eq!(
+        *err.stack_trace,
+        [
+            StackFrame {
+                function: "g".to_string(),
+                call_site: 0..3,
+                position: Some((1, 1)),
+                repeated: 0,
+                omitted_calls: 0,
+            },
+            StackFrame {
+                function: "f".to_string(),
+                call_site: 24..27,
+                position: Some((1, 25)),
+                repeated: 0,
+                omitted_calls: 0,
+            },
+        ]
+    );
+    assert_eq!(
+        err.to_string(),
+        "error[E0302]: Division by zero.
+    in f called at 1:25
+    in g called at 1:1"
+    );
+}
+
+#[test]
+fn error_diagnostics() {
+    let mut interpreter = Interpreter::new();
+    let source = "let f x: (int -> int) = x / 0\nf 1";
+    let err = interpreter
+        .interpret_str(source)
+        .expect_err("Division by zero should fail.");
+    assert_eq!(
+        Renderer::plain().render(&err.diagnostic(), source, None),
+        "error[E0302]: Division by zero.
+ --> <source>:1:25
+  |
+1 | let f x: (int -> int) = x / 0
+  |                         ^^^^^ the divisor is zero
+2 | f 1
+  | --- in this call to f"
+    );
+
+    let source = "let a = 1 )";
+    let err = interpreter
+        .interpret_str(source)
+        .expect_err("Unbalanced parenthesis should fail.");
+    assert_eq!(
+        Renderer::plain().render(&err.diagnostic(), source, None),
+        "error[E0011]: Unexpected token 'RightParen'.
+ --> <source>:1:11
+  |
+1 | let a = 1 )
+  |           ^ unexpected token"
+    );
+
+    let source = "let b = 1\nb = 2";
+    let err = interpreter
+        .interpret_str(source)
+        .expect_err("Assigning to an immutable name should fail.");
+    assert_eq!(
+        Renderer::plain().render(&err.diagnostic(), source, None),
+        "error[E0108]: Cannot assign to b, as it is not mutable.
+ --> <source>:2:1
+  |
+1 | let b = 1
+  |     - b is declared here without `mut`
+2 | b = 2
+  | ^ not mutable
+  |
+  = help: declare it with `mut` to assign to it, e.g. `let mut a = 1`"
+    );
+}
+
+#[test]
+fn explain_error_codes() {
+    assert!(explain("E0302").is_some_and(|text| text.contains("divided by zero")));
+    assert!(explain("E0010").is_some());
+    assert_eq!(explain("E9999"), None);
+}
+
+#[test]
+fn did_you_mean_suggestions() {
+    let mut interpreter = Interpreter::new();
+    interpreter
+        .interpret_str(
+            r#"
+type Point = {x: int, y: int}
+let counter = 1
+let point = Point {x: 1, y: 2}
+"#,
+        )
+        .expect("Unable to interpret.");
+
+    let fix = |interpreter: &mut Interpreter, source: &str| {
+        let err = interpreter
+            .interpret_str(source)
+            .expect_err("A misspelled name should fail.");
+        let InterpreterError::TypeError(err) = err else {
+            panic!("Expected a type error, found {err:?}");
+        };
+        err.suggestion
+            .expect("A suggestion should be found.")
+            .apply(source)
+    };
+
+    assert_eq!(fix(&mut interpreter, "countr + 1"), "counter + 1");
+    assert_eq!(fix(&mut interpreter, "1 + point.yy"), "1 + point.y");
+    assert_eq!(
+        fix(&mut interpreter, "Pont {x: 1, y: 2}"),
+        "Point {x: 1, y: 2}"
+    );
+    assert_eq!(
+        fix(&mut interpreter, "Point {x: 1, yy: 2}"),
+        "Point {x: 1, y: 2}"
+    );
+    assert_eq!(
+        fix(&mut interpreter, "Std.U8.wraping_add 1u8 2u8"),
+        "Std.U8.wrapping_add 1u8 2u8"
+    );
+    assert_eq!(
+        fix(&mut interpreter, "Sdt.U8.wrapping_add 1u8 2u8"),
+        "Std.U8.wrapping_add 1u8 2u8"
+    );
+    assert_eq!(
+        fix(&mut interpreter, "let a: Pointt = point"),
+        "let a: Point = point"
+    );
+    assert_eq!(
+        fix(&mut interpreter, "let f p: ([Point] -> &itn) = &p[0].x"),
+        "let f p: ([Point] -> &int) = &p[0].x"
+    );
+    assert_eq!(fix(&mut interpreter, "point.x as u88"), "point.x as u8");
+    assert_eq!(
+        fix(&mut interpreter, "type Line = {from: Point, to: Pont}"),
+        "type Line = {from: Point, to: Point}"
+    );
+    assert_eq!(
+        fix(&mut interpreter, "type Place = Piont"),
+        "type Place = Point"
+    );
+
+    let err = interpreter
+        .interpret_str("total + 1")
+        .expect_err("An unknown name should fail.");
+    assert_eq!(
+        error_kind(err),
+        ErrorKind::UnknownName {
+            name: "total".to_string()
+        }
+    );
+
+    // The error is at the name of the type, not at the whole let.
+    let err = interpreter
+        .interpret_str("let a: [Pointt] = [point]")
+        .expect_err("An unknown type should fail.");
+    let InterpreterError::TypeError(err) = err else {
+        panic!("Expected a type error, found {err:?}");
+    };
+    assert_eq!(err.span, Some(8..14));
+    assert_eq!(
+        err.kind,
+        ErrorKind::UnknownType {
+            name: "Pointt".to_string()
+        }
+    );
+}
+
+#[test]
+fn partial_application() {
+    let mut interpreter = Interpreter::new();
+    let value = interpreter
+        .interpret_str(
+            r#"
+    let add x y = x + y
+    let inc = add 1
+    inc 41
+    "#,
+        )
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(42));
+
+    let value = interpreter
+        .interpret_str("let wrap = Std.U8.wrapping_add 250u8\nwrap 10u8")
+        .expect("Unable to interpret.");
+    assert_eq!(
+        value,
+        Value::from(Unpacked::SizedInteger(4, IntegerKind::U8))
+    );
+
+    // The partial application outlives the frame of the local it uses.
+    let value = interpreter
+        .interpret_str(
+            r#"
+    let make n =
+        let f x y = x * y + n
+        f 2
+    let g = make 1
+    g 5
+    "#,
+        )
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(11));
+
+    let err = interpreter
+        .interpret_str("inc true")
+        .expect_err("Arguments should be type checked.");
+    assert_eq!(
+        error_kind(err),
+        ErrorKind::MismatchedTypes {
+            expected: "int".to_string(),
+            found: "bool".to_string()
+        }
+    );
+}
+
+#[test]
+fn over_application() {
+    let mut interpreter = Interpreter::new();
+    let value = interpreter
+        .interpret_str(
+            r#"
+    let adder n = fn x -> x + n
+    adder 1 2
+    "#,
+        )
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(3));
+
+    let err = interpreter
+        .interpret_str("adder 1 2 3")
+        .expect_err("The result should not be callable.");
+    assert_eq!(
+        error_kind(err),
+        ErrorKind::NotCallable {
+            name: "adder".to_string(),
+            found: "int".to_string()
+        }
+    );
+}
+
+#[test]
+fn shared_captures() {
+    let mut interpreter = Interpreter::new();
+    let value = interpreter
+        .interpret_str(
+            r#"
+    type Counter = {inc: (() -> int), get: (() -> int)}
+    let counter () =
+        let mut count = 0
+        let inc () =
+            count = count + 1
+            count
+        let get () = count
+        Counter {inc: inc, get: get}
+    let c = counter ()
+    let inc = c.inc
+    let getters = [c.get]
+    let get = getters[0]
+    let apply f = f ()
+    inc ()
+    apply inc
+    get ()
+    "#,
+        )
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(2));
+
+    let value = interpreter
+        .interpret_str(
+            r#"
+    let mut total = 0
+    let add n = total = total + n
+    add 3
+    add 4
+    total
+    "#,
+        )
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(7));
+}
+
+#[test]
+fn captures_in_calls_and_structs() {
+    let mut interpreter = Interpreter::new();
+    let value = interpreter
+        .interpret_str(
+            r#"
+    type Point = {x:int,y:int}
+    let make_point n =
+        let offset = n * 10
+        let add x = x + offset
+        fn y ->
+            let y = add y
+            Point {x: n, y: y}
+    let point = make_point 2
+    let p = point 1
+    p.x + p.y
+    "#,
+        )
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(23));
+
+    let value = interpreter
+        .interpret_str(
+            r#"
+    let g = 1
+    let g = fn x -> x + g
+    g 1
+    "#,
+        )
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(2));
+}
+
+#[test]
+fn block_scopes() {
+    let mut interpreter = Interpreter::new();
+    let value = interpreter
+        .interpret_str(
+            r#"
+    let x = 1
+    let y =
+        let x = 2
+        x + 1
+    let z = if x == 1 then
+            let x = 10
+            x
+    else 0
+    x + y + z
+    "#,
+        )
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(14));
+    // Only the top level names are left on the stack.
+    assert_eq!(interpreter.stack.len(), 3);
+
+    let err = interpreter
+        .interpret_str(
+            r#"
+    if true then
+        let w = 1
+    w
+    "#,
+        )
+        .expect_err("w should not be in scope after the block.");
+    assert_eq!(
+        error_kind(err),
+        ErrorKind::UnknownName {
+            name: "w".to_string()
+        }
+    );
+}
+
+#[test]
+fn references() {
+    let mut interpreter = Interpreter::new();
+    let value = interpreter
+        .interpret_str(
+            r#"
+    let inc r: (&mut int -> ()) = *r += 1
+    let mut a = 1
+    inc &mut a
+    inc &mut a
+    let r = &a
+    let b = *r
+    a = 10
+    b + *r
+    "#,
+        )
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(13));
+
+    // Fields and elements are accessed through references, and a reference
+    // to one of them keeps referring to it.
+    let value = interpreter
+        .interpret_str(
+            r#"
+    type Point = {mut x: int, mut y: int}
+    let move_right p n: (&mut Point -> int -> ()) = p.x += n
+    let mut p = Point {x: 1, y: 2}
+    move_right &mut p 4
+    let y = &mut p.y
+    *y = 7
+    let mut a = [1, 2]
+    let second = &mut a[1]
+    *second = 3
+    let all = &a
+    p.x + p.y + all[0] + all[1]
+    "#,
+        )
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(16));
+
+    // A reference keeps a local alive after its scope ends.
+    let value = interpreter
+        .interpret_str(
+            r#"
+    let counter () =
+        let mut count = 0
+        &mut count
+    let c = counter ()
+    *c += 2
+    *c
+    "#,
+        )
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(2));
+}
+
+#[test]
+fn value_semantics() {
+    let mut interpreter = Interpreter::new();
+    let value = interpreter
+        .interpret_str(
+            r#"
+    type Point = {mut x: int, mut y: int}
+    type Line = {mut from: Point, mut to: Point}
+    let mut a = Point {x: 1, y: 2}
+    let mut b = a
+    b.x = 10
+    let mut line = Line {from: a, to: b}
+    let mut other = line
+    other.to.y = 20
+    a.x + b.x + line.to.y + other.to.y + line.from.x
+    "#,
+        )
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(34));
+
+    // Arguments are copies of the values passed.
+    let value = interpreter
+        .interpret_str(
+            r#"
+    let clear mut grid: ([[int]] -> int) =
+        grid[0][1] = 0
+        grid[0][1]
+    let mut grid = [[1, 2], [3, 4]]
+    let cleared = clear grid
+    let mut rows = grid
+    rows[1] = [5, 6]
+    cleared + grid[0][1] + grid[1][0] + rows[1][0]
+    "#,
+        )
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(10));
+
+    // A reference still refers to the value it was created from, while
+    // copies made before keep their own value.
+    let value = interpreter
+        .interpret_str(
+            r#"
+    let mut c = [1, 2]
+    let copy = c
+    let r = &mut c
+    r[0] = 7
+    let inner = &mut line.from
+    inner.y = 9
+    c[0] + copy[0] + line.from.y + a.y
+    "#,
+        )
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(19));
+}
+
+#[test]
+fn garbage_collection() {
+    let mut interpreter = Interpreter::new();
+    interpreter
+        .interpret_str(
+            r#"
+    let sum n =
+        let rec down m: (int -> int) = if m == 0 then 0 else
+            let rest = down (m - 1)
+            m + rest
+        down n
+    let a = sum 3
+    let b = sum 4
+    "#,
+        )
+        .expect("Unable to interpret.");
+    // Each call leaves a function that captures itself, through the cell of
+    // its name.
+    let stats = interpreter.heap_stats();
+    assert_eq!((stats.functions, stats.cells), (3, 2));
+
+    assert_eq!(interpreter.collect_garbage(), 4);
+    let stats = interpreter.heap_stats();
+    assert_eq!((stats.functions, stats.cells), (1, 0));
+    assert_eq!((stats.collections, stats.collected), (1, 4));
+
+    // Values still in use are kept, even in a cycle.
+    interpreter
+        .interpret_str(
+            r#"
+    let rec fact n: (int -> int) = if n == 0 then 1 else
+        let rest = fact (n - 1)
+        n * rest
+    let mut numbers = [a, b]
+    let r = &mut numbers
+    let add x y = x + y
+    let inc = add 1
+    "#,
+        )
+        .expect("Unable to interpret.");
+    assert_eq!(interpreter.collect_garbage(), 0);
+    let value = interpreter
+        .interpret_str(
+            r#"
+    let f = fact 4
+    r[0] = f
+    let c = inc a
+    numbers[0] + numbers[1] + c
+    "#,
+        )
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(41));
+}
+
+#[test]
+fn garbage_collection_shared_reference() {
+    let mut interpreter = Interpreter::new();
+    interpreter
+        .interpret_str(
+            r#"
+    let mut x = 1
+    let r = &mut x
+    let a =
+        let s = r
+        let t = r
+        let rec f n: (int -> int) = if n == 0 then *s + *t else f (n - 1)
+        f 0
+    "#,
+        )
+        .expect("Unable to interpret.");
+    // The function left behind holds two copies of the reference, which
+    // share it with `r`, so the cell of `x` is still in use.
+    assert_eq!(interpreter.heap_stats().references, 1);
+    assert_eq!(interpreter.collect_garbage(), 4);
+    assert_eq!(interpreter.heap_stats().references, 1);
+    let value = interpreter
+        .interpret_str(
+            r#"
+    *r = 2
+    x + a
+    "#,
+        )
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(4));
+}
+
+#[test]
+fn drop_long_chains() {
+    let mut interpreter = Interpreter::new();
+    // Each closure captures the one before, so dropping the last one drops
+    // all of them, once when the frame of the block is popped and once with
+    // the interpreter.
+    let value = interpreter
+        .interpret_str(
+            r#"
+    let rec chain n f: (int -> (int -> int) -> (int -> int)) =
+        if n == 0 then f else chain (n - 1) (fn x -> f (x + 1))
+    let a =
+        let f = chain 100000 (fn x -> x)
+        1
+    let g = chain 100000 (fn x -> x)
+    a
+    "#,
+        )
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(1));
+    drop(interpreter);
+}
+
+#[test]
+fn resolved_slots() {
+    let mut interpreter = Interpreter::new();
+    interpreter
+        .interpret_str(
+            r#"
+    let a = 1
+    let a = 2
+    let b = 3
+    "#,
+        )
+        .expect("Unable to interpret.");
+    // A shadowed name keeps its slot, so the ones after it are found in
+    // theirs. A statement that fails defines nothing.
+    let err = interpreter
+        .interpret_str(
+            r#"
+    let c = a + b
+    let rec f x: (int -> int) = x / 0
+    and g y: (int -> int) = f y
+    let d = f 1
+    "#,
+        )
+        .expect_err("Dividing by zero should fail.");
+    assert_eq!(error_kind(err), ErrorKind::DivisionByZero);
+    assert_eq!(interpreter.stack.len(), 6);
+    let value = interpreter
+        .interpret_str(
+            r#"
+    let outer x =
+        let y = x + c
+        let middle () =
+            let z =
+                let w = y * 2
+                w + a
+            let inner () = x + y + z
+            inner
+        middle
+    let m = outer b
+    let i = m ()
+    let e = i ()
+    e + a + b
+    "#,
+        )
+        .expect("Unable to interpret.");
+    // y = 8, z = 18, e = 3 + 8 + 18.
+    assert_eq!(value, Value::from(34));
+}
+
+#[test]
+fn compiled_calls() {
+    let mut interpreter = Interpreter::new();
+    // A function called with more arguments than it takes is called again
+    // with the rest once it returns.
+    let value = interpreter
+        .interpret_str(
+            r#"
+    let mk x: (int -> (int -> int -> int)) = fn y z -> x * y + z
+    let partial = mk 3 4
+    let whole = mk 3 4 5
+    let wrapping = Std.U8.wrapping_add 250u8
+    let byte = wrapping 10u8
+    let rest = partial 1
+    whole + rest + byte as int
+    "#,
+        )
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(34));
+
+    // Blocks that declare no names have no frame, so the locals around them
+    // are found through fewer frames.
+    let value = interpreter
+        .interpret_str(
+            r#"
+    let f x: (int -> int) =
+        let a = x + 1
+        if a > 1 then
+            if a > 2 then
+                let b = a * 2
+                b + x
+            else
+                a
+        else
+            x
+    let small = f 1
+    let big = f 5
+    small + big
+    "#,
+        )
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(19));
+
+    // A call that fails in a nested frame leaves nothing behind.
+    let err = interpreter
+        .interpret_str(
+            r#"
+    let g x: (int -> int) =
+        let y = x
+        if y > 0 then
+            let z = y
+            z / 0
+        else
+            y
+    g 1
+    "#,
+        )
+        .expect_err("Dividing by zero should fail.");
+    assert_eq!(error_kind(err), ErrorKind::DivisionByZero);
+    let value = interpreter
+        .interpret_str("let h = f 2\nlet k = g 0\nh + k")
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(8));
+
+    // Operators with a constant and conditions with a comparison are
+    // compiled to one instruction, except where the branch of an `if` ends.
+    let value = interpreter
+        .interpret_str(
+            r#"
+    let t = true
+    let a = 1 + (if t then 2 else 3)
+    let b = if (if t then false else 1 < 2) then 10 else 20
+    let c = if a < b then a - 1 else b * 2
+    a + b + c
+    "#,
+        )
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(25));
+}
+
+#[test]
+fn compact_values() {
+    assert_eq!(std::mem::size_of::<Value>(), 8);
+
+    // Integers at the edges of the ones held immediately, and beyond them.
+    for i in [
+        0,
+        -1,
+        (1 << 47) - 1,
+        -(1 << 47),
+        1 << 47,
+        i64::MIN,
+        i64::MAX,
+    ] {
+        assert_eq!(Value::from(i).unpack(), Unpacked::Integer(i));
+    }
+    for (i, kind) in [
+        (-128, IntegerKind::I8),
+        (u32::MAX as i64, IntegerKind::U32),
+        ((1 << 44) - 1, IntegerKind::U64),
+        (1 << 44, IntegerKind::U64),
+        (-1, IntegerKind::U64),
+    ] {
+        let value = Value::from(Unpacked::SizedInteger(i, kind));
+        assert_eq!(value.clone().unpack(), Unpacked::SizedInteger(i, kind));
+    }
+    // Every NaN is stored as the same one, which no other value is.
+    for f in [0.5, -0.0, f64::INFINITY, f64::NEG_INFINITY] {
+        assert_eq!(Value::from(f).unpack(), Unpacked::Float(f));
+    }
+    assert!(matches!(Value::from(-f64::NAN).unpack(), Unpacked::Float(f) if f.is_nan()));
+
+    let mut interpreter = Interpreter::new();
+    let value = interpreter
+        .interpret_str("let big = 140737488355327 + 1\nlet huge = big * 1000\nhuge / big - 1000")
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(0));
+    let value = interpreter
+        .interpret_str("let nan = 0.0 / 0.0\nnan == nan")
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(false));
+    let value = interpreter
+        .interpret_str("18446744073709551615u64 - 1u64")
+        .expect("Unable to interpret.");
+    assert_eq!(
+        value.as_integer(),
+        Some((u64::MAX as i128 - 1, IntegerKind::U64))
+    );
+    assert_eq!(
+        format!("{value:?}"),
+        "SizedInteger(18446744073709551614, U64)"
+    );
+    let value = interpreter
+        .interpret_str("let big = 18446744073709551615u64\nbig > 9223372036854775807u64")
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(true));
+
+    // Places deeper than the steps a path keeps inline.
+    let value = interpreter
+        .interpret_str(
+            r#"
+    let mut a = [[[1, 2], [3, 4]], [[5, 6], [7, 8]]]
+    a[1][0][1] = 9
+    let r = &mut a[1][1][0]
+    *r = 10
+    a[1][0][1] + a[1][1][0]
+    "#,
+        )
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(19));
+}
+
+#[test]
+fn tail_calls() {
+    let mut interpreter = Interpreter::new();
+    let value = interpreter
+        .interpret_str(
+            r#"
+    let rec count n acc: (int -> int -> int) =
+        if n == 0 then acc else
+            let next = acc + 1
+            count (n - 1) next
+    count 100000 0
+    "#,
+        )
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(100000));
+
+    let value = interpreter
+        .interpret_str(
+            r#"
+    let rec even n: (int -> bool) = if n == 0 then true else odd (n - 1)
+    and odd n: (int -> bool) = if n == 0 then false else even (n - 1)
+    even 100001
+    "#,
+        )
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(false));
+}
+
+#[test]
+fn call_depth_limit() {
+    let mut interpreter = Interpreter::new();
+    interpreter
+        .interpret_str(
+            r#"
+    let rec depth n: (int -> int) = if n == 0 then 0 else
+        let d = depth (n - 1)
+        d + 1
+    "#,
+        )
+        .expect("Unable to interpret.");
+    let err = match interpreter.interpret_str("depth 100000") {
+        Err(InterpreterError::RuntimeError(err)) => err,
+        other => panic!("Expected a runtime error, found {other:?}"),
+    };
+    assert_eq!(err.kind, ErrorKind::CallDepthExceeded { limit: 10000 });
+    // The calls of the recursion are made from the same place, so they take
+    // one frame.
+    assert_eq!(
+        *err.stack_trace,
+        [
+            StackFrame {
+                function: "depth".to_string(),
+                call_site: 0..12,
+                position: Some((1, 1)),
+                repeated: 0,
+                omitted_calls: 0,
+            },
+            StackFrame {
+                function: "depth".to_string(),
+                call_site: 75..88,
+                position: Some((3, 17)),
+                repeated: 9999,
+                omitted_calls: 0,
+            },
+        ]
+    );
+    assert_eq!(
+        err.to_string(),
+        "error[E0307]: Calls nested more than 10000 deep.
+    in depth called at 3:17, and 9999 more times
+    in depth called at 1:1"
+    );
+
+    // Calls that alternate are not the same, so the middle ones are left
+    // out instead.
+    interpreter
+        .interpret_str(
+            r#"
+    let rec ping n: (int -> int) = if n == 0 then 0 else
+        let p = pong (n - 1)
+        p + 1
+    and pong n: (int -> int) = if n == 0 then 0 else
+        let p = ping (n - 1)
+        p + 1
+    "#,
+        )
+        .expect("Unable to interpret.");
+    let err = interpreter
+        .interpret_str("ping 100000")
+        .expect_err("The call depth should be exceeded.");
+    let InterpreterError::RuntimeError(err) = err else {
+        panic!("Expected a runtime error, found {err:?}");
+    };
+    assert_eq!(err.stack_trace.len(), 32);
+    assert_eq!(err.stack_trace[15].omitted_calls, 10001 - 32);
+    let rendered = err.to_string();
+    assert!(rendered.contains("in ping called at 1:1"));
+    assert!(rendered.contains(&format!("\n    ... {} more calls\n", 10001 - 32)));
+    let diagnostic = Renderer::plain().render(&err.diagnostic(), "ping 100000", None);
+    assert!(diagnostic.contains(&format!(
+        "= note: {} more calls are left out of the stack trace",
+        10001 - 32
+    )));
+
+    // The interpreter is still usable, and the limit can be raised.
+    let value = interpreter
+        .interpret_str("depth 100")
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(100));
+    interpreter.set_limits(Limits {
+        max_call_depth: 200000,
+        ..Limits::default()
+    });
+    let value = interpreter
+        .interpret_str("depth 100000")
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(100000));
+}
+
+#[test]
+fn execution_budgets() {
+    let mut interpreter = Interpreter::new();
+    interpreter
+        .interpret_str(
+            r#"
+    let rec forever n: (int -> int) = forever (n + 1)
+    let rec hold n: (int -> int) = if n == 0 then 0 else
+        let a = [n, n]
+        let r = hold (n - 1)
+        r + a[0]
+    let rec churn n: (int -> int) = if n == 0 then 0 else
+        let a = [n, n]
+        churn (n - 1)
+    "#,
+        )
+        .expect("Unable to interpret.");
+
+    // `1 + 2` takes three steps, as adding a constant is one, ending with
+    // the return of its value.
+    interpreter.set_limits(Limits {
+        fuel: Some(3),
+        ..Limits::default()
+    });
+    let value = interpreter
+        .interpret_str("1 + 2")
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(3));
+    interpreter.set_limits(Limits {
+        fuel: Some(2),
+        ..Limits::default()
+    });
+    let err = interpreter
+        .interpret_str("1 + 2")
+        .expect_err("Fuel should run out.");
+    assert_eq!(error_kind(err), ErrorKind::FuelExhausted { limit: 2 });
+
+    interpreter.set_limits(Limits {
+        fuel: Some(100000),
+        ..Limits::default()
+    });
+    let err = interpreter
+        .interpret_str("forever 0")
+        .expect_err("Fuel should run out.");
+    assert_eq!(error_kind(err), ErrorKind::FuelExhausted { limit: 100000 });
+
+    interpreter.set_limits(Limits {
+        timeout: Some(Duration::from_millis(20)),
+        ..Limits::default()
+    });
+    let err = interpreter
+        .interpret_str("forever 0")
+        .expect_err("Time should run out.");
+    assert_eq!(
+        error_kind(err),
+        ErrorKind::TimeLimitExceeded {
+            limit: Duration::from_millis(20)
+        }
+    );
+
+    // Values that are freed along the way do not count.
+    interpreter.set_limits(Limits {
+        max_heap_objects: Some(100),
+        max_heap_bytes: Some(8192),
+        ..Limits::default()
+    });
+    let value = interpreter
+        .interpret_str("churn 10000")
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(0));
+    let err = interpreter
+        .interpret_str("hold 1000")
+        .expect_err("Too many values should be alive.");
+    assert_eq!(
+        error_kind(err),
+        ErrorKind::HeapObjectsExceeded { limit: 100 }
+    );
+    interpreter.set_limits(Limits {
+        max_heap_bytes: Some(8192),
+        ..Limits::default()
+    });
+    let err = interpreter
+        .interpret_str("hold 1000")
+        .expect_err("Values should take too many bytes.");
+    assert_eq!(
+        error_kind(err),
+        ErrorKind::HeapBytesExceeded { limit: 8192 }
+    );
+
+    // The interpreter stays usable after a limit is exceeded.
+    let value = interpreter
+        .interpret_str("hold 10")
+        .expect("Unable to interpret.");
+    assert_eq!(value, Value::from(55));
+}
+
+#[test]
+fn big_number_limits() {
+    let mut interpreter = Interpreter::new();
+    interpreter
+        .interpret_str(
+            r#"
+    let rec grow n x: (int -> bigint -> bigint) = if n == 0 then x else grow (n - 1) (x * x)
+    let rec count n x: (int -> bigint -> bigint) = if n == 0 then x else count (n - 1) (x + 1n)
+    "#,
+        )
+        .expect("Unable to interpret.");
+
+    // The digits count, and are checked as soon as they are computed rather
+    // than once the steps between two checks are taken.
+    interpreter.set_limits(Limits {
+        max_heap_bytes: Some(8192),
+        ..Limits::default()
+    });
+    let value = interpreter
+        .interpret_str("count 10000 0n")
+        .expect("Unable to interpret.");
+    assert_eq!(format!("{value:?}"), "BigInteger(10000)");
+    let err = interpreter
+        .interpret_str("grow 40 3n")
+        .expect_err("Digits should take too many bytes.");
+    assert_eq!(
+        error_kind(err),
+        ErrorKind::HeapBytesExceeded { limit: 8192 }
+    );
+
+    interpreter.set_limits(Limits {
+        timeout: Some(Duration::from_millis(20)),
+        ..Limits::default()
+    });
+    let err = interpreter
+        .interpret_str("grow 40 3n")
+        .expect_err("Time should run out.");
+    assert_eq!(
+        error_kind(err),
+        ErrorKind::TimeLimitExceeded {
+            limit: Duration::from_millis(20)
+        }
+    );
+
+    // 3 to the power of 1024 takes 1624 bits, so 26 words.
+    let bytes = interpreter.heap_stats().bytes;
+    let value = interpreter
+        .interpret_str("grow 10 3n")
+        .expect("Unable to interpret.");
+    assert!(interpreter.heap_stats().bytes >= bytes + 26 * 8);
+    drop(value);
+    assert_eq!(interpreter.heap_stats().bytes, bytes);
 }
@@ -0,0 +1,362 @@
+use std::{cmp::Reverse, rc::Rc};
+
+use super::{
+    operation::{arithmetic, boolean, comparison},
+    reference::resolve_deref,
+};
+use crate::{
+    bytecode::{Chunk, Instruction},
+    context::Context,
+    error::{ErrorKind, RuntimeError, StackFrame},
+    object::{Object, Path, Reference, Unpacked, Value, ValueRef},
+    Interpreter,
+};
+
+/// The number of outermost and of innermost frames kept in a stack trace.
+const TRACE_ENDS: usize = 16;
+
+/// Where a run of the bytecode is: the chunk being run, the position of the
+/// next instruction in it and the calls it returns to.
+pub(super) struct Vm {
+    pub(super) chunk: Rc<Chunk>,
+    pub(super) ip: usize,
+    pub(super) calls: Vec<CallFrame>,
+}
+
+/// A call of a function defined in the language that has not returned yet.
+pub(super) struct CallFrame {
+    /// The chunk and the position to return to.
+    pub(super) chunk: Rc<Chunk>,
+    pub(super) ip: usize,
+    /// The arguments left once the function takes all of its own, which are
+    /// applied to the function it returns.
+    pub(super) pending: Vec<Value>,
+    pub(super) previous_context: Context,
+    /// The number of frames on the stack below the one of the call.
+    pub(super) frames: usize,
+    /// The chunk and the position after the last tail call made by the
+    /// functions the call entered, for stack traces.
+    pub(super) tail_call: Option<(Rc<Chunk>, usize)>,
+}
+
+impl Vm {
+    /// Sets the span of an error to the one of the innermost expression that
+    /// records one, in the function that failed or in its callers.
+    fn with_spans(&self, mut err: RuntimeError) -> RuntimeError {
+        let frames = std::iter::once((&self.chunk, self.ip))
+            .chain(self.calls.iter().rev().map(|call| (&call.chunk, call.ip)));
+        for (chunk, ip) in frames {
+            if let Some(span) = &chunk.spans[ip - 1] {
+                err = err.with_span(span);
+            }
+        }
+        err
+    }
+
+    /// The calls that have not returned yet, the innermost one last. They
+    /// include the calls whose arguments are being evaluated. The same call
+    /// made again and again right inside itself, like by a recursion, takes
+    /// one frame. Of a deep stack that is left, only the outermost and
+    /// innermost calls are kept.
+    fn stack_trace(&self) -> Box<[StackFrame]> {
+        let frames = self
+            .calls
+            .iter()
+            .flat_map(|call| {
+                let tail_call = call.tail_call.as_ref().map(|(chunk, ip)| (chunk, *ip));
+                std::iter::once((&call.chunk, call.ip)).chain(tail_call)
+            })
+            .chain(std::iter::once((&self.chunk, self.ip)));
+        let mut trace: Vec<StackFrame> = Vec::new();
+        for (chunk, ip) in frames {
+            let mut sites = chunk
+                .call_sites
+                .iter()
+                .filter(|site| site.code.contains(&(ip - 1)))
+                .collect::<Vec<_>>();
+            // Calls in the arguments of another call are within its code.
+            sites.sort_by_key(|site| (site.code.start, Reverse(site.code.end)));
+            for site in sites {
+                match trace.last_mut() {
+                    Some(last)
+                        if last.function == site.frame.function
+                            && last.call_site == site.frame.call_site
+                            && last.position == site.frame.position =>
+                    {
+                        last.repeated += 1;
+                    }
+                    _ => trace.push(site.frame.clone()),
+                }
+            }
+        }
+        if trace.len() > 2 * TRACE_ENDS {
+            trace[TRACE_ENDS - 1].omitted_calls = trace
+                .drain(TRACE_ENDS..trace.len() - TRACE_ENDS)
+                .map(|frame| frame.repeated + 1)
+                .sum();
+        }
+        trace.into()
+    }
+}
+
+impl Interpreter {
+    /// Runs a chunk of the top level to its value. If it fails, the frames
+    /// and places it leaves behind are dropped, but not its values on the
+    /// stack.
+    pub(super) fn run(&mut self, chunk: Rc<Chunk>) -> Result<Value, RuntimeError> {
+        let frames = self.stack.frames();
+        let context = self.context.clone();
+        let mut vm = Vm {
+            chunk,
+            ip: 0,
+            calls: Vec::new(),
+        };
+        self.execute(&mut vm).map_err(|err| {
+            let mut err = vm.with_spans(err);
+            if err.stack_trace.is_empty() {
+                err.stack_trace = vm.stack_trace();
+            }
+            self.stack.truncate_frames(frames);
+            self.context = context;
+            self.places.clear();
+            err
+        })
+    }
+
+    fn execute(&mut self, vm: &mut Vm) -> Result<Value, RuntimeError> {
+        loop {
+            // The chunk only changes on calls and returns, which start the
+            // loop over.
+            let chunk = vm.chunk.clone();
+            loop {
+                let instruction = &chunk.code[vm.ip];
+                vm.ip += 1;
+                if self.budget.countdown == 0 {
+                    self.checkpoint()?;
+                }
+                self.budget.countdown -= 1;
+                match instruction {
+                    Instruction::Constant(index) => {
+                        self.stack.push_value(chunk.constants[*index].clone());
+                    }
+                    Instruction::GetLocal { depth, slot } => {
+                        let sp = self.stack.local_sp(*depth, *slot);
+                        self.push_local(sp);
+                    }
+                    Instruction::GetCapture(index) => {
+                        let sp = self.context.capture_sp(*index);
+                        self.push_local(sp);
+                    }
+                    Instruction::GetField(offset) => {
+                        let value = self.stack.pop_value().get_at(&[*offset]);
+                        self.stack.push_value(value);
+                    }
+                    Instruction::Index => {
+                        let index = self.stack.pop_value();
+                        let value = self.stack.pop_value();
+                        let element = self.index_value(value, index)?;
+                        self.stack.push_value(element);
+                    }
+                    Instruction::Deref => {
+                        let reference = resolve_deref(self.stack.pop_value())?;
+                        self.stack.push_value(reference.get());
+                    }
+                    Instruction::Pop => {
+                        self.stack.pop_value();
+                    }
+                    Instruction::Define(slot) => {
+                        let value = self.stack.pop_value();
+                        let sp = self.stack.local_sp(0, *slot);
+                        self.stack.set_value(sp, value);
+                    }
+                    Instruction::PlaceLocal { depth, slot } => {
+                        let sp = self.stack.local_sp(*depth, *slot);
+                        self.places.push(ValueRef::StackRef {
+                            sp,
+                            path: Path::new(),
+                        });
+                    }
+                    Instruction::PlaceCapture(index) => {
+                        let sp = self.context.capture_sp(*index);
+                        self.places.push(ValueRef::StackRef {
+                            sp,
+                            path: Path::new(),
+                        });
+                    }
+                    Instruction::PlaceField(offset) => {
+                        let place = self.pop_place().project(*offset);
+                        self.places.push(place);
+                    }
+                    Instruction::PlaceIndex => {
+                        let place = self.pop_place();
+                        let index = self.stack.pop_value();
+                        let place = self.resolve_index(place, index)?;
+                        self.places.push(place);
+                    }
+                    Instruction::PlaceDeref => {
+                        let reference = resolve_deref(self.stack.pop_value())?;
+                        self.places.push(ValueRef::Reference(reference));
+                    }
+                    Instruction::PlaceTemporary => {
+                        let value = self.stack.pop_value();
+                        let cell = self.heap.alloc_cell(value);
+                        self.places.push(ValueRef::Reference(Reference::new(cell)));
+                    }
+                    Instruction::Reference => {
+                        let place = self.pop_place();
+                        let reference = self.resolve_reference(place)?;
+                        let reference = self.heap.alloc_reference(reference);
+                        self.stack.push_value(Value::from(reference));
+                    }
+                    Instruction::Assign => {
+                        let place = self.pop_place();
+                        let value = self.stack.pop_value();
+                        self.write_place(place, value);
+                        self.stack.push_value(Value::UNIT);
+                    }
+                    Instruction::CompoundAssign(operator) => {
+                        let place = self.pop_place();
+                        let rhs = self.stack.pop_value();
+                        let lhs = self.read_place(&place);
+                        let value = arithmetic(operator, lhs, rhs)?;
+                        let number = self.heap.track_number(&value);
+                        self.write_place(place, value);
+                        self.stack.push_value(Value::UNIT);
+                        if number {
+                            self.check_number()?;
+                        }
+                    }
+                    Instruction::Arithmetic(operator) => {
+                        let rhs = self.stack.pop_value();
+                        let lhs = self.stack.pop_value();
+                        self.push_result(arithmetic(operator, lhs, rhs)?)?;
+                    }
+                    Instruction::ArithmeticConstant(operator, index) => {
+                        let lhs = self.stack.pop_value();
+                        let rhs = chunk.constants[*index].clone();
+                        self.push_result(arithmetic(operator, lhs, rhs)?)?;
+                    }
+                    Instruction::Comparison(operator) => {
+                        let rhs = self.stack.pop_value();
+                        let lhs = self.stack.pop_value();
+                        let value = comparison(operator, &lhs, &rhs)?;
+                        self.stack.push_value(Value::from(value));
+                    }
+                    Instruction::Boolean(operator) => {
+                        let rhs = self.stack.pop_value();
+                        let lhs = self.stack.pop_value();
+                        self.stack.push_value(boolean(operator, lhs, rhs));
+                    }
+                    Instruction::Cast(type_id) => {
+                        let value = self.stack.pop_value();
+                        let value = self.cast_value(value, *type_id)?;
+                        self.push_result(value)?;
+                    }
+                    Instruction::Object { len, type_id } => {
+                        let values = self.stack.pop_values(*len);
+                        let object = self.heap.alloc_object(Object {
+                            values,
+                            type_id: *type_id,
+                        });
+                        self.stack.push_value(Value::from(object));
+                    }
+                    Instruction::SliceBound { depth } => self.check_slice_bound(*depth)?,
+                    Instruction::Slice { from, to } => {
+                        let to = to.then(|| self.stack.pop_value());
+                        let from = from.then(|| self.stack.pop_value());
+                        let value = self.stack.pop_value();
+                        let slice = self.slice_value(value, from, to)?;
+                        self.stack.push_value(slice);
+                    }
+                    Instruction::Closure(index) => {
+                        let function = self.create_closure(&chunk.prototypes[*index])?;
+                        self.stack.push_value(function);
+                    }
+                    Instruction::Call(argc) => {
+                        if self.call(vm, *argc)? {
+                            break;
+                        }
+                    }
+                    Instruction::TailCall(argc) => {
+                        if self.tail_call(vm, *argc)? {
+                            break;
+                        }
+                    }
+                    Instruction::Jump(target) => vm.ip = *target,
+                    Instruction::JumpIfFalse(target) => {
+                        match self.stack.pop_value().deref_value().unpack() {
+                            Unpacked::Boolean(true) => {}
+                            Unpacked::Boolean(false) => vm.ip = *target,
+                            _ => {
+                                return Err(ErrorKind::Internal(
+                                    "Condition is not a boolean.".to_string(),
+                                )
+                                .into())
+                            }
+                        }
+                    }
+                    Instruction::CompareJump(operator, target) => {
+                        let rhs = self.stack.pop_value();
+                        let lhs = self.stack.pop_value();
+                        if !comparison(operator, &lhs, &rhs)? {
+                            vm.ip = *target;
+                        }
+                    }
+                    Instruction::PushFrame => self.stack.push_frame(),
+                    Instruction::PopFrame => {
+                        let value = self.stack.pop_value();
+                        self.stack.pop_frame();
+                        self.stack.push_value(value);
+                    }
+                    Instruction::Return => match self.return_value(vm)? {
+                        Some(value) => return Ok(value),
+                        None => break,
+                    },
+                }
+            }
+        }
+    }
+
+    /// Pushes the value of a local. A variable captured by a function is read
+    /// from the heap, so the value is never a reference to it.
+    fn push_local(&mut self, sp: usize) {
+        let value = self
+            .stack
+            .read_value(sp)
+            .expect("Unable to find registered local.");
+        self.stack.push_value(value);
+    }
+
+    /// Checks the limits of the code being run, once every so many steps.
+    /// The heap is collected there too, so code that runs for long between
+    /// two statements does not keep what it freed tracked.
+    fn checkpoint(&mut self) -> Result<(), ErrorKind> {
+        self.budget.refill()?;
+        self.heap.maybe_collect();
+        self.heap.check_limits(&self.limits)
+    }
+
+    /// Pushes the result of an operation, checking the limits right away
+    /// when it is a big integer or a rational.
+    #[inline]
+    fn push_result(&mut self, value: Value) -> Result<(), ErrorKind> {
+        let number = self.heap.track_number(&value);
+        self.stack.push_value(value);
+        if number {
+            self.check_number()?;
+        }
+        Ok(())
+    }
+
+    /// Checks the time and the heap after a big integer or a rational was
+    /// computed, as a few steps of them can take any amount of either.
+    #[cold]
+    fn check_number(&mut self) -> Result<(), ErrorKind> {
+        self.budget.check_deadline()?;
+        self.heap.check_limits(&self.limits)
+    }
+
+    fn pop_place(&mut self) -> ValueRef {
+        self.places.pop().expect("No places to pop.")
+    }
+}
@@ -1,4 +1,12 @@
-use crate::{object::Value, Interpreter};
+use std::time::Duration;
+
+use diagnostic::Renderer;
+use parser::IntegerKind;
+
+use crate::{
+    object::{Unpacked, Value},
+    Interpreter, Limits,
+};
 
 use js_sys::Object;
 use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
@@ -6,26 +14,53 @@ use wasm_bindgen::{prelude::wasm_bindgen, JsValue};
 #[cfg_attr(target_arch = "wasm32", wasm_bindgen)]
 impl Interpreter {
     pub fn interpret_str_web(&mut self, str: &str) -> Result<JsValue, String> {
-        let value = self.interpret_str(str)?;
+        let value = self
+            .interpret_str(str)
+            .map_err(|err| Renderer::plain().render(&err.diagnostic(), str, None))?;
         self.to_js_value(value)
     }
+
+    /// Sets the limits for the code run from now on, see [`Limits`]. The ones
+    /// left out are not limited, except for the call depth, which keeps its
+    /// default.
+    pub fn set_limits_web(
+        &mut self,
+        fuel: Option<f64>,
+        timeout_ms: Option<f64>,
+        max_heap_objects: Option<u32>,
+        max_heap_bytes: Option<u32>,
+        max_call_depth: Option<u32>,
+    ) {
+        let default = Limits::default();
+        self.set_limits(Limits {
+            max_call_depth: max_call_depth.map_or(default.max_call_depth, |depth| depth as usize),
+            fuel: fuel.map(|fuel| fuel as u64),
+            timeout: timeout_ms.map(|ms| Duration::from_millis(ms as u64)),
+            max_heap_objects: max_heap_objects.map(|objects| objects as usize),
+            max_heap_bytes: max_heap_bytes.map(|bytes| bytes as usize),
+        });
+    }
 }
 
 impl Interpreter {
     pub fn to_js_value(&self, value: Value) -> Result<JsValue, String> {
-        match value {
-            Value::Unit => Ok(Object::new().into()),
-            Value::Boolean(boolean) => {
+        match value.unpack() {
+            Unpacked::Unit => Ok(Object::new().into()),
+            Unpacked::Boolean(boolean) => {
                 if boolean {
                     Ok(JsValue::TRUE)
                 } else {
                     Ok(JsValue::FALSE)
                 }
             }
-            Value::Char(ch) => Ok(JsValue::from(ch.to_string())),
-            Value::Integer(integer) => Ok(JsValue::from(integer)),
-            Value::Float(float) => Ok(JsValue::from(float)),
-            Value::Object(object) => {
+            Unpacked::Char(ch) => Ok(JsValue::from(ch.to_string())),
+            Unpacked::Integer(integer) => Ok(JsValue::from(integer)),
+            Unpacked::SizedInteger(integer, IntegerKind::U64) => Ok(JsValue::from(integer as u64)),
+            Unpacked::SizedInteger(integer, _) => Ok(JsValue::from(integer)),
+            Unpacked::BigInteger(integer) => Ok(JsValue::from(integer.to_string())),
+            Unpacked::Rational(rational) => Ok(JsValue::from(rational.to_string())),
+            Unpacked::Float(float) => Ok(JsValue::from(float)),
+            Unpacked::Object(object) => {
                 let ty = self
                     .type_registry
                     .get_type_from_id(object.borrow().type_id)
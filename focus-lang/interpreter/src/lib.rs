@@ -1,8 +1,20 @@
+mod bytecode;
+mod checker;
 mod context;
+mod error;
+mod heap;
 mod interpreter;
+mod limits;
 mod module;
 mod object;
 mod stack;
+mod stdlib;
+mod suggestion;
 mod r#type;
+pub mod typed;
 
+pub use error::{explain, ErrorKind, InterpreterError, RuntimeError, StackFrame};
+pub use heap::HeapStats;
 pub use interpreter::Interpreter;
+pub use limits::Limits;
+pub use r#type::TypeId;
@@ -0,0 +1,142 @@
+use std::time::Duration;
+
+use crate::error::ErrorKind;
+
+/// The default for [`Limits::max_call_depth`].
+const DEFAULT_MAX_CALL_DEPTH: usize = 10_000;
+
+/// The number of steps between two checks of the time and of the heap.
+const CHECK_INTERVAL: u64 = 1024;
+
+/// Limits on the resources the code an interpreter runs can use, see
+/// [`Interpreter::set_limits`](crate::Interpreter::set_limits). Each one
+/// fails the code with its own error once it is exceeded, after which the
+/// interpreter can run code again.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Limits {
+    /// The number of calls of functions defined in the language that can be
+    /// in progress at once. A call in tail position replaces the call it is
+    /// in, so it does not add to the depth.
+    pub max_call_depth: usize,
+    /// The number of steps, each the run of one bytecode instruction, that
+    /// the code passed to one `interpret_*` call can take.
+    pub fuel: Option<u64>,
+    /// How long the code passed to one `interpret_*` call can run for. Type
+    /// checking it is not included.
+    pub timeout: Option<Duration>,
+    /// The number of structs, arrays, functions, cells, references and
+    /// partial applications that can be alive on the heap at once.
+    pub max_heap_objects: Option<usize>,
+    /// The number of bytes the values on the heap can take at once, as
+    /// estimated from their sizes. The digits of the big integers and
+    /// rationals computed by the code are included, and are checked as soon
+    /// as one is computed.
+    pub max_heap_bytes: Option<usize>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_call_depth: DEFAULT_MAX_CALL_DEPTH,
+            fuel: None,
+            timeout: None,
+            max_heap_objects: None,
+            max_heap_bytes: None,
+        }
+    }
+}
+
+/// What is left of the fuel and the time of the code being run.
+///
+/// Steps are taken from a countdown, which is cheap enough to do for every
+/// instruction. Once it runs out, the time and the heap are checked and the
+/// next steps are taken from the fuel, so the heap limits can be exceeded
+/// by what the steps between two checks allocate. Big integers and rationals
+/// can grow without bound in a few steps, so the time and the heap are also
+/// checked after each one is computed.
+pub(crate) struct Budget {
+    /// The steps left before the next check.
+    pub(crate) countdown: u64,
+    /// The fuel left besides the steps of the countdown.
+    fuel: Option<u64>,
+    deadline: Option<Deadline>,
+    limits: Limits,
+}
+
+impl Budget {
+    /// Starts the budget of code about to run.
+    pub(crate) fn new(limits: Limits) -> Self {
+        Self {
+            countdown: 0,
+            fuel: limits.fuel,
+            deadline: limits.timeout.map(Deadline::after),
+            limits,
+        }
+    }
+
+    /// Checks the time once the countdown has run out, and refills it from
+    /// the fuel.
+    pub(crate) fn refill(&mut self) -> Result<(), ErrorKind> {
+        self.check_deadline()?;
+        self.countdown = match &mut self.fuel {
+            Some(0) => {
+                return Err(ErrorKind::FuelExhausted {
+                    limit: self.limits.fuel.unwrap_or_default(),
+                })
+            }
+            Some(fuel) => {
+                let steps = CHECK_INTERVAL.min(*fuel);
+                *fuel -= steps;
+                steps
+            }
+            None => CHECK_INTERVAL,
+        };
+        Ok(())
+    }
+
+    /// Fails once the code has run for longer than its timeout.
+    pub(crate) fn check_deadline(&self) -> Result<(), ErrorKind> {
+        if let (Some(deadline), Some(limit)) = (&self.deadline, self.limits.timeout) {
+            if deadline.has_passed() {
+                return Err(ErrorKind::TimeLimitExceeded { limit });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The point in time by which code has to finish. `Instant` is not
+/// available in the browser, where the clock of JavaScript is used instead.
+struct Deadline {
+    #[cfg(not(target_arch = "wasm32"))]
+    at: std::time::Instant,
+    /// Milliseconds since the Unix epoch.
+    #[cfg(target_arch = "wasm32")]
+    at: f64,
+}
+
+impl Deadline {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn after(timeout: Duration) -> Self {
+        Self {
+            at: std::time::Instant::now() + timeout,
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn after(timeout: Duration) -> Self {
+        Self {
+            at: js_sys::Date::now() + timeout.as_secs_f64() * 1000.0,
+        }
+    }
+
+    #[cfg(not(target_arch = "wasm32"))]
+    fn has_passed(&self) -> bool {
+        std::time::Instant::now() >= self.at
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn has_passed(&self) -> bool {
+        js_sys::Date::now() >= self.at
+    }
+}
@@ -3,14 +3,14 @@ use std::{collections::HashMap, rc::Rc};
 use crate::{object::Value, r#type::TypeId};
 
 pub enum ModuleDefinition {
-    Value(Rc<Value>),
+    Value(Rc<Value>, TypeId),
     Type(TypeId),
     Module(Rc<Module>),
 }
 
 pub struct Module {
     name: String,
-    names_defined: HashMap<String, Rc<Value>>,
+    names_defined: HashMap<String, (Rc<Value>, TypeId)>,
     types_defined: HashMap<String, TypeId>,
     modules_defined: HashMap<String, Rc<Module>>,
     parent_module: Option<Rc<Module>>,
@@ -37,8 +37,9 @@ impl Module {
         }
     }
 
-    pub fn insert_name(&mut self, name: &str, value: Value) {
-        self.names_defined.insert(name.to_string(), Rc::new(value));
+    pub fn insert_name(&mut self, name: &str, value: Value, type_id: TypeId) {
+        self.names_defined
+            .insert(name.to_string(), (Rc::new(value), type_id));
     }
 
     pub fn insert_type(&mut self, name: &str, type_id: TypeId) {
@@ -56,19 +57,28 @@ impl Module {
     ) -> Option<ModuleDefinition> {
         if let Some(p) = path.next() {
             if let Some(module) = self.modules_defined.get(p.as_ref()) {
-                return Some(ModuleDefinition::Module(module.clone()));
-            } else if let Some(value) = self.names_defined.get(p.as_ref()) {
-                return Some(ModuleDefinition::Value(value.clone()));
-            } else if let Some(type_id) = self.types_defined.get(p.as_ref()) {
-                return Some(ModuleDefinition::Type(*type_id));
+                Some(ModuleDefinition::Module(module.clone()))
+            } else if let Some((value, type_id)) = self.names_defined.get(p.as_ref()) {
+                Some(ModuleDefinition::Value(value.clone(), *type_id))
             } else {
-                return None;
+                self.types_defined
+                    .get(p.as_ref())
+                    .map(|type_id| ModuleDefinition::Type(*type_id))
             }
         } else {
             None
         }
     }
 
+    /// Names of the values, types and modules defined in the module.
+    pub fn definition_names(&self) -> impl Iterator<Item = &str> {
+        self.names_defined
+            .keys()
+            .chain(self.types_defined.keys())
+            .chain(self.modules_defined.keys())
+            .map(String::as_str)
+    }
+
     pub fn get_fully_qualified_name(&self, name: &str) -> String {
         let mut buf = self.get_fully_qualified_name_internal(String::new());
         buf.push_str(name);
@@ -84,3 +94,23 @@ impl Module {
         buf
     }
 }
+
+/// Finds the value defined at a path like `Std.U8.wrapping_add`, starting
+/// from one of the root `modules`.
+pub fn find_module_value(
+    modules: &HashMap<String, Rc<Module>>,
+    path: &str,
+) -> Option<(Rc<Value>, TypeId)> {
+    let mut path_parts = path.split('.');
+    let mut module = modules.get(path_parts.next()?)?.clone();
+
+    loop {
+        match module.get_definition_from_partial_path(&mut path_parts)? {
+            ModuleDefinition::Module(inner) => module = inner,
+            ModuleDefinition::Value(value, type_id) => {
+                return path_parts.next().is_none().then_some((value, type_id))
+            }
+            ModuleDefinition::Type(_) => return None,
+        }
+    }
+}
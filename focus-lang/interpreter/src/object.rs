@@ -1,19 +1,142 @@
-use std::{cell::RefCell, fmt::Debug, ops::Deref, rc::Rc};
+use std::{
+    cell::RefCell, cmp::Ordering, fmt::Debug, marker::PhantomData, mem::ManuallyDrop, ops::Deref,
+    rc::Rc,
+};
 
-use parser::stmt::Expression;
+use num_bigint::BigInt;
+use num_rational::BigRational;
+use num_traits::Zero;
+use parser::{op::ArithmeticOperator, IntegerKind};
 
-use crate::{context::Context, r#type::TypeId};
+use crate::{bytecode::Prototype, error::ErrorKind, heap::Heap, module::Module, r#type::TypeId};
 
+/// A place that can be read and assigned to: a value and the fields and
+/// elements that lead from it to the place.
 pub enum ValueRef {
-    StackRef {
-        sp: usize,
-        type_id: TypeId,
-    },
-    ObjectRef {
-        object: Rc<RefCell<Object>>,
-        index: usize,
-        type_id: TypeId,
+    /// A local, or a field or element of it, like `p.x` or `a[0]`.
+    StackRef { sp: usize, path: Path },
+    /// The place a reference refers to, like `*r`, or a field or element of
+    /// it.
+    Reference(Reference),
+}
+
+impl ValueRef {
+    /// The place of a field or element of the value in this one.
+    pub fn project(self, index: usize) -> Self {
+        match self {
+            ValueRef::StackRef { sp, mut path } => {
+                path.push(index);
+                ValueRef::StackRef { sp, path }
+            }
+            ValueRef::Reference(mut reference) => {
+                reference.path.push(index);
+                ValueRef::Reference(reference)
+            }
+        }
+    }
+}
+
+/// The number of steps a [`Path`] holds without allocating.
+const INLINE_STEPS: usize = 2;
+
+/// The fields and elements that lead from a value to a place in it, like
+/// `[1, 0]` for `a[1].x`. Most places are only a step or two deep, so short
+/// paths are kept inline and assigning to a field or an element does not
+/// allocate.
+#[derive(Clone)]
+pub enum Path {
+    Inline {
+        len: u8,
+        steps: [usize; INLINE_STEPS],
     },
+    Heap(Vec<usize>),
+}
+
+impl Path {
+    pub fn new() -> Self {
+        Path::Inline {
+            len: 0,
+            steps: [0; INLINE_STEPS],
+        }
+    }
+
+    pub fn push(&mut self, step: usize) {
+        match self {
+            Path::Inline { len, steps } if usize::from(*len) < INLINE_STEPS => {
+                steps[usize::from(*len)] = step;
+                *len += 1;
+            }
+            Path::Inline { steps, .. } => {
+                let mut steps = steps.to_vec();
+                steps.push(step);
+                *self = Path::Heap(steps);
+            }
+            Path::Heap(steps) => steps.push(step),
+        }
+    }
+}
+
+impl Default for Path {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Deref for Path {
+    type Target = [usize];
+
+    fn deref(&self) -> &[usize] {
+        match self {
+            Path::Inline { len, steps } => &steps[..usize::from(*len)],
+            Path::Heap(steps) => steps,
+        }
+    }
+}
+
+impl PartialEq for Path {
+    fn eq(&self, other: &Self) -> bool {
+        **self == **other
+    }
+}
+
+/// A reference to a place, like `&a` or `&mut p.x`. It holds the value of the
+/// name the place starts with, moved to the heap like a captured name, and
+/// the fields and elements that lead from it to the place.
+#[derive(Clone)]
+pub struct Reference {
+    pub value: Rc<RefCell<Value>>,
+    pub path: Path,
+}
+
+impl Reference {
+    pub fn new(value: Rc<RefCell<Value>>) -> Self {
+        Self {
+            value,
+            path: Path::new(),
+        }
+    }
+
+    pub fn get(&self) -> Value {
+        self.value.borrow().get_at(&self.path)
+    }
+
+    pub fn set(&self, value: Value, heap: &mut Heap) {
+        self.value.borrow_mut().set_at(&self.path, value, heap);
+    }
+}
+
+impl PartialEq for Reference {
+    fn eq(&self, other: &Self) -> bool {
+        Rc::ptr_eq(&self.value, &other.value) && self.path == other.path
+    }
+}
+
+impl Drop for Reference {
+    fn drop(&mut self) {
+        if Rc::strong_count(&self.value) == 1 {
+            drop_values(vec![self.value.replace(Value::UNIT)]);
+        }
+    }
 }
 
 pub struct CapturedName {
@@ -23,147 +146,824 @@ pub struct CapturedName {
 }
 
 pub struct Function {
-    pub context: Rc<RefCell<Context>>,
-    pub expr: Expression,
-    pub args: Vec<String>,
+    /// The module the function is defined in.
+    pub module: Rc<Module>,
+    pub prototype: Rc<Prototype>,
     pub captured_names: Vec<CapturedName>,
 }
 
+impl Drop for Function {
+    fn drop(&mut self) {
+        let names = std::mem::take(&mut self.captured_names);
+        drop_values(
+            names
+                .into_iter()
+                .map(|name| Value::from(name.value))
+                .collect(),
+        );
+    }
+}
+
+/// A function implemented by the interpreter, like the ones in the `Std` module.
+pub struct NativeFunction {
+    pub ident: String,
+    /// The number of arguments the function takes.
+    pub arity: usize,
+    pub function: Box<dyn Fn(Vec<Value>) -> Result<Value, ErrorKind>>,
+}
+
+/// A function called with fewer arguments than it takes, waiting for the
+/// others.
+pub struct PartialApplication {
+    /// A [`Unpacked::Function`] or a [`Unpacked::NativeFunction`].
+    pub function: Value,
+    pub args: Vec<Value>,
+}
+
+impl Drop for PartialApplication {
+    fn drop(&mut self) {
+        let mut values = std::mem::take(&mut self.args);
+        values.push(std::mem::replace(&mut self.function, Value::UNIT));
+        drop_values(values);
+    }
+}
+
+/// A value at runtime, packed into a single word by NaN-boxing.
+///
+/// A float is held as its own bits. Every other value is held in the bits of
+/// a NaN that no float is stored as, since the NaNs floats produce are all
+/// stored as the same one: a tag above the lowest 48 bits tells what the
+/// value is, and those bits hold it immediately, like a unit, a boolean, a
+/// char or an integer that fits in them, or hold a pointer to the `Rc` it is
+/// in. Integers that do not fit are moved to the heap.
+///
+/// It is taken apart with [`Value::unpack`] or [`Value::view`] to match on
+/// what it holds.
+pub struct Value {
+    bits: u64,
+    /// A value may own an `Rc`, so it is neither `Send` nor `Sync`.
+    _rc: PhantomData<Rc<()>>,
+}
+
+/// The tags of values that are not floats. The ones from
+/// [`tag::BOXED_INTEGER`] on hold a pointer.
+mod tag {
+    pub const FLOAT: u64 = 0;
+    pub const UNIT: u64 = 1;
+    pub const BOOLEAN: u64 = 2;
+    pub const CHAR: u64 = 3;
+    pub const INTEGER: u64 = 4;
+    pub const SIZED_INTEGER: u64 = 5;
+    pub const BOXED_INTEGER: u64 = 6;
+    pub const BIG_INTEGER: u64 = 7;
+    pub const RATIONAL: u64 = 8;
+    pub const REF: u64 = 9;
+    pub const REFERENCE: u64 = 10;
+    pub const OBJECT: u64 = 11;
+    pub const FUNCTION: u64 = 12;
+    pub const NATIVE_FUNCTION: u64 = 13;
+    pub const PARTIAL_APPLICATION: u64 = 14;
+}
+
+const TAG_SHIFT: u32 = 48;
+/// The bits of negative infinity. Every value that is not a float has them
+/// set, and a non-zero tag below them, so it is a NaN with the sign bit set.
+const BOXED: u64 = 0xFFF0 << TAG_SHIFT;
+const PAYLOAD: u64 = (1 << TAG_SHIFT) - 1;
+/// The NaN every NaN float is stored as.
+const CANONICAL_NAN: u64 = 0x7FF8 << TAG_SHIFT;
+/// The bits of an `int` held immediately.
+const INTEGER_BITS: u32 = TAG_SHIFT;
+/// The bits of any other integer held immediately, below the index of its
+/// kind in [`IntegerKind::ALL`].
+const SIZED_INTEGER_BITS: u32 = TAG_SHIFT - 3;
+
+/// An integer that does not fit in a [`Value`], moved to the heap.
+struct BoxedInteger {
+    value: i64,
+    kind: IntegerKind,
+}
+
+/// A [`Value`] taken apart.
 #[derive(Clone)]
-pub enum Value {
+pub enum Unpacked {
     Unit,
     Boolean(bool),
     Char(char),
     Integer(i64),
+    /// An integer of any kind other than `int`, always within the range of its
+    /// kind. A `u64` above the range of `i64` keeps its bits, see
+    /// [`Value::as_integer`].
+    SizedInteger(i64, IntegerKind),
+    BigInteger(Rc<BigInt>),
+    Rational(Rc<BigRational>),
     Float(f64),
+    /// A value moved to the heap to be shared, like a captured name. It is
+    /// read and written through transparently.
     Ref(Rc<RefCell<Value>>),
+    /// A reference created with `&` or `&mut`.
+    Reference(Rc<Reference>),
     Object(Rc<RefCell<Object>>),
     Function(Rc<RefCell<Function>>),
+    NativeFunction(Rc<NativeFunction>),
+    PartialApplication(Rc<PartialApplication>),
 }
 
-impl PartialEq for Value {
+/// A [`Value`] taken apart without taking it, see [`Value::view`].
+pub struct View<'a> {
+    unpacked: ManuallyDrop<Unpacked>,
+    _value: PhantomData<&'a Value>,
+}
+
+impl Deref for View<'_> {
+    type Target = Unpacked;
+
+    fn deref(&self) -> &Unpacked {
+        &self.unpacked
+    }
+}
+
+impl Value {
+    pub const UNIT: Value = Value::new(tag::UNIT, 0);
+
+    const fn new(tag: u64, payload: u64) -> Value {
+        Value {
+            bits: BOXED | tag << TAG_SHIFT | payload,
+            _rc: PhantomData,
+        }
+    }
+
+    fn from_rc<T>(tag: u64, rc: Rc<T>) -> Value {
+        let address = Rc::into_raw(rc) as usize as u64;
+        assert!(address <= PAYLOAD, "Pointer does not fit in a value.");
+        Value::new(tag, address)
+    }
+
+    #[inline]
+    fn tag(&self) -> u64 {
+        if self.bits > BOXED {
+            self.bits >> TAG_SHIFT & 0xF
+        } else {
+            tag::FLOAT
+        }
+    }
+
+    /// Whether the value has `tag`, which is any tag but [`tag::FLOAT`].
+    #[inline]
+    fn has_tag(&self, tag: u64) -> bool {
+        self.bits >> TAG_SHIFT == BOXED >> TAG_SHIFT | tag
+    }
+
+    #[inline]
+    fn payload(&self) -> u64 {
+        self.bits & PAYLOAD
+    }
+
+    #[inline]
+    fn is_pointer(&self) -> bool {
+        self.bits >= BOXED | tag::BOXED_INTEGER << TAG_SHIFT
+    }
+
+    fn pointer<T>(&self) -> *const T {
+        self.payload() as usize as *const T
+    }
+
+    /// Takes the value apart, moving what it holds out of it.
+    #[inline]
+    pub fn unpack(self) -> Unpacked {
+        // SAFETY: the count of the `Rc` the value holds moves to the one
+        // unpacked, except for an integer moved to the heap, which is copied
+        // out of it.
+        let unpacked = unsafe { self.decode() };
+        if self.tag() != tag::BOXED_INTEGER {
+            std::mem::forget(self);
+        }
+        unpacked
+    }
+
+    /// Takes the value apart to look at what it holds. The `Rc`s in it are
+    /// the ones of the value, so cloning them counts them as usual.
+    #[inline]
+    pub fn view(&self) -> View<'_> {
+        View {
+            // SAFETY: the view only lends the `Rc`s it shares with the value,
+            // which outlives it, and never drops them.
+            unpacked: ManuallyDrop::new(unsafe { self.decode() }),
+            _value: PhantomData,
+        }
+    }
+
+    /// Takes the value apart without changing the count of the `Rc` it holds.
+    ///
+    /// # Safety
+    ///
+    /// The `Rc` in the result, if any, must not be dropped unless the value
+    /// is forgotten instead.
+    unsafe fn decode(&self) -> Unpacked {
+        let payload = self.payload();
+        match self.tag() {
+            tag::FLOAT => Unpacked::Float(f64::from_bits(self.bits)),
+            tag::UNIT => Unpacked::Unit,
+            tag::BOOLEAN => Unpacked::Boolean(payload != 0),
+            tag::CHAR => Unpacked::Char(char::from_u32_unchecked(payload as u32)),
+            tag::INTEGER => Unpacked::Integer(sign_extend(payload, INTEGER_BITS)),
+            tag::SIZED_INTEGER => Unpacked::SizedInteger(
+                sign_extend(payload, SIZED_INTEGER_BITS),
+                IntegerKind::ALL[(payload >> SIZED_INTEGER_BITS) as usize],
+            ),
+            tag::BOXED_INTEGER => {
+                let boxed = &*self.pointer::<BoxedInteger>();
+                match boxed.kind {
+                    IntegerKind::I64 => Unpacked::Integer(boxed.value),
+                    kind => Unpacked::SizedInteger(boxed.value, kind),
+                }
+            }
+            tag::BIG_INTEGER => Unpacked::BigInteger(Rc::from_raw(self.pointer())),
+            tag::RATIONAL => Unpacked::Rational(Rc::from_raw(self.pointer())),
+            tag::REF => Unpacked::Ref(Rc::from_raw(self.pointer())),
+            tag::REFERENCE => Unpacked::Reference(Rc::from_raw(self.pointer())),
+            tag::OBJECT => Unpacked::Object(Rc::from_raw(self.pointer())),
+            tag::FUNCTION => Unpacked::Function(Rc::from_raw(self.pointer())),
+            tag::NATIVE_FUNCTION => Unpacked::NativeFunction(Rc::from_raw(self.pointer())),
+            tag::PARTIAL_APPLICATION => Unpacked::PartialApplication(Rc::from_raw(self.pointer())),
+            tag => unreachable!("Unknown value tag {tag}."),
+        }
+    }
+
+    /// Creates a value of the given integer kind held immediately if it fits,
+    /// and moved to the heap otherwise. `value` is the bits of a `u64`.
+    fn from_integer(value: i64, kind: IntegerKind) -> Value {
+        let (tag, bits) = match kind {
+            IntegerKind::I64 => (tag::INTEGER, INTEGER_BITS),
+            _ => (tag::SIZED_INTEGER, SIZED_INTEGER_BITS),
+        };
+        if sign_extend(value as u64, bits) != value {
+            return Value::from_rc(tag::BOXED_INTEGER, Rc::new(BoxedInteger { value, kind }));
+        }
+        let payload = value as u64 & ((1 << bits) - 1);
+        match kind {
+            IntegerKind::I64 => Value::new(tag, payload),
+            _ => {
+                let index = IntegerKind::ALL.iter().position(|k| *k == kind).unwrap() as u64;
+                Value::new(tag, index << SIZED_INTEGER_BITS | payload)
+            }
+        }
+    }
+
+    /// The `int` held immediately, if the value is one. It is meant for the
+    /// most common cases, which skip taking the value apart.
+    #[inline]
+    pub fn as_immediate_integer(&self) -> Option<i64> {
+        self.has_tag(tag::INTEGER)
+            .then(|| sign_extend(self.payload(), INTEGER_BITS))
+    }
+
+    /// The cell the value was moved to, if it is a [`Unpacked::Ref`].
+    #[inline]
+    pub fn as_cell(&self) -> Option<&RefCell<Value>> {
+        // SAFETY: the value holds a count of the `Rc` the cell is in, so it
+        // lives at least as long as the value.
+        self.has_tag(tag::REF)
+            .then(|| unsafe { &*self.pointer::<RefCell<Value>>() })
+    }
+
+    /// The struct or array, if the value is one.
+    #[inline]
+    pub fn as_object(&self) -> Option<&RefCell<Object>> {
+        // SAFETY: as in `as_cell`.
+        self.has_tag(tag::OBJECT)
+            .then(|| unsafe { &*self.pointer::<RefCell<Object>>() })
+    }
+
+    /// The function defined in the language, if the value is one.
+    #[inline]
+    pub fn as_function(&self) -> Option<&RefCell<Function>> {
+        // SAFETY: as in `as_cell`.
+        self.has_tag(tag::FUNCTION)
+            .then(|| unsafe { &*self.pointer::<RefCell<Function>>() })
+    }
+}
+
+/// Reads the lowest `bits` bits of `payload` as a signed integer.
+#[inline]
+fn sign_extend(payload: u64, bits: u32) -> i64 {
+    ((payload << (64 - bits)) as i64) >> (64 - bits)
+}
+
+impl Clone for Value {
+    #[inline]
+    fn clone(&self) -> Self {
+        if self.is_pointer() {
+            // SAFETY: the value holds a count of the `Rc` it points to, so the
+            // `Rc` is alive, and the pointer is the one of the type its tag
+            // tells.
+            unsafe {
+                match self.tag() {
+                    tag::BOXED_INTEGER => {
+                        Rc::increment_strong_count(self.pointer::<BoxedInteger>())
+                    }
+                    tag::BIG_INTEGER => Rc::increment_strong_count(self.pointer::<BigInt>()),
+                    tag::RATIONAL => Rc::increment_strong_count(self.pointer::<BigRational>()),
+                    tag::REF => Rc::increment_strong_count(self.pointer::<RefCell<Value>>()),
+                    tag::REFERENCE => Rc::increment_strong_count(self.pointer::<Reference>()),
+                    tag::OBJECT => Rc::increment_strong_count(self.pointer::<RefCell<Object>>()),
+                    tag::FUNCTION => {
+                        Rc::increment_strong_count(self.pointer::<RefCell<Function>>())
+                    }
+                    tag::NATIVE_FUNCTION => {
+                        Rc::increment_strong_count(self.pointer::<NativeFunction>())
+                    }
+                    tag::PARTIAL_APPLICATION => {
+                        Rc::increment_strong_count(self.pointer::<PartialApplication>())
+                    }
+                    tag => unreachable!("Value with tag {tag} is not a pointer."),
+                }
+            }
+        }
+        Value {
+            bits: self.bits,
+            _rc: PhantomData,
+        }
+    }
+}
+
+impl Drop for Value {
+    #[inline]
+    fn drop(&mut self) {
+        if self.is_pointer() {
+            // SAFETY: the value owns a count of the `Rc` it points to, which
+            // is dropped with it, and the pointer is the one of the type its
+            // tag tells.
+            unsafe {
+                match self.tag() {
+                    tag::BOXED_INTEGER => {
+                        Rc::decrement_strong_count(self.pointer::<BoxedInteger>())
+                    }
+                    tag::BIG_INTEGER => Rc::decrement_strong_count(self.pointer::<BigInt>()),
+                    tag::RATIONAL => Rc::decrement_strong_count(self.pointer::<BigRational>()),
+                    tag::REF => Rc::decrement_strong_count(self.pointer::<RefCell<Value>>()),
+                    tag::REFERENCE => Rc::decrement_strong_count(self.pointer::<Reference>()),
+                    tag::OBJECT => Rc::decrement_strong_count(self.pointer::<RefCell<Object>>()),
+                    tag::FUNCTION => {
+                        Rc::decrement_strong_count(self.pointer::<RefCell<Function>>())
+                    }
+                    tag::NATIVE_FUNCTION => {
+                        Rc::decrement_strong_count(self.pointer::<NativeFunction>())
+                    }
+                    tag::PARTIAL_APPLICATION => {
+                        Rc::decrement_strong_count(self.pointer::<PartialApplication>())
+                    }
+                    tag => unreachable!("Value with tag {tag} is not a pointer."),
+                }
+            }
+        }
+    }
+}
+
+impl From<Unpacked> for Value {
+    fn from(value: Unpacked) -> Self {
+        match value {
+            Unpacked::Unit => Value::UNIT,
+            Unpacked::Boolean(boolean) => Value::from(boolean),
+            Unpacked::Char(char) => Value::from(char),
+            Unpacked::Integer(integer) => Value::from(integer),
+            Unpacked::SizedInteger(integer, kind) => Value::from_integer(integer, kind),
+            Unpacked::Float(float) => Value::from(float),
+            Unpacked::BigInteger(integer) => Value::from_rc(tag::BIG_INTEGER, integer),
+            Unpacked::Rational(rational) => Value::from_rc(tag::RATIONAL, rational),
+            Unpacked::Ref(cell) => Value::from_rc(tag::REF, cell),
+            Unpacked::Reference(reference) => Value::from_rc(tag::REFERENCE, reference),
+            Unpacked::Object(object) => Value::from_rc(tag::OBJECT, object),
+            Unpacked::Function(function) => Value::from_rc(tag::FUNCTION, function),
+            Unpacked::NativeFunction(native) => Value::from_rc(tag::NATIVE_FUNCTION, native),
+            Unpacked::PartialApplication(partial) => {
+                Value::from_rc(tag::PARTIAL_APPLICATION, partial)
+            }
+        }
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::new(tag::BOOLEAN, value as u64)
+    }
+}
+
+impl From<char> for Value {
+    fn from(value: char) -> Self {
+        Value::new(tag::CHAR, value as u64)
+    }
+}
+
+impl From<i64> for Value {
+    #[inline]
+    fn from(value: i64) -> Self {
+        Value::from_integer(value, IntegerKind::I64)
+    }
+}
+
+impl From<f64> for Value {
+    #[inline]
+    fn from(value: f64) -> Self {
+        let bits = if value.is_nan() {
+            CANONICAL_NAN
+        } else {
+            value.to_bits()
+        };
+        Value {
+            bits,
+            _rc: PhantomData,
+        }
+    }
+}
+
+macro_rules! impl_from_rc {
+    ($($ty:ty => $variant:ident),* $(,)?) => {
+        $(
+            impl From<Rc<$ty>> for Value {
+                fn from(value: Rc<$ty>) -> Self {
+                    Value::from(Unpacked::$variant(value))
+                }
+            }
+        )*
+    };
+}
+
+impl_from_rc! {
+    BigInt => BigInteger,
+    BigRational => Rational,
+    RefCell<Value> => Ref,
+    Reference => Reference,
+    RefCell<Object> => Object,
+    RefCell<Function> => Function,
+    NativeFunction => NativeFunction,
+    PartialApplication => PartialApplication,
+}
+
+impl PartialEq for Unpacked {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
             (Self::Boolean(l0), Self::Boolean(r0)) => l0 == r0,
             (Self::Char(l0), Self::Char(r0)) => l0 == r0,
             (Self::Integer(l0), Self::Integer(r0)) => l0 == r0,
+            (Self::SizedInteger(l0, l1), Self::SizedInteger(r0, r1)) => l0 == r0 && l1 == r1,
+            (Self::BigInteger(l0), Self::BigInteger(r0)) => l0 == r0,
+            (Self::Rational(l0), Self::Rational(r0)) => l0 == r0,
             (Self::Float(l0), Self::Float(r0)) => l0 == r0,
             (Self::Ref(l0), Self::Ref(r0)) => l0 == r0,
+            (Self::Reference(l0), Self::Reference(r0)) => l0 == r0,
             (Self::Object(l0), Self::Object(r0)) => Rc::ptr_eq(l0, r0),
             (Self::Function(l0), Self::Function(r0)) => Rc::ptr_eq(l0, r0),
+            (Self::NativeFunction(l0), Self::NativeFunction(r0)) => Rc::ptr_eq(l0, r0),
+            (Self::PartialApplication(l0), Self::PartialApplication(r0)) => Rc::ptr_eq(l0, r0),
             _ => core::mem::discriminant(self) == core::mem::discriminant(other),
         }
     }
 }
 
+impl PartialEq for Value {
+    fn eq(&self, other: &Self) -> bool {
+        *self.view() == *other.view()
+    }
+}
+
 impl Value {
+    #[inline]
     pub fn deref_value(self) -> Value {
-        match self {
-            Value::Unit
-            | Value::Boolean(_)
-            | Value::Char(_)
-            | Value::Integer(_)
-            | Value::Float(_)
-            | Value::Object(_)
-            | Value::Function(_) => self,
-            Value::Ref(value) => value.borrow().deref().clone(),
+        match self.as_cell() {
+            Some(cell) => cell.borrow().clone(),
+            None => self,
+        }
+    }
+
+    /// Creates a value of the given integer kind, if it is within its range.
+    pub fn integer(value: i128, kind: IntegerKind) -> Option<Value> {
+        // Truncating keeps the bits of a `u64`.
+        kind.contains(value)
+            .then(|| Value::from_integer(value as i64, kind))
+    }
+
+    pub fn as_integer(&self) -> Option<(i128, IntegerKind)> {
+        match &*self.view() {
+            Unpacked::Integer(i) => Some((*i as i128, IntegerKind::I64)),
+            Unpacked::SizedInteger(i, kind) => Some((sized_integer(*i, *kind), *kind)),
+            Unpacked::Ref(value) => value.borrow().as_integer(),
+            _ => None,
+        }
+    }
+
+    /// Gets the integers of two values of the same integer kind.
+    fn integer_operands(&self, other: &Value) -> Option<(i128, i128, IntegerKind)> {
+        match (self.as_integer()?, other.as_integer()?) {
+            ((l, l_kind), (r, r_kind)) if l_kind == r_kind => Some((l, r, l_kind)),
+            _ => None,
+        }
+    }
+
+    /// Calls `f` with the value, or with the one it holds if it was moved to
+    /// the heap, without copying it.
+    fn with_inner<T>(&self, f: impl FnOnce(&Value) -> T) -> T {
+        match self.as_cell() {
+            Some(cell) => cell.borrow().with_inner(f),
+            None => f(self),
         }
     }
 
     #[inline]
     pub fn is_ref(&self) -> bool {
-        match self {
-            Value::Ref(_) => true,
-            _ => false,
-        }
+        self.has_tag(tag::REF)
+    }
+
+    /// Whether the value is a big integer or a rational.
+    #[inline]
+    pub fn is_big_number(&self) -> bool {
+        self.has_tag(tag::BIG_INTEGER) || self.has_tag(tag::RATIONAL)
     }
 
     pub fn set_inner_value(&mut self, value: Value) {
-        match self {
-            Value::Ref(v) => v.borrow_mut().set_inner_value(value),
-            this => *this = value,
+        match self.as_cell() {
+            Some(cell) => cell.borrow_mut().set_inner_value(value),
+            None => *self = value,
         }
     }
 
-    pub fn add(self, other: Value) -> Option<Value> {
-        match (self.deref_value(), other.deref_value()) {
-            (Value::Float(l), Value::Float(r)) => Some(Value::Float(l + r)),
-            (Value::Integer(l), Value::Integer(r)) => Some(Value::Integer(l + r)),
-            _ => None,
+    /// Gets the field or element at the end of `path`, or the value itself
+    /// if the path is empty.
+    pub fn get_at(&self, path: &[usize]) -> Value {
+        path.iter()
+            .fold(self.clone().deref_value(), |value, index| {
+                value
+                    .as_object()
+                    .expect("Value is not a struct or an array.")
+                    .borrow()
+                    .get_value(*index)
+                    .expect("Unable to find field or element.")
+                    .deref_value()
+            })
+    }
+
+    /// Sets the field or element at the end of `path`, or the value itself
+    /// if the path is empty. Structs and arrays are shared until they are
+    /// changed: the ones along the path that other values still hold are
+    /// copied first, so the change is only seen through this value.
+    pub fn set_at(&mut self, path: &[usize], value: Value, heap: &mut Heap) {
+        let Some((index, rest)) = path.split_first() else {
+            return self.set_inner_value(value);
+        };
+        if let Some(cell) = self.as_cell() {
+            return cell.borrow_mut().set_at(path, value, heap);
         }
+        let copy = match &*self.view() {
+            Unpacked::Object(object) if Rc::strong_count(object) > 1 => {
+                Some(object.borrow().clone())
+            }
+            Unpacked::Object(_) => None,
+            _ => panic!("Value is not a struct or an array."),
+        };
+        if let Some(copy) = copy {
+            *self = Value::from(heap.alloc_object(copy));
+        }
+        self.as_object()
+            .expect("Value is a struct or an array.")
+            .borrow_mut()
+            .get_value_mut(*index)
+            .expect("Unable to find field or element.")
+            .set_at(rest, value, heap);
     }
 
-    pub fn sub(self, other: Value) -> Option<Value> {
-        match (self.deref_value(), other.deref_value()) {
-            (Value::Float(l), Value::Float(r)) => Some(Value::Float(l - r)),
-            (Value::Integer(l), Value::Integer(r)) => Some(Value::Integer(l - r)),
-            _ => None,
+    pub fn add(self, other: Value) -> Result<Value, ErrorKind> {
+        let (lhs, rhs) = (self.deref_value(), other.deref_value());
+        match (&*lhs.view(), &*rhs.view()) {
+            (Unpacked::Float(l), Unpacked::Float(r)) => Ok(Value::from(l + r)),
+            (Unpacked::BigInteger(l), Unpacked::BigInteger(r)) => {
+                Ok(Value::from(Rc::new(&**l + &**r)))
+            }
+            (Unpacked::Rational(l), Unpacked::Rational(r)) => Ok(Value::from(Rc::new(&**l + &**r))),
+            _ => match lhs.integer_operands(&rhs) {
+                Some((l, r, kind)) => {
+                    Value::integer(l + r, kind).ok_or(ErrorKind::IntegerOverflow {
+                        operator: ArithmeticOperator::Add,
+                        lhs: l,
+                        rhs: r,
+                    })
+                }
+                None => Err(ErrorKind::Internal("Unable to add values.".to_string())),
+            },
         }
     }
 
-    pub fn mul(self, other: Value) -> Option<Value> {
-        match (self.deref_value(), other.deref_value()) {
-            (Value::Float(l), Value::Float(r)) => Some(Value::Float(l * r)),
-            (Value::Integer(l), Value::Integer(r)) => Some(Value::Integer(l * r)),
-            _ => None,
+    pub fn sub(self, other: Value) -> Result<Value, ErrorKind> {
+        let (lhs, rhs) = (self.deref_value(), other.deref_value());
+        match (&*lhs.view(), &*rhs.view()) {
+            (Unpacked::Float(l), Unpacked::Float(r)) => Ok(Value::from(l - r)),
+            (Unpacked::BigInteger(l), Unpacked::BigInteger(r)) => {
+                Ok(Value::from(Rc::new(&**l - &**r)))
+            }
+            (Unpacked::Rational(l), Unpacked::Rational(r)) => Ok(Value::from(Rc::new(&**l - &**r))),
+            _ => match lhs.integer_operands(&rhs) {
+                Some((l, r, kind)) => {
+                    Value::integer(l - r, kind).ok_or(ErrorKind::IntegerOverflow {
+                        operator: ArithmeticOperator::Sub,
+                        lhs: l,
+                        rhs: r,
+                    })
+                }
+                None => Err(ErrorKind::Internal(
+                    "Unable to subtract values.".to_string(),
+                )),
+            },
         }
     }
 
-    pub fn div(self, other: Value) -> Option<Value> {
-        match (self.deref_value(), other.deref_value()) {
-            (Value::Float(l), Value::Float(r)) => Some(Value::Float(l / r)),
-            (Value::Integer(l), Value::Integer(r)) => Some(Value::Integer(l / r)),
-            _ => None,
+    pub fn mul(self, other: Value) -> Result<Value, ErrorKind> {
+        let (lhs, rhs) = (self.deref_value(), other.deref_value());
+        match (&*lhs.view(), &*rhs.view()) {
+            (Unpacked::Float(l), Unpacked::Float(r)) => Ok(Value::from(l * r)),
+            (Unpacked::BigInteger(l), Unpacked::BigInteger(r)) => {
+                Ok(Value::from(Rc::new(&**l * &**r)))
+            }
+            (Unpacked::Rational(l), Unpacked::Rational(r)) => Ok(Value::from(Rc::new(&**l * &**r))),
+            _ => match lhs.integer_operands(&rhs) {
+                Some((l, r, kind)) => l
+                    .checked_mul(r)
+                    .and_then(|v| Value::integer(v, kind))
+                    .ok_or(ErrorKind::IntegerOverflow {
+                        operator: ArithmeticOperator::Mul,
+                        lhs: l,
+                        rhs: r,
+                    }),
+                None => Err(ErrorKind::Internal(
+                    "Unable to multiply values.".to_string(),
+                )),
+            },
         }
     }
 
-    pub fn are_equal(&self, other: &Value) -> bool {
-        match (self.clone().deref_value(), other.clone().deref_value()) {
-            (Value::Unit, Value::Unit) => true,
-            (Value::Boolean(v1), Value::Boolean(v2)) => v1 == v2,
-            (Value::Char(v1), Value::Char(v2)) => v1 == v2,
-            (Value::Integer(v1), Value::Integer(v2)) => v1 == v2,
-            (Value::Float(v1), Value::Float(v2)) => v1 == v2,
-            (Value::Ref(v1), Value::Ref(v2)) => v1.borrow().are_equal(&v2.borrow()),
-            (Value::Object(v1), Value::Object(v2)) => v1.borrow().equals(&v2.borrow()),
-            (Value::Function(v1), Value::Function(v2)) => Rc::ptr_eq(&v1, &v2),
-            _ => unreachable!(),
+    /// Integer division truncates towards zero while rational division is exact.
+    /// Float division follows IEEE 754, so dividing a float by zero results in
+    /// an infinity or NaN.
+    pub fn div(self, other: Value) -> Result<Value, ErrorKind> {
+        let (lhs, rhs) = (self.deref_value(), other.deref_value());
+        match (&*lhs.view(), &*rhs.view()) {
+            (Unpacked::Float(l), Unpacked::Float(r)) => Ok(Value::from(l / r)),
+            (Unpacked::BigInteger(_), Unpacked::BigInteger(r)) if r.is_zero() => {
+                Err(ErrorKind::DivisionByZero)
+            }
+            (Unpacked::BigInteger(l), Unpacked::BigInteger(r)) => {
+                Ok(Value::from(Rc::new(&**l / &**r)))
+            }
+            (Unpacked::Rational(_), Unpacked::Rational(r)) if r.is_zero() => {
+                Err(ErrorKind::DivisionByZero)
+            }
+            (Unpacked::Rational(l), Unpacked::Rational(r)) => Ok(Value::from(Rc::new(&**l / &**r))),
+            _ => match lhs.integer_operands(&rhs) {
+                Some((_, 0, _)) => Err(ErrorKind::DivisionByZero),
+                Some((l, r, kind)) => {
+                    Value::integer(l / r, kind).ok_or(ErrorKind::IntegerOverflow {
+                        operator: ArithmeticOperator::Div,
+                        lhs: l,
+                        rhs: r,
+                    })
+                }
+                None => Err(ErrorKind::Internal("Unable to divide values.".to_string())),
+            },
         }
     }
 
+    /// The remainder has the same sign as the dividend.
+    pub fn rem(self, other: Value) -> Result<Value, ErrorKind> {
+        let (lhs, rhs) = (self.deref_value(), other.deref_value());
+        match (&*lhs.view(), &*rhs.view()) {
+            (Unpacked::Float(l), Unpacked::Float(r)) => Ok(Value::from(l % r)),
+            (Unpacked::BigInteger(_), Unpacked::BigInteger(r)) if r.is_zero() => {
+                Err(ErrorKind::DivisionByZero)
+            }
+            (Unpacked::BigInteger(l), Unpacked::BigInteger(r)) => {
+                Ok(Value::from(Rc::new(&**l % &**r)))
+            }
+            (Unpacked::Rational(_), Unpacked::Rational(r)) if r.is_zero() => {
+                Err(ErrorKind::DivisionByZero)
+            }
+            (Unpacked::Rational(l), Unpacked::Rational(r)) => Ok(Value::from(Rc::new(&**l % &**r))),
+            _ => match lhs.integer_operands(&rhs) {
+                Some((_, 0, _)) => Err(ErrorKind::DivisionByZero),
+                Some((l, r, kind)) => {
+                    Value::integer(l % r, kind).ok_or(ErrorKind::IntegerOverflow {
+                        operator: ArithmeticOperator::Mod,
+                        lhs: l,
+                        rhs: r,
+                    })
+                }
+                None => Err(ErrorKind::Internal(
+                    "Unable to calculate the remainder of values.".to_string(),
+                )),
+            },
+        }
+    }
+
+    /// Orders two numbers or chars. Returns `Ok(None)` when the values are
+    /// unordered, like when comparing against a NaN float.
+    pub fn compare(&self, other: &Value) -> Result<Option<Ordering>, ErrorKind> {
+        self.with_inner(|lhs| {
+            other.with_inner(|rhs| match (&*lhs.view(), &*rhs.view()) {
+                (Unpacked::Float(l), Unpacked::Float(r)) => Ok(l.partial_cmp(r)),
+                (Unpacked::Char(l), Unpacked::Char(r)) => Ok(l.partial_cmp(r)),
+                (Unpacked::BigInteger(l), Unpacked::BigInteger(r)) => Ok(l.partial_cmp(r)),
+                (Unpacked::Rational(l), Unpacked::Rational(r)) => Ok(l.partial_cmp(r)),
+                _ => match lhs.integer_operands(rhs) {
+                    Some((l, r, _)) => Ok(l.partial_cmp(&r)),
+                    None => Err(ErrorKind::Internal("Unable to compare values.".to_string())),
+                },
+            })
+        })
+    }
+
+    pub fn are_equal(&self, other: &Value) -> bool {
+        self.with_inner(|l| {
+            other.with_inner(|r| match (&*l.view(), &*r.view()) {
+                (Unpacked::Unit, Unpacked::Unit) => true,
+                (Unpacked::Boolean(v1), Unpacked::Boolean(v2)) => v1 == v2,
+                (Unpacked::Char(v1), Unpacked::Char(v2)) => v1 == v2,
+                (Unpacked::Integer(v1), Unpacked::Integer(v2)) => v1 == v2,
+                (Unpacked::SizedInteger(v1, _), Unpacked::SizedInteger(v2, _)) => v1 == v2,
+                (Unpacked::BigInteger(v1), Unpacked::BigInteger(v2)) => v1 == v2,
+                (Unpacked::Rational(v1), Unpacked::Rational(v2)) => v1 == v2,
+                (Unpacked::Float(v1), Unpacked::Float(v2)) => v1 == v2,
+                (Unpacked::Reference(v1), Unpacked::Reference(v2)) => v1.get().are_equal(&v2.get()),
+                (Unpacked::Object(v1), Unpacked::Object(v2)) => v1.borrow().equals(&v2.borrow()),
+                (Unpacked::Function(v1), Unpacked::Function(v2)) => Rc::ptr_eq(v1, v2),
+                (Unpacked::NativeFunction(v1), Unpacked::NativeFunction(v2)) => Rc::ptr_eq(v1, v2),
+                (Unpacked::PartialApplication(v1), Unpacked::PartialApplication(v2)) => {
+                    Rc::ptr_eq(v1, v2)
+                }
+                _ => unreachable!(),
+            })
+        })
+    }
+
+    /// Sets the value to another of the same type, or the one it holds if it
+    /// was moved to the heap.
     pub fn set(&mut self, other: Value) {
-        match (self, other) {
-            (Value::Unit, Value::Unit) => {}
-            (Value::Boolean(v1), Value::Boolean(v2)) => *v1 = v2,
-            (Value::Char(v1), Value::Char(v2)) => *v1 = v2,
-            (Value::Integer(v1), Value::Integer(v2)) => *v1 = v2,
-            (Value::Float(v1), Value::Float(v2)) => *v1 = v2,
-            (Value::Ref(v1), Value::Ref(v2)) => *v1 = v2,
-            (Value::Ref(v1), v2) => v1.borrow_mut().set(v2),
-            (Value::Object(v1), Value::Object(v2)) => *v1 = v2,
-            _ => unreachable!(),
+        match self.as_cell() {
+            Some(cell) if !other.is_ref() => cell.borrow_mut().set(other),
+            _ => *self = other,
         }
     }
 }
 
+/// The value of an integer of a kind other than `int`, whose bits are held in
+/// an `i64`.
+fn sized_integer(value: i64, kind: IntegerKind) -> i128 {
+    match kind {
+        IntegerKind::U64 => value as u64 as i128,
+        _ => value as i128,
+    }
+}
+
 impl Debug for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.view().fmt(f)
+    }
+}
+
+impl Debug for Unpacked {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Self::Unit => write!(f, "Unit"),
             Self::Boolean(arg0) => f.debug_tuple("Boolean").field(arg0).finish(),
             Self::Char(arg0) => f.debug_tuple("Char").field(arg0).finish(),
             Self::Integer(arg0) => f.debug_tuple("Integer").field(arg0).finish(),
+            Self::SizedInteger(arg0, arg1) => f
+                .debug_tuple("SizedInteger")
+                .field(&sized_integer(*arg0, *arg1))
+                .field(arg1)
+                .finish(),
+            Self::BigInteger(arg0) => write!(f, "BigInteger({arg0})"),
+            Self::Rational(arg0) => write!(f, "Rational({arg0})"),
             Self::Float(arg0) => f.debug_tuple("Float").field(arg0).finish(),
             Self::Ref(arg0) => f.debug_tuple("Ref").field(arg0).finish(),
+            Self::Reference(arg0) => f.debug_tuple("Reference").field(&arg0.get()).finish(),
             Self::Object(arg0) => f
                 .debug_tuple("Object")
                 .field(&arg0.borrow().type_id)
                 .finish(),
             Self::Function(_) => f.debug_tuple("Function").finish(),
+            Self::NativeFunction(arg0) => {
+                f.debug_tuple("NativeFunction").field(&arg0.ident).finish()
+            }
+            Self::PartialApplication(arg0) => f
+                .debug_tuple("PartialApplication")
+                .field(&arg0.function)
+                .field(&arg0.args.len())
+                .finish(),
         }
     }
 }
 
+/// The fields of a struct or the elements of an array. It is shared by the
+/// values that are copies of each other until one of them changes it, see
+/// [`Value::set_at`].
+#[derive(Clone)]
 pub struct Object {
     pub values: Vec<Value>,
     pub type_id: TypeId,
@@ -210,3 +1010,59 @@ impl Object {
                 .all(|(v1, v2)| v1.are_equal(v2))
     }
 }
+
+impl Drop for Object {
+    fn drop(&mut self) {
+        drop_values(std::mem::take(&mut self.values));
+    }
+}
+
+/// Drops values without recursing into the ones they own, which would run
+/// out of stack for a long chain, like closures that each capture the one
+/// before. What a value alone refers to is taken out of it and dropped in
+/// turn, so the value itself is dropped empty.
+fn drop_values(mut values: Vec<Value>) {
+    while let Some(value) = values.pop() {
+        if !value.is_pointer() {
+            continue;
+        }
+        match value.unpack() {
+            Unpacked::Ref(cell) => {
+                if Rc::strong_count(&cell) == 1 {
+                    values.push(cell.replace(Value::UNIT));
+                }
+            }
+            Unpacked::Reference(reference) => {
+                if Rc::strong_count(&reference) == 1 && Rc::strong_count(&reference.value) == 1 {
+                    values.push(reference.value.replace(Value::UNIT));
+                }
+            }
+            Unpacked::Object(object) => {
+                if Rc::strong_count(&object) == 1 {
+                    values.append(&mut object.borrow_mut().values);
+                }
+            }
+            Unpacked::Function(function) => {
+                if Rc::strong_count(&function) == 1 {
+                    let names = std::mem::take(&mut function.borrow_mut().captured_names);
+                    values.extend(names.into_iter().map(|name| Value::from(name.value)));
+                }
+            }
+            Unpacked::PartialApplication(partial) => {
+                if let Ok(mut partial) = Rc::try_unwrap(partial) {
+                    values.append(&mut partial.args);
+                    values.push(std::mem::replace(&mut partial.function, Value::UNIT));
+                }
+            }
+            Unpacked::Unit
+            | Unpacked::Boolean(_)
+            | Unpacked::Char(_)
+            | Unpacked::Integer(_)
+            | Unpacked::SizedInteger(..)
+            | Unpacked::BigInteger(_)
+            | Unpacked::Rational(_)
+            | Unpacked::Float(_)
+            | Unpacked::NativeFunction(_) => {}
+        }
+    }
+}
@@ -1,38 +1,153 @@
-use crate::object::Value;
+use std::{cell::RefCell, rc::Rc};
+
+use crate::{
+    heap::Heap,
+    object::{Unpacked, Value},
+};
 
 #[derive(Default)]
 pub struct ValueStack {
     values: Vec<Value>,
     frames: Vec<Frame>,
+    /// Where the innermost frame starts, or 0 without frames.
+    base: usize,
 }
 
 impl ValueStack {
+    #[inline]
     pub fn push_frame(&mut self) {
         let top = self.values.len();
-        self.frames.push(Frame { top })
+        self.frames.push(Frame { top });
+        self.base = top;
+    }
+
+    /// Pushes a frame for a call, which starts with the `args` values on top.
+    #[inline]
+    pub fn push_call_frame(&mut self, args: usize) {
+        let top = self.values.len() - args;
+        self.frames.push(Frame { top });
+        self.base = top;
     }
 
+    #[inline]
     pub fn pop_frame(&mut self) -> Frame {
         let frame = self.frames.pop().expect("No frames to pop.");
-        self.values.drain(frame.top..);
+        self.values.truncate(frame.top);
+        self.reset_base();
         frame
     }
 
+    fn reset_base(&mut self) {
+        self.base = self.frames.last().map_or(0, |frame| frame.top);
+    }
+
+    #[inline]
     pub fn push_value(&mut self, value: Value) -> usize {
         let sp = self.values.len();
         self.values.push(value);
         sp
     }
 
+    #[inline]
+    pub fn pop_value(&mut self) -> Value {
+        self.values.pop().expect("No values to pop.")
+    }
+
+    /// Pops the `n` values on top, the lowest one first.
+    pub fn pop_values(&mut self, n: usize) -> Vec<Value> {
+        let len = self.values.len();
+        self.values.split_off(len - n)
+    }
+
+    /// The value `depth` values below the one on top.
+    #[inline]
+    pub fn peek(&self, depth: usize) -> &Value {
+        &self.values[self.values.len() - 1 - depth]
+    }
+
+    /// Sets the value in a slot, or the value on the heap a slot refers to.
     pub fn set_value(&mut self, sp: usize, value: Value) -> Option<Value> {
         let v = self.values.get_mut(sp)?;
-        v.set(value);
-        Some(v.clone())
+        v.set_inner_value(value.clone());
+        Some(value)
+    }
+
+    /// Moves the value in a slot to the heap, leaving a reference to it in
+    /// the slot, unless it is there already. Returns the value on the heap.
+    pub fn box_value(&mut self, sp: usize, heap: &mut Heap) -> Option<Rc<RefCell<Value>>> {
+        let v = self.values.get_mut(sp)?;
+        if let Unpacked::Ref(value) = &*v.view() {
+            return Some(value.clone());
+        }
+        let value = heap.alloc_cell(std::mem::replace(v, Value::UNIT));
+        *v = Value::from(value.clone());
+        Some(value)
+    }
+
+    /// The number of values on the stack.
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Drops the values above the first `len` ones.
+    pub fn truncate(&mut self, len: usize) {
+        self.values.truncate(len);
+    }
+
+    /// The number of frames on the stack.
+    pub fn frames(&self) -> usize {
+        self.frames.len()
+    }
+
+    /// Drops the frames above the first `len` ones, without their values.
+    pub fn truncate_frames(&mut self, len: usize) {
+        self.frames.truncate(len);
+        self.reset_base();
+    }
+
+    /// Drops the frames above the first `frames` ones, with their values and
+    /// the `below` values under them, except for the `keep` values on top,
+    /// which take their place.
+    pub fn replace_frames(&mut self, frames: usize, below: usize, keep: usize) {
+        let top = self.frames[frames].top - below;
+        let len = self.values.len();
+        self.values.drain(top..len - keep);
+        self.frames.truncate(frames);
+        self.reset_base();
+    }
+
+    /// The position of a local declared `depth` frames out from the
+    /// innermost one, in slot `slot` of that frame. The top level has no
+    /// frame, so it is outside of all of them.
+    #[inline]
+    pub fn local_sp(&self, depth: usize, slot: usize) -> usize {
+        if depth == 0 {
+            return self.base + slot;
+        }
+        let top = match self.frames.len().checked_sub(depth + 1) {
+            Some(frame) => self.frames[frame].top,
+            None => 0,
+        };
+        top + slot
+    }
+
+    /// The value in a slot, read from the heap if it was moved there.
+    #[inline]
+    pub fn read_value(&self, sp: usize) -> Option<Value> {
+        let value = self.values.get(sp)?;
+        match value.as_cell() {
+            Some(cell) => Some(cell.borrow().clone()),
+            None => Some(value.clone()),
+        }
     }
 
     pub fn get_value(&self, sp: usize) -> Option<Value> {
         self.values.get(sp).cloned()
     }
+
+    pub fn get_value_mut(&mut self, sp: usize) -> Option<&mut Value> {
+        self.values.get_mut(sp)
+    }
 }
 
 pub struct Frame {
@@ -0,0 +1,123 @@
+use std::rc::Rc;
+
+use parser::{op::ArithmeticOperator, IntegerKind};
+
+use crate::{
+    error::ErrorKind,
+    module::Module,
+    object::{NativeFunction, Value},
+    r#type::{PrimitiveType, TypeId, TypeRegistry},
+};
+
+type IntegerOperation = fn(i128, i128, IntegerKind) -> Result<i128, ErrorKind>;
+
+/// Creates the `Std` module with the functions implemented by the interpreter.
+pub fn std_module(type_registry: &mut TypeRegistry) -> Module {
+    let mut module = Module::new("Std");
+    for kind in IntegerKind::ALL {
+        module.insert_module(
+            &kind.name().to_uppercase(),
+            integer_module(type_registry, kind),
+        );
+    }
+    module
+}
+
+/// Creates a module like `Std.U8` with wrapping, checked and saturating
+/// arithmetic for an integer kind. Checked arithmetic fails with an overflow
+/// like the operators do. Operands are at most 64 bits wide so only their
+/// multiplication can overflow an `i128`.
+fn integer_module(type_registry: &mut TypeRegistry, kind: IntegerKind) -> Module {
+    let mut module = Module::new(&kind.name().to_uppercase());
+    let type_id = binary_function_type(type_registry, PrimitiveType::from_integer_kind(kind));
+
+    let operations: [(&str, IntegerOperation); 9] = [
+        ("wrapping_add", |l, r, kind| {
+            Ok(wrap(l.wrapping_add(r), kind))
+        }),
+        ("wrapping_sub", |l, r, kind| {
+            Ok(wrap(l.wrapping_sub(r), kind))
+        }),
+        ("wrapping_mul", |l, r, kind| {
+            Ok(wrap(l.wrapping_mul(r), kind))
+        }),
+        ("checked_add", |l, r, kind| {
+            check(l.checked_add(r), ArithmeticOperator::Add, l, r, kind)
+        }),
+        ("checked_sub", |l, r, kind| {
+            check(l.checked_sub(r), ArithmeticOperator::Sub, l, r, kind)
+        }),
+        ("checked_mul", |l, r, kind| {
+            check(l.checked_mul(r), ArithmeticOperator::Mul, l, r, kind)
+        }),
+        ("saturating_add", |l, r, kind| Ok(saturate(l + r, kind))),
+        ("saturating_sub", |l, r, kind| Ok(saturate(l - r, kind))),
+        ("saturating_mul", |l, r, kind| {
+            let overflow = if (l < 0) == (r < 0) {
+                i128::MAX
+            } else {
+                i128::MIN
+            };
+            Ok(saturate(l.checked_mul(r).unwrap_or(overflow), kind))
+        }),
+    ];
+
+    for (ident, operation) in operations {
+        let function = NativeFunction {
+            ident: format!("Std.{}.{ident}", kind.name().to_uppercase()),
+            arity: 2,
+            function: Box::new(move |args| {
+                let [l, r] = args.as_slice() else {
+                    return Err(ErrorKind::ArgumentCount {
+                        expected: 2,
+                        found: args.len(),
+                    });
+                };
+                let (Some((l, _)), Some((r, _))) = (l.as_integer(), r.as_integer()) else {
+                    return Err(ErrorKind::Internal(
+                        "Expected integer arguments.".to_string(),
+                    ));
+                };
+                Ok(Value::integer(operation(l, r, kind)?, kind)
+                    .expect("Result is within the range of its kind."))
+            }),
+        };
+        module.insert_name(ident, Value::from(Rc::new(function)), type_id);
+    }
+
+    module
+}
+
+fn binary_function_type(type_registry: &mut TypeRegistry, primitive: PrimitiveType) -> TypeId {
+    let type_id = primitive.type_id();
+    type_registry.insert_or_get_function_type(vec![type_id, type_id], type_id)
+}
+
+/// Wraps a value around the range of an integer kind, as two's complement would.
+fn wrap(value: i128, kind: IntegerKind) -> i128 {
+    let modulus = 1 << kind.bits();
+    let value = value.rem_euclid(modulus);
+    if value > kind.max() {
+        value - modulus
+    } else {
+        value
+    }
+}
+
+/// Fails with an overflow unless the value is within the range of an integer
+/// kind.
+fn check(
+    value: Option<i128>,
+    operator: ArithmeticOperator,
+    lhs: i128,
+    rhs: i128,
+    kind: IntegerKind,
+) -> Result<i128, ErrorKind> {
+    value
+        .filter(|value| (kind.min()..=kind.max()).contains(value))
+        .ok_or(ErrorKind::IntegerOverflow { operator, lhs, rhs })
+}
+
+fn saturate(value: i128, kind: IntegerKind) -> i128 {
+    value.clamp(kind.min(), kind.max())
+}
@@ -0,0 +1,73 @@
+/// Number of single character insertions, deletions, substitutions and
+/// swaps of adjacent characters needed to turn `a` into `b`.
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a = a.chars().collect::<Vec<_>>();
+    let b = b.chars().collect::<Vec<_>>();
+    let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, distance) in distances[0].iter_mut().enumerate() {
+        *distance = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let substitution = distances[i - 1][j - 1] + usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = substitution
+                .min(distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+            distances[i][j] = distance;
+        }
+    }
+
+    distances[a.len()][b.len()]
+}
+
+/// Finds the candidate that `name` is most likely a typo of. Candidates that
+/// only differ in case rank first, then the ones with the smallest edit
+/// distance, as long as it is at most a third of the length of `name`.
+pub fn best_match<'a>(name: &str, candidates: impl IntoIterator<Item = &'a str>) -> Option<String> {
+    let max_distance = (name.chars().count() / 3).max(1);
+    candidates
+        .into_iter()
+        .filter(|candidate| *candidate != name)
+        .filter_map(|candidate| {
+            let distance = if candidate.eq_ignore_ascii_case(name) {
+                0
+            } else {
+                edit_distance(name, candidate)
+            };
+            (distance <= max_distance).then_some((distance, candidate))
+        })
+        .min()
+        .map(|(_, candidate)| candidate.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{best_match, edit_distance};
+
+    #[test]
+    fn distances() {
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("count", "count"), 0);
+        assert_eq!(edit_distance("cont", "count"), 1);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("λx", "λy"), 1);
+        assert_eq!(edit_distance("Sdt", "Std"), 1);
+    }
+
+    #[test]
+    fn best_matches() {
+        let names = ["counter", "count", "amount", "Count"];
+        assert_eq!(best_match("cont", names), Some("count".to_string()));
+        assert_eq!(best_match("COUNT", names), Some("Count".to_string()));
+        assert_eq!(best_match("counte", names), Some("count".to_string()));
+        assert_eq!(best_match("total", names), None);
+        assert_eq!(best_match("count", ["count"]), None);
+    }
+}
@@ -1,6 +1,9 @@
 use std::{collections::HashMap, rc::Rc};
 
-use parser::stmt::{AliasItem, StructItem, Visibility};
+use parser::{
+    stmt::{AliasItem, Span, StructItem},
+    IntegerKind,
+};
 
 pub type TypeId = usize;
 
@@ -31,59 +34,89 @@ impl TypeRegistry {
     pub fn get_type_from_expr(&mut self, ty: &parser::Type) -> Option<Rc<Type>> {
         match ty {
             parser::Type::Unit => self.get_type_from_id(0),
-            parser::Type::Name(name) => self.get_type_from_name(&name),
+            parser::Type::Name(name, _) => self.get_type_from_name(name),
             parser::Type::Array(array) => {
                 let ty = self.get_type_from_expr(array)?;
                 let type_id = self.insert_or_get_array_type_for_type(ty.type_id)?;
                 self.get_type_from_id(type_id)
             }
             parser::Type::Function(function) => {
-                let mut args = function
+                let mut arg_types = function
                     .args
                     .iter()
-                    .map(|arg| self.get_type_from_expr(arg))
+                    .map(|arg| Some(self.get_type_from_expr(arg)?.type_id))
                     .collect::<Option<Vec<_>>>()?;
 
-                if args.is_empty() {
-                    args.push(self.get_type_from_name("unit")?);
+                if arg_types.is_empty() {
+                    arg_types.push(PrimitiveType::Unit.type_id());
                 }
 
-                let ret = self.get_type_from_expr(&function.ret)?;
-
-                let ident = self.get_function_type_ident(args.as_slice(), &ret)?;
-
-                if let Some(ty) = self.get_type_from_name(&ident) {
-                    Some(ty)
-                } else {
-                    let type_id = self.type_ids.len();
-                    let ty = Rc::new(Type {
-                        ident,
-                        type_id,
-                        layout: TypeLayout::Function(FunctionType {
-                            arg_types: args.iter().map(|t| t.type_id).collect(),
-                            ret_type: ret.type_id,
-                        }),
-                        visibility: Visibility::Private,
-                    });
-
-                    self.type_ids.insert(ty.ident.clone(), type_id);
-                    self.types.push(ty.clone());
-
-                    Some(ty)
-                }
+                let ret_type = self.get_type_from_expr(&function.ret)?.type_id;
+                let type_id = self.insert_or_get_function_type(arg_types, ret_type);
+                self.get_type_from_id(type_id)
+            }
+            parser::Type::Reference { ty, mutable } => {
+                let ty = self.get_type_from_expr(ty)?;
+                let type_id = self.insert_or_get_reference_type(ty.type_id, *mutable)?;
+                self.get_type_from_id(type_id)
             }
         }
     }
 
-    fn get_function_type_ident<T: AsRef<Type>>(&self, args: &[T], ret: &T) -> Option<String> {
-        let idents = args
+    /// Gets the type of functions with the given arguments and return type,
+    /// registering it the first time it is used.
+    pub fn insert_or_get_function_type(
+        &mut self,
+        arg_types: Vec<TypeId>,
+        ret_type: TypeId,
+    ) -> TypeId {
+        let idents = arg_types
             .iter()
-            .map(|arg| arg.as_ref().ident.as_str())
-            .chain([ret.as_ref().ident.as_str()])
+            .chain([&ret_type])
+            .map(|type_id| self.type_name(*type_id))
             .collect::<Vec<_>>()
             .join(" -> ");
+        let ident = format!("( {idents} )");
+
+        self.get_type_id_from_name(&ident).unwrap_or_else(|| {
+            self.insert_type(
+                ident,
+                TypeLayout::Function(FunctionType {
+                    arg_types,
+                    ret_type,
+                }),
+            )
+        })
+    }
 
-        Some(format!("( {idents} )"))
+    /// Registers a new type variable, a type that is not known yet and is
+    /// found out by type inference.
+    pub fn insert_type_variable(&mut self) -> TypeId {
+        let ident = format!("'_{}", self.types.len());
+        self.insert_type(ident, TypeLayout::Variable)
+    }
+
+    /// Gets the generic type with the given index, named `'a` for the first
+    /// one, `'b` for the second one and so on.
+    pub fn insert_or_get_generic_type(&mut self, index: usize) -> TypeId {
+        let letter = char::from(b'a' + (index % 26) as u8);
+        let ident = match index / 26 {
+            0 => format!("'{letter}"),
+            n => format!("'{letter}{n}"),
+        };
+        self.get_type_id_from_name(&ident)
+            .unwrap_or_else(|| self.insert_type(ident, TypeLayout::Generic))
+    }
+
+    fn insert_type(&mut self, ident: String, layout: TypeLayout) -> TypeId {
+        let type_id = self.types.len();
+        self.types.push(Rc::new(Type {
+            ident: ident.clone(),
+            type_id,
+            layout,
+        }));
+        self.type_ids.insert(ident, type_id);
+        type_id
     }
 
     #[inline]
@@ -104,20 +137,40 @@ impl TypeRegistry {
         }
     }
 
-    pub fn are_types_equal(&self, t1: TypeId, t2: TypeId) -> Result<bool, String> {
-        let t1 = self.resolve_type(
-            self.get_type_from_id(t1)
-                .ok_or(format!("Type with id {t1} not found"))?
-                .as_ref(),
-        );
+    /// Names of the struct types.
+    pub fn struct_names(&self) -> impl Iterator<Item = &str> {
+        self.type_ids
+            .keys()
+            .filter(|name| self.get_struct_type_id_from_name(name).is_some())
+            .map(String::as_str)
+    }
 
-        let t2 = self.resolve_type(
-            self.get_type_from_id(t2)
-                .ok_or(format!("Type with id {t2} not found"))?
-                .as_ref(),
-        );
+    /// Names of the types that annotations can name, like `int` or the
+    /// name of a struct.
+    pub fn type_names(&self) -> impl Iterator<Item = &str> {
+        self.type_ids
+            .iter()
+            .filter(|(_, type_id)| {
+                self.get_type_from_id(**type_id).is_some_and(|ty| {
+                    !matches!(
+                        ty.layout,
+                        TypeLayout::Function(_)
+                            | TypeLayout::Array(_)
+                            | TypeLayout::Reference { .. }
+                            | TypeLayout::Variable
+                            | TypeLayout::Generic
+                    )
+                })
+            })
+            .map(|(name, _)| name.as_str())
+    }
 
-        Ok(t1 == t2)
+    /// Gets the name of a type to show in errors.
+    pub fn type_name(&self, type_id: TypeId) -> String {
+        self.get_type_from_id(type_id).map_or_else(
+            || format!("<unknown type {type_id}>"),
+            |ty| ty.ident.clone(),
+        )
     }
 
     pub fn resolve_type(&self, ty: &Type) -> Rc<Type> {
@@ -132,6 +185,12 @@ impl TypeRegistry {
         }
     }
 
+    /// Gets the type with the given id, following any aliases.
+    pub fn get_resolved_type_from_id(&self, type_id: TypeId) -> Option<Rc<Type>> {
+        self.get_type_from_id(type_id)
+            .map(|ty| self.resolve_type(&ty))
+    }
+
     pub fn insert_or_get_array_type_for_type(&mut self, type_id: TypeId) -> Option<TypeId> {
         let ty = self.get_type_from_id(type_id)?;
         let ty_name = format!("array of {}", ty.ident);
@@ -142,7 +201,6 @@ impl TypeRegistry {
                     ident: ty_name.clone(),
                     type_id: arr_type_id,
                     layout: TypeLayout::Array(type_id),
-                    visibility: Visibility::Public,
                 }
                 .into(),
             );
@@ -151,19 +209,37 @@ impl TypeRegistry {
         })
     }
 
-    pub fn insert_alias_type_from_item(
+    /// Gets the type of references to values of a type, `&'a` or `&mut 'a`,
+    /// registering it the first time it is used.
+    pub fn insert_or_get_reference_type(
         &mut self,
-        alias: AliasItem,
-        visibility: Visibility,
+        type_id: TypeId,
+        mutable: bool,
     ) -> Option<TypeId> {
-        let ty = self.get_type_from_name(&alias.path.unwrap_or("()".to_string()))?;
+        let ty = self.get_type_from_id(type_id)?;
+        let ident = if mutable {
+            format!("&mut {}", ty.ident)
+        } else {
+            format!("&{}", ty.ident)
+        };
+        Some(
+            self.get_type_id_from_name(&ident).unwrap_or_else(|| {
+                self.insert_type(ident, TypeLayout::Reference { type_id, mutable })
+            }),
+        )
+    }
+
+    pub fn insert_alias_type_from_item(&mut self, alias: AliasItem) -> Option<TypeId> {
+        let name = alias
+            .path
+            .map_or_else(|| "()".to_string(), |path| path.path);
+        let ty = self.get_type_from_name(&name)?;
         let type_id = self.types.len();
         self.types.push(
             Type {
                 ident: alias.ident.clone(),
                 type_id,
                 layout: TypeLayout::Alias(ty.type_id),
-                visibility,
             }
             .into(),
         );
@@ -171,11 +247,7 @@ impl TypeRegistry {
         Some(type_id)
     }
 
-    pub fn insert_struct_type_from_item(
-        &mut self,
-        struc: StructItem,
-        visibility: Visibility,
-    ) -> Option<TypeId> {
+    pub fn insert_struct_type_from_item(&mut self, struc: StructItem) -> Option<TypeId> {
         let mut fields = Vec::new();
         for f in struc.fields {
             let ty = self
@@ -183,8 +255,9 @@ impl TypeRegistry {
                 .map(|t| self.resolve_type(&t))?;
             fields.push(StructField {
                 ident: f.ident.clone(),
-                visibility: f.visibility,
+                mutable: f.mutable,
                 type_id: ty.type_id,
+                span: f.span,
             });
         }
         let type_id = self.types.len();
@@ -193,7 +266,6 @@ impl TypeRegistry {
                 ident: struc.ident.clone(),
                 type_id,
                 layout: TypeLayout::Struct(fields),
-                visibility,
             }
             .into(),
         );
@@ -203,36 +275,27 @@ impl TypeRegistry {
     }
 
     fn insert_primitives(&mut self) {
-        self.type_ids.insert(
-            PrimitiveType::Unit.name().to_string(),
-            PrimitiveType::Unit.type_id(),
-        );
-        self.types.push(PrimitiveType::Unit.to_type().into());
-        self.type_ids.insert(
-            PrimitiveType::Boolean.name().to_string(),
-            PrimitiveType::Boolean.type_id(),
-        );
-        self.types.push(PrimitiveType::Boolean.to_type().into());
-        self.type_ids.insert(
-            PrimitiveType::Char.name().to_string(),
-            PrimitiveType::Char.type_id(),
-        );
-        self.types.push(PrimitiveType::Char.to_type().into());
-        self.type_ids.insert(
-            PrimitiveType::Integer.name().to_string(),
-            PrimitiveType::Integer.type_id(),
-        );
-        self.types.push(PrimitiveType::Integer.to_type().into());
-        self.type_ids.insert(
-            PrimitiveType::Float.name().to_string(),
-            PrimitiveType::Float.type_id(),
-        );
-        self.types.push(PrimitiveType::Float.to_type().into());
-        self.type_ids.insert(
-            PrimitiveType::Object.name().to_string(),
-            PrimitiveType::Object.type_id(),
+        for primitive in PrimitiveType::ALL {
+            self.type_ids
+                .insert(primitive.name().to_string(), primitive.type_id());
+            self.types.push(primitive.to_type().into());
+        }
+
+        self.insert_primitive_alias(IntegerKind::I64.name(), PrimitiveType::Integer);
+        self.insert_primitive_alias("byte", PrimitiveType::SizedInteger(IntegerKind::U8));
+    }
+
+    fn insert_primitive_alias(&mut self, ident: &str, primitive: PrimitiveType) {
+        let type_id = self.types.len();
+        self.types.push(
+            Type {
+                ident: ident.to_string(),
+                type_id,
+                layout: TypeLayout::Alias(primitive.type_id()),
+            }
+            .into(),
         );
-        self.types.push(PrimitiveType::Object.to_type().into());
+        self.type_ids.insert(ident.to_string(), type_id);
     }
 }
 
@@ -240,58 +303,48 @@ pub struct Type {
     pub ident: String,
     pub type_id: TypeId,
     pub layout: TypeLayout,
-    pub visibility: Visibility,
 }
 
 impl Type {
-    #[inline]
-    pub fn is_unit(&self) -> bool {
-        matches!(self.layout, TypeLayout::Unit)
-    }
-
-    #[inline]
-    pub fn is_boolean(&self) -> bool {
-        matches!(self.layout, TypeLayout::Boolean)
-    }
-
     #[inline]
     pub fn is_char(&self) -> bool {
         matches!(self.layout, TypeLayout::Char)
     }
 
-    #[inline]
-    pub fn is_integer(&self) -> bool {
-        matches!(self.layout, TypeLayout::Integer)
-    }
-
-    #[inline]
-    pub fn is_float(&self) -> bool {
-        matches!(self.layout, TypeLayout::Float)
+    /// Returns the kind of integer for `int` and the sized integer types.
+    pub fn integer_kind(&self) -> Option<IntegerKind> {
+        match self.layout {
+            TypeLayout::Integer => Some(IntegerKind::I64),
+            TypeLayout::SizedInteger(kind) => Some(kind),
+            _ => None,
+        }
     }
 
     #[inline]
-    pub fn is_object(&self) -> bool {
-        matches!(self.layout, TypeLayout::Object)
+    pub fn is_big_integer(&self) -> bool {
+        matches!(self.layout, TypeLayout::BigInteger)
     }
 
     #[inline]
-    pub fn is_function(&self) -> bool {
-        matches!(self.layout, TypeLayout::Function(_))
+    pub fn is_rational(&self) -> bool {
+        matches!(self.layout, TypeLayout::Rational)
     }
 
     #[inline]
-    pub fn is_array(&self) -> bool {
-        matches!(self.layout, TypeLayout::Array(_))
+    pub fn is_numeric(&self) -> bool {
+        self.is_float()
+            || self.integer_kind().is_some()
+            || self.is_big_integer()
+            || self.is_rational()
     }
 
     #[inline]
-    pub fn is_alias(&self) -> bool {
-        matches!(self.layout, TypeLayout::Alias(_))
+    pub fn is_float(&self) -> bool {
+        matches!(self.layout, TypeLayout::Float)
     }
 
-    #[inline]
-    pub fn is_struct(&self) -> bool {
-        matches!(self.layout, TypeLayout::Struct(_))
+    pub fn is_variable(&self) -> bool {
+        matches!(self.layout, TypeLayout::Variable)
     }
 
     pub fn as_function(&self) -> Option<&FunctionType> {
@@ -301,9 +354,17 @@ impl Type {
         }
     }
 
+    /// The type a reference refers to, and whether it is mutable.
+    pub fn as_reference(&self) -> Option<(TypeId, bool)> {
+        match self.layout {
+            TypeLayout::Reference { type_id, mutable } => Some((type_id, mutable)),
+            _ => None,
+        }
+    }
+
     pub fn as_struct(&self) -> Option<&[StructField]> {
         match self.layout {
-            TypeLayout::Struct(ref fields) => Some(&fields),
+            TypeLayout::Struct(ref fields) => Some(fields),
             _ => None,
         }
     }
@@ -320,18 +381,34 @@ pub enum TypeLayout {
     Boolean,
     Char,
     Integer,
+    SizedInteger(IntegerKind),
+    BigInteger,
+    Rational,
     Float,
     Object,
     Function(FunctionType),
     Array(TypeId),
+    /// A reference to a value of a type, which can be assigned through if it
+    /// is `mutable`.
+    Reference {
+        type_id: TypeId,
+        mutable: bool,
+    },
     Alias(TypeId),
     Struct(Vec<StructField>),
+    /// A type that is not known yet while inferring types.
+    Variable,
+    /// A type parameter of a generic function, like `'a` in `( 'a -> 'a )`.
+    Generic,
 }
 
 pub struct StructField {
     pub ident: String,
-    pub visibility: Visibility,
+    /// Whether the field can be assigned to.
+    pub mutable: bool,
     pub type_id: TypeId,
+    /// Span of the name of the field where the struct is declared.
+    pub span: Span,
 }
 
 pub struct FunctionType {
@@ -339,6 +416,7 @@ pub struct FunctionType {
     pub ret_type: TypeId,
 }
 
+#[derive(Clone, Copy)]
 pub enum PrimitiveType {
     Unit,
     Boolean,
@@ -346,9 +424,39 @@ pub enum PrimitiveType {
     Integer,
     Float,
     Object,
+    /// Any integer kind except [`IntegerKind::I64`] which is [`PrimitiveType::Integer`].
+    SizedInteger(IntegerKind),
+    BigInteger,
+    Rational,
 }
 
 impl PrimitiveType {
+    /// All primitive types ordered by their type id.
+    pub const ALL: [PrimitiveType; 15] = [
+        PrimitiveType::Unit,
+        PrimitiveType::Boolean,
+        PrimitiveType::Char,
+        PrimitiveType::Integer,
+        PrimitiveType::Float,
+        PrimitiveType::Object,
+        PrimitiveType::SizedInteger(IntegerKind::I8),
+        PrimitiveType::SizedInteger(IntegerKind::I16),
+        PrimitiveType::SizedInteger(IntegerKind::I32),
+        PrimitiveType::SizedInteger(IntegerKind::U8),
+        PrimitiveType::SizedInteger(IntegerKind::U16),
+        PrimitiveType::SizedInteger(IntegerKind::U32),
+        PrimitiveType::SizedInteger(IntegerKind::U64),
+        PrimitiveType::BigInteger,
+        PrimitiveType::Rational,
+    ];
+
+    pub fn from_integer_kind(kind: IntegerKind) -> Self {
+        match kind {
+            IntegerKind::I64 => PrimitiveType::Integer,
+            kind => PrimitiveType::SizedInteger(kind),
+        }
+    }
+
     pub fn name(&self) -> &str {
         match self {
             PrimitiveType::Unit => "()",
@@ -357,6 +465,9 @@ impl PrimitiveType {
             PrimitiveType::Integer => "int",
             PrimitiveType::Float => "float",
             PrimitiveType::Object => "object",
+            PrimitiveType::SizedInteger(kind) => kind.name(),
+            PrimitiveType::BigInteger => "bigint",
+            PrimitiveType::Rational => "rational",
         }
     }
 
@@ -368,6 +479,18 @@ impl PrimitiveType {
             PrimitiveType::Integer => 3,
             PrimitiveType::Float => 4,
             PrimitiveType::Object => 5,
+            PrimitiveType::SizedInteger(kind) => match kind {
+                IntegerKind::I8 => 6,
+                IntegerKind::I16 => 7,
+                IntegerKind::I32 => 8,
+                IntegerKind::U8 => 9,
+                IntegerKind::U16 => 10,
+                IntegerKind::U32 => 11,
+                IntegerKind::U64 => 12,
+                IntegerKind::I64 => PrimitiveType::Integer.type_id(),
+            },
+            PrimitiveType::BigInteger => 13,
+            PrimitiveType::Rational => 14,
         }
     }
 
@@ -379,15 +502,17 @@ impl PrimitiveType {
             PrimitiveType::Integer => TypeLayout::Integer,
             PrimitiveType::Float => TypeLayout::Float,
             PrimitiveType::Object => TypeLayout::Object,
+            PrimitiveType::SizedInteger(kind) => TypeLayout::SizedInteger(*kind),
+            PrimitiveType::BigInteger => TypeLayout::BigInteger,
+            PrimitiveType::Rational => TypeLayout::Rational,
         }
     }
 
-    pub fn to_type(&self) -> Type {
+    pub fn to_type(self) -> Type {
         Type {
             ident: self.name().to_string(),
             type_id: self.type_id(),
             layout: self.layout(),
-            visibility: Visibility::Public,
         }
     }
 }
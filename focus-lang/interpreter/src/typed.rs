@@ -0,0 +1,154 @@
+//! The program as the type checker leaves it, lowered from the syntax tree of
+//! the parser. Every expression knows its type and span, and every name what
+//! it refers to, so the interpreter can evaluate it without checking any types
+//! again and tools can inspect it without checking them themselves.
+//!
+//! The type ids can be turned into names with
+//! [`Interpreter::type_name`](crate::Interpreter::type_name).
+
+use std::rc::Rc;
+
+use parser::stmt::{Literal, Operator, Span};
+
+use crate::r#type::TypeId;
+
+#[derive(Debug, Clone)]
+pub enum TypedStmt {
+    Item(TypedItem),
+    Let(TypedLet),
+    /// The functions of a `let rec`. All of their names are bound before any
+    /// of their closures is created, so they can capture each other.
+    LetRec(Vec<TypedLet>),
+    Expr(TypedExpr),
+}
+
+/// A type declared with `type`. Types are registered while checking, so
+/// nothing is left to do when running one.
+#[derive(Debug, Clone)]
+pub struct TypedItem {
+    pub ident: String,
+    pub type_id: TypeId,
+    /// Span of the declaration.
+    pub span: Span,
+}
+
+/// A `let` binding. Functions declared with `let` are bound to a closure.
+#[derive(Debug, Clone)]
+pub struct TypedLet {
+    pub ident: String,
+    /// The slot of the name in the scope it is declared in.
+    pub slot: usize,
+    pub type_id: TypeId,
+    pub mutable: bool,
+    /// Span of the name.
+    pub span: Span,
+    /// `None` for a name declared without a value.
+    pub expr: Option<TypedExpr>,
+}
+
+/// What a name refers to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Binding {
+    /// A local declared `depth` scopes out from where it is used, `0` being
+    /// the innermost one, in slot `slot` of that scope. Scopes are the top
+    /// level, functions, whose arguments take the first slots, and blocks,
+    /// including the body of a function. Every declaration takes the next
+    /// slot of its scope, even if it shadows a name declared before. Every
+    /// scope but the top level has a frame on the stack while it runs, which
+    /// holds the value of each slot at the same offset.
+    Local { depth: usize, slot: usize },
+    /// A name declared outside of the function it is used in, which is the
+    /// `index`th [`Capture`] of that function.
+    Capture { index: usize },
+    /// A definition of a module, like `Std.U8.wrapping_add`.
+    ModuleItem(String),
+}
+
+/// A name a function uses from outside of it. The function shares the
+/// variable with the scope it is declared in, and with the other functions
+/// that capture it, so an assignment by any of them is seen by all of them.
+#[derive(Debug, Clone)]
+pub struct Capture {
+    pub ident: String,
+    pub type_id: TypeId,
+    /// What the name refers to where the function is created.
+    pub binding: Binding,
+}
+
+#[derive(Debug, Clone)]
+pub struct TypedExpr {
+    pub kind: TypedExprKind,
+    pub type_id: TypeId,
+    /// Span of the expression, set for every expression the checker
+    /// produces.
+    pub span: Option<Span>,
+}
+
+#[derive(Debug, Clone)]
+pub enum TypedExprKind {
+    Literal(Literal),
+    Path {
+        ident: String,
+        binding: Binding,
+    },
+    /// A field of a struct value, like `point.x`.
+    Field {
+        value: Box<TypedExpr>,
+        /// The position of the field in the order the struct declares them.
+        offset: usize,
+    },
+    Operation {
+        op: Operator,
+        lhs: Box<TypedExpr>,
+        rhs: Box<TypedExpr>,
+    },
+    Call {
+        path: String,
+        binding: Binding,
+        params: Vec<TypedExpr>,
+    },
+    /// The values of the fields in the order the struct declares them.
+    Struct(Vec<TypedExpr>),
+    Array(Vec<TypedExpr>),
+    Index {
+        value: Box<TypedExpr>,
+        index: Box<TypedExpr>,
+    },
+    Slice {
+        value: Box<TypedExpr>,
+        from: Option<Box<TypedExpr>>,
+        to: Option<Box<TypedExpr>>,
+    },
+    IfElse {
+        condition: Box<TypedExpr>,
+        if_expr: Box<TypedExpr>,
+        else_expr: Option<Box<TypedExpr>>,
+    },
+    Block(Vec<TypedStmt>),
+    /// A function. Its arguments take the first slots of its scope.
+    Closure {
+        args: Vec<String>,
+        /// The names the body uses from outside of the function, in the
+        /// order they are first used.
+        captures: Vec<Capture>,
+        body: Rc<TypedExpr>,
+    },
+    /// A cast to the type of the expression.
+    Cast(Box<TypedExpr>),
+    /// A reference to a place, like `&a` or `&mut p.x`, or to a temporary
+    /// value for any other expression.
+    Reference(Box<TypedExpr>),
+    /// The value a reference refers to, like `*r`. Fields and elements are
+    /// accessed through references with one too.
+    Deref(Box<TypedExpr>),
+}
+
+impl TypedExpr {
+    pub fn new(kind: TypedExprKind, type_id: TypeId) -> Self {
+        Self {
+            kind,
+            type_id,
+            span: None,
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use std::{env::args, error::Error, fmt::Display};
 
+use diagnostic::Renderer;
 use parser::{Parser, ParserError, ParserErrorInfo};
 
 #[derive(Debug)]
@@ -38,7 +39,9 @@ fn main() -> Result<(), RunError> {
                 ..
             }) => break,
             Err(err) => {
-                println!("{err}");
+                let rendered =
+                    Renderer::plain().render(&err.diagnostic(), &file, err.file.as_deref());
+                println!("{rendered}");
                 return Err(RunError::ParserError(err));
             }
             Ok(stmt) => println!("{stmt:?}"),
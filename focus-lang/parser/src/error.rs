@@ -1,7 +1,10 @@
 use std::{error::Error, fmt::Display};
 
+use diagnostic::Diagnostic;
 use scanner::Token;
 
+use crate::stmt::Span;
+
 #[derive(Debug)]
 pub enum ParserErrorInfo {
     UnexpectedTokenExpected { found: Token, expected: Token },
@@ -25,6 +28,45 @@ pub enum ParserErrorInfo {
     InvalidCharacterLiteral,
 }
 
+impl ParserErrorInfo {
+    /// A stable code for the kind of error. Syntax errors are in the `E00xx` range.
+    pub fn code(&self) -> &'static str {
+        match self {
+            ParserErrorInfo::UnexpectedTokenExpected { .. } => "E0010",
+            ParserErrorInfo::UnexpectedToken { .. } => "E0011",
+            ParserErrorInfo::ExpectedIdentifierFoundKeyword { .. } => "E0012",
+            ParserErrorInfo::InvalidUseOfKeyword { .. } => "E0013",
+            ParserErrorInfo::UnableToParse { .. } => "E0014",
+            ParserErrorInfo::EarlyEof => "E0015",
+            ParserErrorInfo::Eof => "E0016",
+            ParserErrorInfo::InvalidIndentation => "E0017",
+            ParserErrorInfo::InvalidLevelForItem => "E0018",
+            ParserErrorInfo::ExpectedExpresion => "E0019",
+            ParserErrorInfo::InvalidStructFieldType(_) => "E0020",
+            ParserErrorInfo::InvalidEnumVariantType(_) => "E0021",
+            ParserErrorInfo::InvalidTypeRef => "E0022",
+            ParserErrorInfo::DuplicateGenericName(_) => "E0023",
+            ParserErrorInfo::InvalidExpression { .. } => "E0024",
+            ParserErrorInfo::StatementNotValidInThisPosition => "E0025",
+            ParserErrorInfo::ExpressionNotValidInThisPosition => "E0026",
+            ParserErrorInfo::InvalidOperandForRangeExpression => "E0027",
+            ParserErrorInfo::InvalidCharacterLiteral => "E0028",
+        }
+    }
+
+    fn label(&self) -> String {
+        match self {
+            ParserErrorInfo::UnexpectedTokenExpected { expected, .. } => {
+                format!("expected '{expected:?}' here")
+            }
+            ParserErrorInfo::UnexpectedToken { .. } => "unexpected token".to_string(),
+            ParserErrorInfo::EarlyEof => "the source ends here".to_string(),
+            ParserErrorInfo::InvalidIndentation => "invalid indentation".to_string(),
+            _ => String::new(),
+        }
+    }
+}
+
 impl Display for ParserErrorInfo {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let fmt = match self {
@@ -34,29 +76,27 @@ impl Display for ParserErrorInfo {
             ParserErrorInfo::UnexpectedToken { found } => {
                 format!("Unexpected token '{found:?}'.")
             }
-            ParserErrorInfo::EarlyEof => {
-                format!("Early end of file.")
-            }
+            ParserErrorInfo::EarlyEof => "Early end of file.".to_string(),
             ParserErrorInfo::ExpectedIdentifierFoundKeyword { keyword } => {
                 format!("Expected identifier, found: '{keyword}'.")
             }
             ParserErrorInfo::UnableToParse { expr, err } => {
                 format!("Unable to parse '{expr}'. Error: {err}.")
             }
-            ParserErrorInfo::Eof => format!("End of file."),
-            ParserErrorInfo::InvalidIndentation => format!("Invalid indentation."),
+            ParserErrorInfo::Eof => "End of file.".to_string(),
+            ParserErrorInfo::InvalidIndentation => "Invalid indentation.".to_string(),
             ParserErrorInfo::InvalidUseOfKeyword { keyword } => {
                 format!("Invalid use of keyword '{keyword}'.")
             }
-            ParserErrorInfo::InvalidLevelForItem => format!("Item cannot be a top level item."),
-            ParserErrorInfo::ExpectedExpresion => format!("Expected at least one expression."),
+            ParserErrorInfo::InvalidLevelForItem => "Item cannot be a top level item.".to_string(),
+            ParserErrorInfo::ExpectedExpresion => "Expected at least one expression.".to_string(),
             ParserErrorInfo::InvalidStructFieldType(name) => {
                 format!("Invalid type for field with name: '{name}'.")
             }
             ParserErrorInfo::InvalidEnumVariantType(name) => {
                 format!("Invalid type for variant with name: '{name}'.")
             }
-            ParserErrorInfo::InvalidTypeRef => format!("Invalid type ref."),
+            ParserErrorInfo::InvalidTypeRef => "Invalid type ref.".to_string(),
             ParserErrorInfo::DuplicateGenericName(ident) => {
                 format!("Duplicate generic argument name: '{ident}'.")
             }
@@ -64,16 +104,16 @@ impl Display for ParserErrorInfo {
                 format!("Invalid expression of type '{found}'. Expected: '<{expected}>'")
             }
             ParserErrorInfo::StatementNotValidInThisPosition => {
-                format!("A statement is not valid in this position.")
+                "A statement is not valid in this position.".to_string()
             }
             ParserErrorInfo::ExpressionNotValidInThisPosition => {
-                format!("An expression is not valid in this position.")
+                "An expression is not valid in this position.".to_string()
             }
             ParserErrorInfo::InvalidOperandForRangeExpression => {
-                format!("This operand is invalid for a range expression.")
+                "This operand is invalid for a range expression.".to_string()
             }
             ParserErrorInfo::InvalidCharacterLiteral => {
-                format!("Invalid character literal expression.")
+                "Invalid character literal expression.".to_string()
             }
         };
         f.write_str(&fmt)
@@ -82,16 +122,88 @@ impl Display for ParserErrorInfo {
 
 #[derive(Debug)]
 pub struct ParserError {
-    pub message: String,
-    pub location: String,
     pub info: ParserErrorInfo,
+    /// Span of the token where parsing stopped.
+    pub span: Span,
+    pub file: Option<String>,
+}
+
+impl ParserError {
+    pub fn diagnostic(&self) -> Diagnostic {
+        Diagnostic::error(self.info.to_string())
+            .with_code(self.info.code())
+            .with_primary(self.span.clone(), self.info.label())
+    }
 }
 
 impl Display for ParserError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        self.info.fmt(f)?;
-        f.write_str(format!("\n{}\nAt location {}", self.message, self.location).as_str())
+        write!(f, "error[{}]: {}", self.info.code(), self.info)
     }
 }
 
 impl Error for ParserError {}
+
+/// Returns the long explanation of a syntax error code.
+pub fn explain(code: &str) -> Option<&'static str> {
+    let explanation = match code {
+        "E0010" => {
+            "A specific token was expected but a different one was found.
+
+    let a = (1 + 2
+
+The closing parenthesis is missing, so the parser reaches the end of the
+line while it still expects ')'."
+        }
+        "E0011" => {
+            "A token was found in a position where it cannot appear.
+
+    let a = 1 )
+
+Remove the token or check for a missing operator or opening bracket."
+        }
+        "E0012" => "An identifier was expected, but a keyword was written instead. Keywords such as `let`, `if` or `for` cannot be used as names.",
+        "E0013" => "A keyword was used in a position where it has no meaning.",
+        "E0014" => {
+            "A literal could not be parsed, e.g. a number that does not fit its type
+or an unknown integer suffix.
+
+    let a = 300u8
+    let b = 1q
+
+Use a literal that fits the type or one of the integer suffixes i8, i16, i32,
+i64, u8, u16, u32 or u64."
+        }
+        "E0015" => "The source ended in the middle of an expression or statement. Check for unclosed brackets, strings or missing bodies.",
+        "E0016" => "The end of the source was reached. This is reported when there is nothing left to parse.",
+        "E0017" => {
+            "The body of a multiline block must be indented further than the line
+that opens it.
+
+    let f x: (int -> int) =
+    x + 1"
+        }
+        "E0018" => "This item can only be declared at the top level of a module.",
+        "E0019" => "A block must contain at least one expression.",
+        "E0020" => "The type of a struct field could not be parsed.",
+        "E0021" => "The type of an enum variant could not be parsed.",
+        "E0022" => "A type reference could not be parsed.",
+        "E0023" => "The same name was used for two generic arguments.",
+        "E0024" => "An expression of a different kind was expected in this position.",
+        "E0025" => "A statement such as `let` was written where only an expression is allowed, e.g. as the operand of an operation.",
+        "E0026" => "An expression was written where only a statement is allowed.",
+        "E0027" => {
+            "The bounds of a range must be number literals or names.
+
+    for i in 0..len do print i"
+        }
+        "E0028" => {
+            "A character literal must contain exactly one character between single
+quotes.
+
+    let c = 'ab'"
+        }
+        _ => return None,
+    };
+    Some(explanation)
+}
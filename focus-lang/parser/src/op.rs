@@ -31,3 +31,15 @@ pub enum CompoundAssignmentOperator {
     Div,
     Mod,
 }
+
+impl From<CompoundAssignmentOperator> for ArithmeticOperator {
+    fn from(value: CompoundAssignmentOperator) -> Self {
+        match value {
+            CompoundAssignmentOperator::Add => ArithmeticOperator::Add,
+            CompoundAssignmentOperator::Sub => ArithmeticOperator::Sub,
+            CompoundAssignmentOperator::Mul => ArithmeticOperator::Mul,
+            CompoundAssignmentOperator::Div => ArithmeticOperator::Div,
+            CompoundAssignmentOperator::Mod => ArithmeticOperator::Mod,
+        }
+    }
+}
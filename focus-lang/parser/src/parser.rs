@@ -1,13 +1,14 @@
-use scanner::{Scanner, ScannerPosition, Token};
+use scanner::{Scanner, Token};
 
 use crate::{
-    op::{ArithmeticOperator, BooleanOperator, ComparisonOperator},
+    op::{ArithmeticOperator, BooleanOperator, ComparisonOperator, CompoundAssignmentOperator},
     stmt::{
-        AliasItem, Call, Closure, Expression, For, IfElse, Index, Item, ItemStmt, LetStmt, Literal,
-        Match, MatchBranch, Operation, Operator, Range, Stmt, StmtDetails, Struct, StructField,
-        StructItem, StructItemField, Visibility,
+        AliasItem, Arg, Call, Cast, Closure, Deref, Expression, Field, For, IfElse, Index, Item,
+        ItemStmt, LetStmt, Literal, Match, MatchBranch, Operation, Operator, Path, Range,
+        Reference, Span, Stmt, StmtDetails, Struct, StructField, StructItem, StructItemField,
+        Visibility,
     },
-    FunctionType, ParserError, ParserErrorInfo, Type,
+    FunctionType, IntegerKind, ParserError, ParserErrorInfo, Type,
 };
 
 #[derive(Clone)]
@@ -41,9 +42,9 @@ impl<'a> Parser<'a> {
         let start_position = self.scanner.position();
         self.parse()
             .map_err(|info| ParserError {
-                message: self.error_message(start_position.clone()),
-                location: self.error_location(),
                 info,
+                span: self.scanner.token_start()..self.scanner.position().pos,
+                file: self.filename.clone(),
             })
             .map(|stmt| StmtDetails {
                 file: self.filename.clone(),
@@ -62,18 +63,24 @@ impl<'a> Parser<'a> {
         };
 
         let res = match self.scanner.peek_skip_empty() {
+            Token::Let if self.scanner.peek_skip_empty_nth(2) == Some(Token::Rec) => {
+                self.depth += 1;
+                let stmt = self.parse_let_rec(visibility).map(Stmt::LetRec)?;
+                self.depth -= 1;
+                stmt
+            }
             Token::Let => {
                 self.depth += 1;
-                let stmt = self.parse_let(visibility).map(|res| Stmt::Let(res))?;
+                let stmt = self.parse_let(visibility).map(Stmt::Let)?;
                 self.depth -= 1;
                 stmt
             }
-            Token::Type | Token::Use | Token::Module => self
-                .parse_item_stmt(visibility)
-                .map(|res| Stmt::Item(res))?,
+            Token::Type | Token::Use | Token::Module => {
+                self.parse_item_stmt(visibility).map(Stmt::Item)?
+            }
             _ => {
                 self.depth += 1;
-                let stmt = self.parse_expr().map(|res| Stmt::Expr(res))?;
+                let stmt = self.parse_expr().map(Stmt::Expr)?;
                 self.depth -= 1;
                 stmt
             }
@@ -93,9 +100,18 @@ impl<'a> Parser<'a> {
 
     fn parse_type(&mut self) -> Result<Type, ParserErrorInfo> {
         if self.scanner.check_and_consume_indented(Token::Ident) {
-            Ok(Type::Name(self.scanner.slice().to_string()))
+            Ok(Type::Name(
+                self.scanner.slice().to_string(),
+                self.token_span(),
+            ))
         } else if self.scanner.check_and_consume_indented(Token::Unit) {
             Ok(Type::Unit)
+        } else if self.scanner.check_and_consume_indented(Token::Ampersand) {
+            let mutable = self.scanner.check_and_consume_indented(Token::Mut);
+            Ok(Type::Reference {
+                ty: self.parse_type()?.into(),
+                mutable,
+            })
         } else if self.scanner.check_and_consume_indented(Token::LeftSquare) {
             let ty = Type::Array(self.parse_type()?.into());
             self.expect_skip_empty(Token::RightSquare)?;
@@ -164,22 +180,68 @@ impl<'a> Parser<'a> {
         Ok(path)
     }
 
+    fn parse_path_expr(&mut self) -> Result<Expression, ParserErrorInfo> {
+        Ok(Expression::Path(self.parse_spanned_path()?))
+    }
+
+    fn parse_spanned_path(&mut self) -> Result<Path, ParserErrorInfo> {
+        let path = self.parse_path()?;
+        let end = self.scanner.position().pos;
+        Ok(Path {
+            span: end - path.len()..end,
+            path,
+        })
+    }
+
     pub fn parse_let(
         &mut self,
         visibility: Option<Visibility>,
     ) -> Result<LetStmt, ParserErrorInfo> {
         self.expect_skip_empty(Token::Let)?;
+        self.parse_let_binding(visibility)
+    }
+
+    /// Parses `let rec` followed by bindings joined with `and`, each on the
+    /// same line as the end of the previous one or on a line of its own.
+    pub fn parse_let_rec(
+        &mut self,
+        visibility: Option<Visibility>,
+    ) -> Result<Vec<LetStmt>, ParserErrorInfo> {
+        self.expect_skip_empty(Token::Let)?;
+        self.expect_skip_empty(Token::Rec)?;
+        let mut bindings = vec![self.parse_let_binding(visibility.clone())?];
+        loop {
+            let mut next = self.clone();
+            next.skip_empty_lines();
+            if next.scanner.peek_skip_empty() != Token::AndLet {
+                break;
+            }
+            self.skip_empty_lines();
+            self.expect_skip_empty(Token::AndLet)?;
+            bindings.push(self.parse_let_binding(visibility.clone())?);
+        }
+        Ok(bindings)
+    }
+
+    /// Parses what follows `let`: the name, the arguments, the type and the
+    /// value.
+    fn parse_let_binding(
+        &mut self,
+        visibility: Option<Visibility>,
+    ) -> Result<LetStmt, ParserErrorInfo> {
+        let mutable = self.scanner.check_and_consume_skip_empty(Token::Mut);
         self.expect_skip_empty(Token::Ident)?;
         let ident = self.scanner.slice().to_string();
+        let span = self.token_span();
 
-        let mut args = Vec::new();
-
-        while self.scanner.check_and_consume_indented(Token::Ident) {
-            args.push(self.scanner.slice().to_string());
-        }
+        let mut args = self.parse_args()?;
 
         if self.scanner.check_and_consume_indented(Token::Unit) {
-            args.push("".to_string());
+            args.push(Arg {
+                ident: "".to_string(),
+                mutable: false,
+                span: self.token_span(),
+            });
         }
 
         let ty = if self.scanner.check_and_consume_indented(Token::Colon) {
@@ -197,12 +259,38 @@ impl<'a> Parser<'a> {
         Ok(LetStmt {
             ident,
             visibility,
+            mutable,
             args,
             expr,
             ty,
+            span,
         })
     }
 
+    /// Parses the names of the arguments of a function, each of which can be
+    /// declared with `mut`.
+    fn parse_args(&mut self) -> Result<Vec<Arg>, ParserErrorInfo> {
+        let mut args = Vec::new();
+        loop {
+            let mutable = self.scanner.check_and_consume_indented(Token::Mut);
+            if mutable {
+                self.expect_indented(Token::Ident)?;
+            } else if !self.scanner.check_and_consume_indented(Token::Ident) {
+                return Ok(args);
+            }
+            args.push(Arg {
+                ident: self.scanner.slice().to_string(),
+                mutable,
+                span: self.token_span(),
+            });
+        }
+    }
+
+    /// The span of the last token consumed.
+    fn token_span(&self) -> Span {
+        self.scanner.token_start()..self.scanner.position().pos
+    }
+
     pub fn parse_expr(&mut self) -> Result<Expression, ParserErrorInfo> {
         if self.depth == 0 && !self.allow_top_level {
             return Err(ParserErrorInfo::ExpressionNotValidInThisPosition);
@@ -220,16 +308,20 @@ impl<'a> Parser<'a> {
             Token::Ident => {
                 let mut cloned = self.clone();
                 cloned.parse_path()?;
+                if cloned.scanner.check(Token::LeftSquare) {
+                    // An index directly following a path, e.g. `a[0]`, can be the
+                    // left hand side of an operation so it is parsed as a primary.
+                    return self.parse_operation();
+                }
                 match cloned.scanner.peek_indented() {
                     Some(Token::Range) => self.parse_range(),
                     Some(Token::LeftCurly) => self.parse_struct(),
-                    Some(Token::LeftSquare) => self.parse_index(),
-                    Some(t) if t.is_operator() => self.parse_operation(),
+                    Some(t) if t.is_operator() || t == Token::As => self.parse_operation(),
                     Some(t) if t.is_primary() => self.parse_call(),
-                    _ => Ok(Expression::Path(self.parse_path()?)),
+                    _ => self.parse_path_expr(),
                 }
             }
-            Token::Eof => return Err(ParserErrorInfo::Eof),
+            Token::Eof => Err(ParserErrorInfo::Eof),
             t if t.is_primary() => self.parse_operation(),
             _ => Err(ParserErrorInfo::UnexpectedToken {
                 found: self.scanner.advance_skip_empty(),
@@ -237,23 +329,40 @@ impl<'a> Parser<'a> {
         }
     }
 
-    fn parse_index(&mut self) -> Result<Expression, ParserErrorInfo> {
-        let mut value = Expression::Path(self.parse_path()?).into();
-        let mut index;
+    /// Parses the indices following a path, and the fields and further
+    /// indices following them, like `ps[0].xs[1]`.
+    fn parse_index(
+        &mut self,
+        mut value: Expression,
+        start: usize,
+    ) -> Result<Expression, ParserErrorInfo> {
         loop {
-            self.expect_skip_empty(Token::LeftSquare)?;
-            index = self.parse_operation()?.into();
-            self.expect_skip_empty(Token::RightSquare)?;
-            if !self.scanner.check_skip_empty(Token::LeftSquare) {
-                break;
+            if self.scanner.check_and_consume(Token::LeftSquare) {
+                let index = self.parse_expr()?.into();
+                self.expect_skip_empty(Token::RightSquare)?;
+                value = Expression::Index(Index {
+                    value: value.into(),
+                    index,
+                    span: start..self.scanner.position().pos,
+                });
+            } else if !matches!(value, Expression::Path(_))
+                && self.scanner.check_and_consume(Token::Dot)
+            {
+                self.expect(Token::Ident)?;
+                value = Expression::Field(Field {
+                    value: value.into(),
+                    ident: self.scanner.slice().to_string(),
+                    span: start..self.scanner.position().pos,
+                });
+            } else {
+                return Ok(value);
             }
-            value = Expression::Index(Index { value, index }).into();
         }
-        Ok(Expression::Index(Index { value, index }))
     }
 
     fn parse_struct(&mut self) -> Result<Expression, ParserErrorInfo> {
         self.expect_indented(Token::Ident)?;
+        let start = self.scanner.token_start();
         let path = self.scanner.slice().to_string();
         self.expect_ignore(Token::LeftCurly)?;
 
@@ -265,19 +374,25 @@ impl<'a> Parser<'a> {
             }
 
             self.expect_ignore(Token::Ident)?;
+            let span = self.scanner.token_start()..self.scanner.position().pos;
             let ident = self.scanner.slice().to_string();
             self.expect_skip_empty(Token::Colon)?;
             let expr = self.parse_operation()?.into();
             self.scanner.check_and_consume(Token::Comma);
-            fields.push(StructField { ident, expr })
+            fields.push(StructField { ident, expr, span })
         }
 
         self.expect_ignore(Token::RightCurly)?;
-        Ok(Expression::Struct(Struct { path, fields }))
+        Ok(Expression::Struct(Struct {
+            path,
+            fields,
+            span: start..self.scanner.position().pos,
+        }))
     }
 
     fn parse_match(&mut self) -> Result<Expression, ParserErrorInfo> {
         self.expect_skip_empty(Token::Match)?;
+        let start = self.scanner.token_start();
         let match_expr = self.parse_expr()?.into();
         let mut branches = Vec::new();
         self.expect_skip_empty(Token::NewLine)?;
@@ -303,11 +418,13 @@ impl<'a> Parser<'a> {
         Ok(Expression::Match(Match {
             match_expr,
             branches,
+            span: start..self.scanner.position().pos,
         }))
     }
 
     fn parse_if(&mut self) -> Result<Expression, ParserErrorInfo> {
         self.expect_skip_empty(Token::If)?;
+        let start = self.scanner.token_start();
         let indentation = self.scanner.cursor().line_indentation();
         let condition = self.parse_operation()?.into();
         self.expect_skip_empty(Token::Then)?;
@@ -327,11 +444,13 @@ impl<'a> Parser<'a> {
             condition,
             if_expr,
             else_expr,
+            span: start..self.scanner.position().pos,
         }))
     }
 
     fn parse_for(&mut self) -> Result<Expression, ParserErrorInfo> {
         self.expect_skip_empty(Token::For)?;
+        let start = self.scanner.token_start();
         self.expect_skip_empty(Token::Ident)?;
         let ident = self.scanner.slice().to_string();
         self.expect_skip_empty(Token::In)?;
@@ -342,12 +461,14 @@ impl<'a> Parser<'a> {
             ident,
             in_expr,
             block,
+            span: start..self.scanner.position().pos,
         }))
     }
 
     fn parse_block(&mut self) -> Result<Expression, ParserErrorInfo> {
         let indentation = self.scanner.cursor().line_indentation();
         let is_multiline = self.scanner.check_and_consume_skip_empty(Token::NewLine);
+        let start = self.next_token_start();
 
         if is_multiline {
             if self.scanner.cursor().line_indentation() <= indentation {
@@ -374,23 +495,32 @@ impl<'a> Parser<'a> {
                 }
             }
             if !stmts.is_empty() {
-                Ok(Expression::Block(stmts))
+                Ok(Expression::Block(stmts, start..self.scanner.position().pos))
             } else {
                 Err(ParserErrorInfo::EarlyEof)
             }
         } else {
-            Ok(Expression::Block(vec![self.parse()?]))
+            let stmt = self.parse()?;
+            Ok(Expression::Block(
+                vec![stmt],
+                start..self.scanner.position().pos,
+            ))
         }
     }
 
     fn parse_call(&mut self) -> Result<Expression, ParserErrorInfo> {
         let path = self.parse_path()?;
+        let start = self.scanner.position().pos - path.len();
         let mut params = Vec::new();
         while self.scanner.peek_indented().is_some_and(|t| t.is_primary()) {
             self.scanner.check_and_consume_skip_empty(Token::NewLine);
             params.push(self.parse_primary()?);
         }
-        let call = Expression::Call(Call { path, params });
+        let call = Expression::Call(Call {
+            path,
+            params,
+            span: start..self.scanner.position().pos,
+        });
 
         if self.scanner.check_and_consume_indented(Token::At) {
             self.scanner.check_and_consume_skip_empty(Token::NewLine);
@@ -399,9 +529,10 @@ impl<'a> Parser<'a> {
                     c.params.push(call);
                     Ok(Expression::Call(c))
                 }
-                Expression::Path(path) => Ok(Expression::Call(Call {
+                Expression::Path(Path { path, span }) => Ok(Expression::Call(Call {
                     path,
                     params: vec![call],
+                    span,
                 })),
                 expr => Err(ParserErrorInfo::InvalidExpression {
                     found: expr.name().to_string(),
@@ -431,51 +562,91 @@ impl<'a> Parser<'a> {
             Some(Token::Equal) => Some(Operator::Comparison(ComparisonOperator::Equal)),
             Some(Token::NotEqual) => Some(Operator::Comparison(ComparisonOperator::NotEqual)),
             Some(Token::Assign) => Some(Operator::Assignment),
+            Some(Token::PlusAssign) => Some(Operator::CompoundAssignment(
+                CompoundAssignmentOperator::Add,
+            )),
+            Some(Token::DashAssign) => Some(Operator::CompoundAssignment(
+                CompoundAssignmentOperator::Sub,
+            )),
+            Some(Token::StarAssign) => Some(Operator::CompoundAssignment(
+                CompoundAssignmentOperator::Mul,
+            )),
+            Some(Token::SlashAssign) => Some(Operator::CompoundAssignment(
+                CompoundAssignmentOperator::Div,
+            )),
+            Some(Token::PercentAssign) => Some(Operator::CompoundAssignment(
+                CompoundAssignmentOperator::Mod,
+            )),
             _ => None,
         }
     }
 
     fn parse_operation(&mut self) -> Result<Expression, ParserErrorInfo> {
-        let mut left = self.parse_primary()?;
-        let mut prev_prec = 0;
+        self.parse_operation_with_precedence(0)
+    }
 
-        loop {
-            let op = match self.parse_operator() {
-                Some(op) => op,
-                None => return Ok(left),
-            };
+    fn parse_operation_with_precedence(
+        &mut self,
+        min_prec: i32,
+    ) -> Result<Expression, ParserErrorInfo> {
+        let start = self.next_token_start();
+        let mut left = self.parse_primary()?;
 
+        while let Some(op) = self.parse_operator() {
             let cur_prec = op.precedence();
-            if cur_prec < prev_prec {
-                return Ok(left);
+            if cur_prec < min_prec {
+                break;
             }
 
             self.scanner.advance_indented(); // consume op token
             self.scanner.check_and_consume_skip_empty(Token::NewLine);
 
-            let mut cloned = self.clone();
-            cloned.parse_primary()?;
-            let next_op = cloned.parse_operator();
-
-            let right = if next_op.is_some_and(|op| op.precedence() > cur_prec) {
-                prev_prec += 1;
-                self.parse_operation()?
-            } else {
-                self.parse_primary()?
+            // Assignments are right associative, every other operator is left associative.
+            let next_min_prec = match op {
+                Operator::Assignment | Operator::CompoundAssignment(_) => cur_prec,
+                _ => cur_prec + 1,
             };
+            let right = self.parse_operation_with_precedence(next_min_prec)?;
 
             left = Expression::Operation(Operation {
                 lhs: left.into(),
                 rhs: right.into(),
                 op,
+                span: start..self.scanner.position().pos,
             })
         }
+
+        Ok(left)
     }
 
     fn parse_primary(&mut self) -> Result<Expression, ParserErrorInfo> {
+        let start = self.next_token_start();
+        let mut expr = self.parse_primary_operand()?;
+        while self.scanner.check_and_consume_indented(Token::As) {
+            expr = Expression::Cast(Cast {
+                expr: expr.into(),
+                ty: self.parse_type()?,
+                span: start..self.scanner.position().pos,
+            });
+        }
+        Ok(expr)
+    }
+
+    /// Byte offset where the next token starts, skipping any whitespace.
+    fn next_token_start(&self) -> usize {
+        let mut scanner = self.scanner.clone();
+        scanner.advance_skip_empty();
+        scanner.token_start()
+    }
+
+    fn parse_primary_operand(&mut self) -> Result<Expression, ParserErrorInfo> {
         match self.scanner.peek_skip_empty() {
             t if t.is_literal() => self.parse_literal(),
-            Token::Ident => Ok(Expression::Path(self.parse_path()?)),
+            Token::Ident => {
+                let path = self.parse_path_expr()?;
+                let start = path.span().start;
+                self.parse_index(path, start)
+            }
             Token::LeftParen => {
                 self.scanner.advance_skip_empty();
                 let expr = self.parse_expr()?;
@@ -483,8 +654,29 @@ impl<'a> Parser<'a> {
                 Ok(expr)
             }
             Token::Fn => self.parse_closure(),
+            Token::Ampersand => {
+                self.scanner.advance_skip_empty();
+                let start = self.scanner.token_start();
+                let mutable = self.scanner.check_and_consume_indented(Token::Mut);
+                let expr = self.parse_primary_operand()?.into();
+                Ok(Expression::Reference(Reference {
+                    expr,
+                    mutable,
+                    span: start..self.scanner.position().pos,
+                }))
+            }
+            Token::Star => {
+                self.scanner.advance_skip_empty();
+                let start = self.scanner.token_start();
+                let expr = self.parse_primary_operand()?.into();
+                Ok(Expression::Deref(Deref {
+                    expr,
+                    span: start..self.scanner.position().pos,
+                }))
+            }
             Token::LeftSquare => {
                 self.scanner.advance_skip_empty();
+                let start = self.scanner.token_start();
                 let mut exprs = Vec::new();
                 loop {
                     self.skip_empty_lines();
@@ -494,16 +686,16 @@ impl<'a> Parser<'a> {
                         _ => self.parse_expr()?,
                     };
                     exprs.push(expr);
-                    if self.scanner.check_and_consume_ignore(Token::Comma) {
-                        if self.scanner.check_ignore(Token::RightSquare) {
-                            return Err(ParserErrorInfo::UnexpectedToken {
-                                found: Token::RightSquare,
-                            });
-                        }
+                    if self.scanner.check_and_consume_ignore(Token::Comma)
+                        && self.scanner.check_ignore(Token::RightSquare)
+                    {
+                        return Err(ParserErrorInfo::UnexpectedToken {
+                            found: Token::RightSquare,
+                        });
                     }
                 }
                 self.scanner.advance_ignore(); // skip right square bracket
-                Ok(Expression::Array(exprs))
+                Ok(Expression::Array(exprs, start..self.scanner.position().pos))
             }
             _ => Err(ParserErrorInfo::UnexpectedToken {
                 found: self.scanner.advance_skip_empty(),
@@ -512,22 +704,36 @@ impl<'a> Parser<'a> {
     }
 
     fn parse_range(&mut self) -> Result<Expression, ParserErrorInfo> {
+        let start = self.next_token_start();
         let from = match self.scanner.peek_skip_empty() {
             Token::Number => Some(self.parse_literal()?.into()),
-            Token::Ident => Some(Expression::Path(self.parse_path()?).into()),
+            Token::Ident => Some(self.parse_path_expr()?.into()),
             Token::Range => None,
             _ => return Err(ParserErrorInfo::InvalidOperandForRangeExpression),
         };
         self.expect_skip_empty(Token::Range)?;
         let to = match self.scanner.peek_skip_empty() {
             Token::Number => Some(self.parse_literal()?.into()),
-            Token::Ident => Some(Expression::Path(self.parse_path()?).into()),
+            Token::Ident => Some(self.parse_path_expr()?.into()),
             _ => None,
         };
-        Ok(Expression::Range(Range { from, to }))
+        Ok(Expression::Range(Range {
+            from,
+            to,
+            span: start..self.scanner.position().pos,
+        }))
     }
 
     fn parse_literal(&mut self) -> Result<Expression, ParserErrorInfo> {
+        let start = self.next_token_start();
+        let literal = self.parse_literal_value()?;
+        Ok(Expression::Literal(
+            literal,
+            start..self.scanner.position().pos,
+        ))
+    }
+
+    fn parse_literal_value(&mut self) -> Result<Literal, ParserErrorInfo> {
         match self.scanner.peek_skip_empty() {
             Token::Number => {
                 self.scanner.advance_skip_empty();
@@ -535,31 +741,30 @@ impl<'a> Parser<'a> {
                 if self.scanner.check_and_consume(Token::Dot) {
                     str.push('.');
                     self.expect(Token::Number)?;
-                    str.push_str(self.scanner.slice());
+                    let fraction = self.scanner.slice();
+                    if let Some(digits) = fraction.strip_suffix('r') {
+                        str.push_str(digits);
+                        return Self::parse_rational(str);
+                    }
+                    str.push_str(fraction);
                     let num = str
                         .parse::<f64>()
                         .map_err(|e| ParserErrorInfo::UnableToParse {
                             expr: str,
                             err: e.to_string(),
                         })?;
-                    Ok(Expression::Literal(Literal::Float(num)))
+                    Ok(Literal::Float(num))
                 } else {
-                    let num = str
-                        .parse::<i64>()
-                        .map_err(|e| ParserErrorInfo::UnableToParse {
-                            expr: str,
-                            err: e.to_string(),
-                        })?;
-                    Ok(Expression::Literal(Literal::Integer(num)))
+                    Self::parse_integer(str)
                 }
             }
             Token::True => {
                 self.scanner.advance_skip_empty();
-                Ok(Expression::Literal(Literal::Boolean(true)))
+                Ok(Literal::Boolean(true))
             }
             Token::False => {
                 self.scanner.advance_skip_empty();
-                Ok(Expression::Literal(Literal::Boolean(false)))
+                Ok(Literal::Boolean(false))
             }
             Token::SingleQuote => {
                 self.scanner.advance_skip_empty();
@@ -570,11 +775,11 @@ impl<'a> Parser<'a> {
                 }
                 let ch = ch.chars().next().unwrap();
                 self.expect(Token::SingleQuote)?;
-                Ok(Expression::Literal(Literal::Char(ch)))
+                Ok(Literal::Char(ch))
             }
             Token::Unit => {
                 self.scanner.advance_skip_empty();
-                Ok(Expression::Literal(Literal::Unit))
+                Ok(Literal::Unit)
             }
             Token::DoubleQuote => {
                 self.scanner.advance_skip_empty();
@@ -589,7 +794,7 @@ impl<'a> Parser<'a> {
                         break;
                     }
                 }
-                Ok(Expression::Literal(Literal::String(str.into())))
+                Ok(Literal::String(str.into()))
             }
             _ => {
                 let token = self.scanner.advance_skip_empty();
@@ -598,15 +803,67 @@ impl<'a> Parser<'a> {
         }
     }
 
+    /// Parses an integer literal with an optional type suffix, e.g. `255u8`.
+    fn parse_integer(str: String) -> Result<Literal, ParserErrorInfo> {
+        let suffix_start = str.find(|c: char| !c.is_ascii_digit()).unwrap_or(str.len());
+        let (digits, suffix) = str.split_at(suffix_start);
+        let kind = if suffix == "n" {
+            return Ok(Literal::BigInteger(digits.to_string()));
+        } else if suffix == "r" {
+            return Self::parse_rational(digits.to_string());
+        } else if suffix.is_empty() {
+            IntegerKind::I64
+        } else {
+            IntegerKind::from_name(suffix).ok_or(ParserErrorInfo::UnableToParse {
+                expr: str.clone(),
+                err: format!("unknown integer suffix '{suffix}'"),
+            })?
+        };
+
+        let num = digits
+            .parse::<i128>()
+            .ok()
+            .filter(|num| kind.contains(*num))
+            .ok_or(ParserErrorInfo::UnableToParse {
+                expr: str.clone(),
+                err: format!("integer literal out of range for {}", kind.name()),
+            })?;
+
+        match kind {
+            IntegerKind::I64 => Ok(Literal::Integer(num as i64)),
+            kind => Ok(Literal::SizedInteger(num, kind)),
+        }
+    }
+
+    /// Checks the digits of a rational literal like `12.50r`, without the suffix.
+    fn parse_rational(str: String) -> Result<Literal, ParserErrorInfo> {
+        let (whole, fraction) = str.split_once('.').unwrap_or((&str, "0"));
+        if whole.is_empty()
+            || fraction.is_empty()
+            || !whole
+                .chars()
+                .chain(fraction.chars())
+                .all(|c| c.is_ascii_digit())
+        {
+            return Err(ParserErrorInfo::UnableToParse {
+                expr: format!("{str}r"),
+                err: "invalid rational literal".to_string(),
+            });
+        }
+        Ok(Literal::Rational(str))
+    }
+
     fn parse_closure(&mut self) -> Result<Expression, ParserErrorInfo> {
         self.expect_indented(Token::Fn)?;
-        let mut args = Vec::new();
-        while self.scanner.check_and_consume_indented(Token::Ident) {
-            args.push(self.scanner.slice().to_string());
-        }
+        let start = self.scanner.token_start();
+        let args = self.parse_args()?;
         self.expect_indented(Token::ThinArrow)?;
         let block = self.parse_block()?.into();
-        Ok(Expression::Closure(Closure { args, block }))
+        Ok(Expression::Closure(Closure {
+            args,
+            block,
+            span: start..self.scanner.position().pos,
+        }))
     }
 
     fn parse_item_stmt(
@@ -617,6 +874,7 @@ impl<'a> Parser<'a> {
             return Err(ParserErrorInfo::StatementNotValidInThisPosition);
         }
 
+        let start = self.next_token_start();
         let item = match self.scanner.peek_skip_empty() {
             Token::Type => self.parse_type_stmt()?,
             Token::Module => self.parse_module_stmt()?,
@@ -627,6 +885,7 @@ impl<'a> Parser<'a> {
         Ok(ItemStmt {
             item,
             visibility: visibility.unwrap_or(Visibility::Private),
+            span: start..self.scanner.position().pos,
         })
     }
 
@@ -651,21 +910,25 @@ impl<'a> Parser<'a> {
                         Visibility::Private
                     };
 
+                    let mutable = self.scanner.check_and_consume_ignore(Token::Mut);
                     self.expect_ignore(Token::Ident)?;
                     let ident = self.scanner.slice().to_string();
+                    let span = self.token_span();
                     self.expect_ignore(Token::Colon)?;
                     let ty = self.parse_type()?;
                     fields.push(StructItemField {
                         ident,
                         visibility,
+                        mutable,
                         ty,
+                        span,
                     });
                     self.scanner.check_and_consume_ignore(Token::Comma);
                 }
 
                 Ok(Item::Struct(StructItem { ident, fields }))
             } else {
-                let path = Some(self.parse_path()?);
+                let path = Some(self.parse_spanned_path()?);
                 Ok(Item::Alias(AliasItem { ident, path }))
             }
         } else {
@@ -741,42 +1004,15 @@ impl<'a> Parser<'a> {
             })
         }
     }
-
-    fn error_message(&self, start_position: ScannerPosition) -> String {
-        let end_position = self.scanner.position();
-        let len = end_position.pos - start_position.pos;
-        let sentence = self.scanner.source()[start_position.pos..]
-            .chars()
-            .enumerate()
-            .take_while(|(i, c)| *i < len - 1 || *c != '\n')
-            .map(|(_, c)| c)
-            .collect::<String>();
-
-        let token_len = self.scanner.slice().len();
-
-        format!(
-            "{}\n{}{}",
-            sentence,
-            " ".repeat(end_position.col.checked_sub(token_len).unwrap_or(0)),
-            "^".repeat(token_len)
-        )
-    }
-
-    fn error_location(&self) -> String {
-        let position = self.scanner.position();
-        let token_len = self.scanner.slice().len();
-        format!(
-            "{}:{}:{}",
-            self.filename.as_ref().unwrap_or(&"<source>".to_string()),
-            position.row + 1,
-            position.col + 1 - token_len
-        )
-    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::Parser;
+    use crate::{
+        op::{BooleanOperator, ComparisonOperator},
+        stmt::{Expression, Field, Index, Item, ItemStmt, Literal, Operator, Stmt},
+        IntegerKind, Parser,
+    };
 
     #[test]
     fn parse_operation() {
@@ -786,12 +1022,157 @@ mod tests {
         println!("{:?}", parser.parse_expr());
     }
 
+    #[test]
+    fn parse_operation_precedence() {
+        let mut parser = Parser::new_with_top_level("a < b + 1 && c || d", None);
+        let Ok(Expression::Operation(or)) = parser.parse_expr() else {
+            panic!("Expected an operation.");
+        };
+        assert_eq!(or.op, Operator::Boolean(BooleanOperator::Or));
+        let Expression::Operation(and) = *or.lhs else {
+            panic!("Expected an operation.");
+        };
+        assert_eq!(and.op, Operator::Boolean(BooleanOperator::And));
+        let Expression::Operation(less) = *and.lhs else {
+            panic!("Expected an operation.");
+        };
+        assert_eq!(less.op, Operator::Comparison(ComparisonOperator::Less));
+    }
+
     #[test]
     fn parse_array() {
         let mut parser = Parser::new_with_top_level("[1 + 3, 2, 3, 4, 5]", None);
         println!("{:?}", parser.parse_expr());
     }
 
+    #[test]
+    fn parse_index_assignment() {
+        let mut parser = Parser::new_with_top_level("grid[r][c + 1] = xs[1..3]", None);
+        println!("{:?}", parser.parse_expr());
+    }
+
+    #[test]
+    fn parse_index_fields() {
+        let mut parser = Parser::new_with_top_level("ps[0].xs[1]", None);
+        let Expression::Index(Index { value, span, .. }) = parser.parse_expr().unwrap() else {
+            panic!("Expected an index.");
+        };
+        assert_eq!(span, 0..11);
+        let Expression::Field(Field { value, ident, span }) = *value else {
+            panic!("Expected a field.");
+        };
+        assert_eq!((ident.as_str(), span), ("xs", 0..8));
+        let Expression::Index(Index { span, .. }) = *value else {
+            panic!("Expected an index.");
+        };
+        assert_eq!(span, 0..5);
+    }
+
+    #[test]
+    fn parse_compound_assignment() {
+        let mut parser = Parser::new_with_top_level("point.x += 1 * 2", None);
+        println!("{:?}", parser.parse_expr());
+    }
+
+    #[test]
+    fn parse_cast() {
+        let mut parser = Parser::new_with_top_level("a + b as float * 2.0", None);
+        println!("{:?}", parser.parse_expr());
+    }
+
+    #[test]
+    fn parse_integer_suffix() {
+        let mut parser = Parser::new_with_top_level("255u8", None);
+        assert_eq!(
+            parser.parse_expr().unwrap(),
+            Expression::Literal(Literal::SizedInteger(255, IntegerKind::U8), 0..5)
+        );
+        let mut parser = Parser::new_with_top_level("256u8", None);
+        assert!(parser.parse_expr().is_err());
+        let mut parser = Parser::new_with_top_level("7i64", None);
+        assert_eq!(
+            parser.parse_expr().unwrap(),
+            Expression::Literal(Literal::Integer(7), 0..4)
+        );
+    }
+
+    #[test]
+    fn parse_big_number_literals() {
+        let mut parser = Parser::new_with_top_level("123456789012345678901234567890n", None);
+        assert_eq!(
+            parser.parse_expr().unwrap(),
+            Expression::Literal(
+                Literal::BigInteger("123456789012345678901234567890".to_string()),
+                0..31
+            )
+        );
+        let mut parser = Parser::new_with_top_level("12.50r", None);
+        assert_eq!(
+            parser.parse_expr().unwrap(),
+            Expression::Literal(Literal::Rational("12.50".to_string()), 0..6)
+        );
+        let mut parser = Parser::new_with_top_level("3r", None);
+        assert_eq!(
+            parser.parse_expr().unwrap(),
+            Expression::Literal(Literal::Rational("3".to_string()), 0..2)
+        );
+    }
+
+    #[test]
+    fn parse_spans() {
+        let mut parser = Parser::new_with_top_level("a + b * c", None);
+        let expr = parser.parse_expr().unwrap();
+        assert_eq!(expr.span(), &(0..9));
+        let Expression::Operation(operation) = expr else {
+            panic!("Expected an operation.");
+        };
+        assert_eq!(operation.rhs.span(), &(4..9));
+
+        let mut parser = Parser::new("let x = if [1, 2] == xs then 1 else 2", None);
+        let Ok(Stmt::Let(l)) = parser.parse() else {
+            panic!("Expected a let.");
+        };
+        let Some(Expression::Block(stmts, _)) = l.expr.map(|expr| *expr) else {
+            panic!("Expected a block.");
+        };
+        let Some(Stmt::Expr(Expression::IfElse(if_else))) = stmts.into_iter().next() else {
+            panic!("Expected an if else.");
+        };
+        assert_eq!(if_else.span, 8..37);
+        let Expression::Operation(operation) = *if_else.condition else {
+            panic!("Expected an operation.");
+        };
+        assert!(matches!(*operation.lhs, Expression::Array(..)));
+        assert_eq!(operation.lhs.span(), &(11..17));
+        assert_eq!(if_else.if_expr.span(), &(29..30));
+        assert_eq!(
+            if_else.else_expr.as_ref().map(|expr| expr.span()),
+            Some(&(36..37))
+        );
+
+        let mut parser = Parser::new("let f = fn a ->\n    let b = a\n    b..2", None);
+        let Ok(Stmt::Let(l)) = parser.parse() else {
+            panic!("Expected a let.");
+        };
+        let Some(Expression::Block(stmts, span)) = l.expr.as_deref() else {
+            panic!("Expected a block.");
+        };
+        assert_eq!(span, &(8..38));
+        let [Stmt::Expr(Expression::Closure(closure))] = &stmts[..] else {
+            panic!("Expected a closure.");
+        };
+        assert_eq!(closure.span, 8..38);
+        assert_eq!(closure.block.span(), &(20..38));
+        let Expression::Block(stmts, _) = &*closure.block else {
+            panic!("Expected a block.");
+        };
+        let Stmt::Expr(range) = &stmts[1] else {
+            panic!("Expected an expression.");
+        };
+        assert!(matches!(range, Expression::Range(_)));
+        assert_eq!(range.span(), &(34..38));
+    }
+
     #[test]
     fn parse_if() {
         let mut parser =
@@ -869,4 +1250,76 @@ mod tests {
         );
         println!("{:?}", parser.parse());
     }
+
+    #[test]
+    fn parse_let_rec() {
+        let mut parser = Parser::new(
+            "let rec even n = if n == 0 then true else odd (n - 1)\nand odd n =\n    if n == 0 then false else even (n - 1)\n1",
+            None,
+        );
+        let Ok(Stmt::LetRec(bindings)) = parser.parse() else {
+            panic!("Expected a let rec.");
+        };
+        let idents = bindings
+            .iter()
+            .map(|l| l.ident.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(idents, ["even", "odd"]);
+        assert!(matches!(parser.parse(), Ok(Stmt::Expr(_))));
+    }
+
+    #[test]
+    fn parse_mut() {
+        let mut parser = Parser::new(
+            "let mut a = 1\nlet f mut n m = n\ntype Point = {mut x: int, pub y: int}",
+            None,
+        );
+        let Ok(Stmt::Let(l)) = parser.parse() else {
+            panic!("Expected a let.");
+        };
+        assert!(l.mutable);
+        assert_eq!(l.span, 8..9);
+        let Ok(Stmt::Let(l)) = parser.parse() else {
+            panic!("Expected a let.");
+        };
+        assert!(!l.mutable);
+        let args = l
+            .args
+            .iter()
+            .map(|arg| (arg.ident.as_str(), arg.mutable))
+            .collect::<Vec<_>>();
+        assert_eq!(args, [("n", true), ("m", false)]);
+        let Ok(Stmt::Item(ItemStmt {
+            item: Item::Struct(struc),
+            ..
+        })) = parser.parse()
+        else {
+            panic!("Expected a struct.");
+        };
+        let fields = struc
+            .fields
+            .iter()
+            .map(|field| (field.ident.as_str(), field.mutable))
+            .collect::<Vec<_>>();
+        assert_eq!(fields, [("x", true), ("y", false)]);
+    }
+
+    #[test]
+    fn parse_references() {
+        let mut parser = Parser::new("let r: &mut int = &mut a\n*r = *b + 1", None);
+        let Ok(Stmt::Let(l)) = parser.parse() else {
+            panic!("Expected a let.");
+        };
+        assert_eq!(l.ty.map(|ty| ty.to_string()).as_deref(), Some("&mut int"));
+        assert!(matches!(
+            l.expr.as_deref(),
+            Some(Expression::Block(stmts, _))
+                if matches!(&stmts[..], [Stmt::Expr(Expression::Reference(r))] if r.mutable)
+        ));
+        let Ok(Stmt::Expr(Expression::Operation(operation))) = parser.parse() else {
+            panic!("Expected an assignment.");
+        };
+        assert!(matches!(*operation.lhs, Expression::Deref(_)));
+        assert_eq!(operation.lhs.span(), &(25..27));
+    }
 }
@@ -2,13 +2,16 @@ use std::fmt::Debug;
 
 use crate::{
     op::{ArithmeticOperator, BooleanOperator, ComparisonOperator, CompoundAssignmentOperator},
-    Type,
+    IntegerKind, Type,
 };
 
+/// A range of byte offsets in the source an expression was parsed from.
+pub type Span = std::ops::Range<usize>;
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct StmtDetails {
     pub file: Option<String>,
-    pub defined_at: std::ops::Range<usize>,
+    pub defined_at: Span,
     pub stmt: Stmt,
 }
 
@@ -23,6 +26,9 @@ pub enum Visibility {
 pub enum Stmt {
     Item(ItemStmt),
     Let(LetStmt),
+    /// The bindings of a `let rec`, joined with `and`. Each of them is in
+    /// scope in all of their bodies.
+    LetRec(Vec<LetStmt>),
     Expr(Expression),
 }
 
@@ -30,6 +36,8 @@ pub enum Stmt {
 pub struct ItemStmt {
     pub item: Item,
     pub visibility: Visibility,
+    /// Span of the item, without its visibility.
+    pub span: Span,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -43,7 +51,7 @@ pub enum Item {
 #[derive(Debug, Clone, PartialEq)]
 pub struct AliasItem {
     pub ident: String,
-    pub path: Option<String>,
+    pub path: Option<Path>,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -56,51 +64,100 @@ pub struct StructItem {
 pub struct StructItemField {
     pub ident: String,
     pub visibility: Visibility,
+    /// Whether the field is declared with `mut` and can be assigned to.
+    pub mutable: bool,
     pub ty: Type,
+    /// Span of the name of the field.
+    pub span: Span,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct LetStmt {
     pub ident: String,
     pub visibility: Option<Visibility>,
-    pub args: Vec<String>,
+    /// Whether the name is declared with `mut` and can be assigned to.
+    pub mutable: bool,
+    pub args: Vec<Arg>,
     pub expr: Option<Box<Expression>>,
     pub ty: Option<Type>,
+    /// Span of the name.
+    pub span: Span,
+}
+
+/// An argument of a function. The `unit` argument of `let f () = ..` has
+/// an empty name.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Arg {
+    pub ident: String,
+    /// Whether the argument is declared with `mut` and can be assigned to.
+    pub mutable: bool,
+    /// Span of the name.
+    pub span: Span,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Expression {
-    Literal(Literal),
-    Path(String),
+    Literal(Literal, Span),
+    Path(Path),
     Operation(Operation),
     Call(Call),
     Struct(Struct),
     Range(Range),
-    Array(Vec<Expression>),
+    Array(Vec<Expression>, Span),
     Index(Index),
+    Field(Field),
     IfElse(IfElse),
     Match(Match),
     For(For),
-    Block(Vec<Stmt>),
+    Block(Vec<Stmt>, Span),
     Closure(Closure),
+    Cast(Cast),
+    Reference(Reference),
+    Deref(Deref),
 }
 
 impl Expression {
+    pub fn span(&self) -> &Span {
+        match self {
+            Expression::Literal(_, span)
+            | Expression::Array(_, span)
+            | Expression::Block(_, span)
+            | Expression::Path(Path { span, .. })
+            | Expression::Operation(Operation { span, .. })
+            | Expression::Call(Call { span, .. })
+            | Expression::Struct(Struct { span, .. })
+            | Expression::Range(Range { span, .. })
+            | Expression::Index(Index { span, .. })
+            | Expression::Field(Field { span, .. })
+            | Expression::IfElse(IfElse { span, .. })
+            | Expression::Match(Match { span, .. })
+            | Expression::For(For { span, .. })
+            | Expression::Closure(Closure { span, .. })
+            | Expression::Cast(Cast { span, .. })
+            | Expression::Reference(Reference { span, .. })
+            | Expression::Deref(Deref { span, .. }) => span,
+        }
+    }
+
     pub fn name(&self) -> &str {
         match self {
-            Expression::Literal(_) => "<Literal>",
+            Expression::Literal(..) => "<Literal>",
             Expression::Path(_) => "<Path>",
             Expression::Operation(_) => "<Operation>",
             Expression::Call(_) => "<Call>",
             Expression::Struct(_) => "<Struct>",
             Expression::Range(_) => "<Range>",
-            Expression::Array(_) => "<Array>",
+            Expression::Array(..) => "<Array>",
             Expression::Index(_) => "<Index>",
+            Expression::Field(_) => "<Field>",
             Expression::IfElse(_) => "<IfElse>",
             Expression::Match(_) => "<Match>",
             Expression::For(_) => "<For>",
-            Expression::Block(_) => "<Block>",
+            Expression::Block(..) => "<Block>",
             Expression::Closure(_) => "<Closure>",
+            Expression::Cast(_) => "<Cast>",
+            Expression::Reference(_) => "<Reference>",
+            Expression::Deref(_) => "<Deref>",
         }
     }
 }
@@ -111,15 +168,28 @@ pub enum Literal {
     Boolean(bool),
     Char(char),
     Integer(i64),
+    SizedInteger(i128, IntegerKind),
+    /// The digits of a `bigint` literal like `123n`.
+    BigInteger(String),
+    /// The decimal digits of a `rational` literal like `0.1r`.
+    Rational(String),
     Float(f64),
     String(Box<String>),
 }
 
+/// A name, possibly qualified with fields or modules like `point.x`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Path {
+    pub path: String,
+    pub span: Span,
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct Operation {
     pub lhs: Box<Expression>,
     pub rhs: Box<Expression>,
     pub op: Operator,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -134,10 +204,10 @@ pub enum Operator {
 impl Operator {
     pub fn precedence(&self) -> i32 {
         match self {
-            Operator::Assignment => 10,
+            Operator::Assignment | Operator::CompoundAssignment(_) => 10,
+            Operator::Boolean(BooleanOperator::Or) => 14,
+            Operator::Boolean(BooleanOperator::And) => 16,
             Operator::Comparison(_) => 20,
-            Operator::Boolean(_) => 20,
-            Operator::CompoundAssignment(_) => 20,
             Operator::Arithmetic(ArithmeticOperator::Add | ArithmeticOperator::Sub) => 30,
             Operator::Arithmetic(
                 ArithmeticOperator::Mul | ArithmeticOperator::Div | ArithmeticOperator::Mod,
@@ -150,24 +220,29 @@ impl Operator {
 pub struct Call {
     pub path: String,
     pub params: Vec<Expression>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Struct {
     pub path: String,
     pub fields: Vec<StructField>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct StructField {
     pub ident: String,
     pub expr: Box<Expression>,
+    /// Span of the name of the field.
+    pub span: Span,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Range {
     pub from: Option<Box<Expression>>,
     pub to: Option<Box<Expression>>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -175,12 +250,14 @@ pub struct IfElse {
     pub condition: Box<Expression>,
     pub if_expr: Box<Expression>,
     pub else_expr: Option<Box<Expression>>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Match {
     pub match_expr: Box<Expression>,
     pub branches: Vec<MatchBranch>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -195,16 +272,51 @@ pub struct For {
     pub ident: String,
     pub in_expr: Box<Expression>,
     pub block: Box<Expression>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Closure {
-    pub args: Vec<String>,
+    pub args: Vec<Arg>,
     pub block: Box<Expression>,
+    pub span: Span,
 }
 
 #[derive(Debug, Clone, PartialEq)]
 pub struct Index {
     pub value: Box<Expression>,
     pub index: Box<Expression>,
+    pub span: Span,
+}
+
+/// A field of a value that is not a path, like `ps[0].x`. The fields of a
+/// path like `p.x` are part of the path.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field {
+    pub value: Box<Expression>,
+    pub ident: String,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cast {
+    pub expr: Box<Expression>,
+    pub ty: Type,
+    pub span: Span,
+}
+
+/// A reference to a value like `&a`, or `&mut a` to be able to assign
+/// through it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Reference {
+    pub expr: Box<Expression>,
+    pub mutable: bool,
+    pub span: Span,
+}
+
+/// The value a reference refers to, like `*r`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Deref {
+    pub expr: Box<Expression>,
+    pub span: Span,
 }
@@ -1,9 +1,19 @@
+use std::fmt::Display;
+
+use crate::stmt::Span;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Type {
     Unit,
-    Name(String),
+    /// A type named like `int`, with the span of its name.
+    Name(String, Span),
     Array(Box<Type>),
     Function(FunctionType),
+    /// A reference like `&int`, or `&mut int` to be able to assign through it.
+    Reference {
+        ty: Box<Type>,
+        mutable: bool,
+    },
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -11,3 +21,105 @@ pub struct FunctionType {
     pub args: Vec<Type>,
     pub ret: Box<Type>,
 }
+
+impl Display for Type {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Type::Unit => f.write_str("()"),
+            Type::Name(name, _) => f.write_str(name),
+            Type::Array(ty) => write!(f, "[{ty}]"),
+            Type::Reference { ty, mutable: true } => write!(f, "&mut {ty}"),
+            Type::Reference { ty, mutable: false } => write!(f, "&{ty}"),
+            Type::Function(FunctionType { args, ret }) => {
+                f.write_str("(")?;
+                for arg in args {
+                    write!(f, "{arg} -> ")?;
+                }
+                write!(f, "{ret})")
+            }
+        }
+    }
+}
+
+/// The integer types with an explicit size. `int` is a 64 bit signed integer,
+/// so `i64` refers to the same type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum IntegerKind {
+    I8,
+    I16,
+    I32,
+    I64,
+    U8,
+    U16,
+    U32,
+    U64,
+}
+
+impl IntegerKind {
+    pub const ALL: [IntegerKind; 8] = [
+        IntegerKind::I8,
+        IntegerKind::I16,
+        IntegerKind::I32,
+        IntegerKind::I64,
+        IntegerKind::U8,
+        IntegerKind::U16,
+        IntegerKind::U32,
+        IntegerKind::U64,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            IntegerKind::I8 => "i8",
+            IntegerKind::I16 => "i16",
+            IntegerKind::I32 => "i32",
+            IntegerKind::I64 => "i64",
+            IntegerKind::U8 => "u8",
+            IntegerKind::U16 => "u16",
+            IntegerKind::U32 => "u32",
+            IntegerKind::U64 => "u64",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<IntegerKind> {
+        IntegerKind::ALL
+            .into_iter()
+            .find(|kind| kind.name() == name)
+    }
+
+    pub fn bits(&self) -> u32 {
+        match self {
+            IntegerKind::I8 | IntegerKind::U8 => 8,
+            IntegerKind::I16 | IntegerKind::U16 => 16,
+            IntegerKind::I32 | IntegerKind::U32 => 32,
+            IntegerKind::I64 | IntegerKind::U64 => 64,
+        }
+    }
+
+    pub fn is_signed(&self) -> bool {
+        matches!(
+            self,
+            IntegerKind::I8 | IntegerKind::I16 | IntegerKind::I32 | IntegerKind::I64
+        )
+    }
+
+    pub fn min(&self) -> i128 {
+        if self.is_signed() {
+            -(1 << (self.bits() - 1))
+        } else {
+            0
+        }
+    }
+
+    pub fn max(&self) -> i128 {
+        if self.is_signed() {
+            (1 << (self.bits() - 1)) - 1
+        } else {
+            (1 << self.bits()) - 1
+        }
+    }
+
+    #[inline]
+    pub fn contains(&self, value: i128) -> bool {
+        (self.min()..=self.max()).contains(&value)
+    }
+}
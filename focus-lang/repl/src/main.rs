@@ -1,12 +1,22 @@
-use std::io::{Error, Write};
+use std::{
+    env::args,
+    io::{Error, Write},
+};
 
 use console::{style, Style, Term};
+use diagnostic::Renderer;
 use interpreter::Interpreter;
 use parser::{Parser, ParserError, ParserErrorInfo};
 use scanner::{Scanner, Token};
 
 fn main() -> Result<(), Error> {
+    let args = args().collect::<Vec<_>>();
+    if args.get(1).is_some_and(|arg| arg == "explain") {
+        return explain(args.get(2).map(String::as_str));
+    }
+
     let mut term = Term::stdout();
+    let renderer = Renderer::colored();
     let mut interpreter = Interpreter::new();
 
     term.set_title("REPL");
@@ -16,7 +26,7 @@ fn main() -> Result<(), Error> {
         style("focus-lang REPL").yellow(),
         style(env!("CARGO_PKG_VERSION")).yellow()
     ))?;
-    term.write(b"> ")?;
+    term.write_all(b"> ")?;
 
     let mut history: Vec<String> = Vec::new();
     let mut history_index: usize = 0;
@@ -26,18 +36,16 @@ fn main() -> Result<(), Error> {
     let mut cursor_x: usize = 0;
     loop {
         match term.read_key()? {
-            console::Key::ArrowLeft => cursor_x = cursor_x.checked_sub(1).unwrap_or(0),
+            console::Key::ArrowLeft => cursor_x = cursor_x.saturating_sub(1),
             console::Key::ArrowRight => cursor_x = (cursor_x + 1).min(line.len()),
-            console::Key::ArrowUp => {
-                if history.len() > 0 {
-                    history_index = history_index.checked_sub(1).unwrap_or(0);
-                    term.clear_line()?;
-                    line = history[history_index].clone();
-                    cursor_x = line.len();
-                }
+            console::Key::ArrowUp if !history.is_empty() => {
+                history_index = history_index.saturating_sub(1);
+                term.clear_line()?;
+                line = history[history_index].clone();
+                cursor_x = line.len();
             }
             console::Key::ArrowDown => {
-                if history.len() > 0 && history_index < history.len() - 1 {
+                if !history.is_empty() && history_index < history.len() - 1 {
                     history_index += 1;
                     term.clear_line()?;
                     line = history[history_index].clone();
@@ -50,7 +58,7 @@ fn main() -> Result<(), Error> {
             console::Key::Enter => {
                 cursor_x = 0;
                 term.clear_line()?;
-                term.write(b"  ")?;
+                term.write_all(b"  ")?;
                 write_colored_line(&mut term, &line)?;
                 term.write_line("")?;
                 let parse = if line.trim_end().ends_with(';') {
@@ -70,7 +78,9 @@ fn main() -> Result<(), Error> {
                                 let result = interpreter.interpret_stmt(exp.stmt);
                                 match result {
                                     Ok(value) => term.write_fmt(format_args!("{value:?}"))?,
-                                    Err(err) => term.write_fmt(format_args!("Error: {err}"))?,
+                                    Err(err) => term.write_all(
+                                        renderer.render(&err.diagnostic(), &expr, None).as_bytes(),
+                                    )?,
                                 };
                                 let mut line = expr[exp.defined_at].to_string();
                                 if line.starts_with('\n') {
@@ -90,7 +100,7 @@ fn main() -> Result<(), Error> {
                                 ..
                             }) => break,
                             Err(err) => {
-                                term.write_line(&err.to_string())?;
+                                term.write_line(&renderer.render(&err.diagnostic(), &expr, None))?;
                                 term.write_line("")?;
                                 break;
                             }
@@ -100,16 +110,12 @@ fn main() -> Result<(), Error> {
                 }
                 line.clear();
             }
-            console::Key::Backspace => {
-                if !line.is_empty() {
-                    line.drain(cursor_x.checked_sub(1).unwrap_or_default()..cursor_x);
-                    cursor_x = cursor_x.checked_sub(1).unwrap_or(0);
-                }
+            console::Key::Backspace if !line.is_empty() => {
+                line.drain(cursor_x.saturating_sub(1)..cursor_x);
+                cursor_x = cursor_x.saturating_sub(1);
             }
-            console::Key::Del => {
-                if !line.is_empty() {
-                    line.drain(cursor_x..(cursor_x + 1).min(line.len()));
-                }
+            console::Key::Del if !line.is_empty() => {
+                line.drain(cursor_x..(cursor_x + 1).min(line.len()));
             }
             console::Key::Home => {
                 cursor_x = 0;
@@ -125,16 +131,25 @@ fn main() -> Result<(), Error> {
         }
 
         term.clear_line()?;
-        term.write("> ".as_bytes())?;
+        term.write_all("> ".as_bytes())?;
         let term_width = term.size().1 as _;
-        write_colored_line(
-            &mut term,
-            &line[cursor_x.checked_sub(term_width - 2).unwrap_or_default()..],
-        )?;
+        write_colored_line(&mut term, &line[cursor_x.saturating_sub(term_width - 2)..])?;
         term.move_cursor_to((cursor_x + 2).min(term_width), term.size().0 as usize)?;
     }
 }
 
+/// Prints the explanation of an error code, e.g. `focus explain E0302`.
+fn explain(code: Option<&str>) -> Result<(), Error> {
+    let term = Term::stdout();
+    match code {
+        Some(code) => match interpreter::explain(&code.to_uppercase()) {
+            Some(explanation) => term.write_line(explanation),
+            None => term.write_line(&format!("Unknown error code {code}.")),
+        },
+        None => term.write_line("Usage: focus explain <code>"),
+    }
+}
+
 fn write_colored_line(term: &mut Term, line: &str) -> Result<(), Error> {
     let mut scanner = Scanner::new(line);
     let mut final_string = String::with_capacity(132);
@@ -164,6 +179,6 @@ fn write_colored_line(term: &mut Term, line: &str) -> Result<(), Error> {
         };
         final_string.push_str(&format!("{}", style.apply_to(scanner.slice())))
     }
-    term.write(final_string.as_bytes())?;
+    term.write_all(final_string.as_bytes())?;
     Ok(())
 }
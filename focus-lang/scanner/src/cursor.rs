@@ -65,7 +65,7 @@ impl<'a> Cursor<'a> {
                 break;
             } else if (c.is_alphanumeric() == is_alpha
                 && !c.is_whitespace()
-                && (is_alpha || (!is_alpha && chars_count < min_chars)))
+                && (is_alpha || chars_count < min_chars))
                 || (is_alpha && c == '_')
             {
                 end_position += c.len_utf8();
@@ -149,6 +149,11 @@ impl<'a> Scanner<'a> {
         }
     }
 
+    /// Byte offset in the source where the last token starts.
+    pub fn token_start(&self) -> usize {
+        self.cursor.start_position()
+    }
+
     pub fn slice(&self) -> &str {
         &self.cursor.source()[self.cursor.start_position()..self.cursor.position()]
     }
@@ -217,4 +222,19 @@ mod tests {
         assert_eq!(scanner.advance_skip_empty(), Token::Equal);
         assert_eq!(scanner.advance_skip_empty(), Token::False);
     }
+
+    #[test]
+    fn compound_assignment() {
+        let mut scanner = Scanner::new("a += 1 -= 2 *= 3 /= 4 %= 5");
+        assert_eq!(scanner.advance_skip_empty(), Token::Ident);
+        assert_eq!(scanner.advance_skip_empty(), Token::PlusAssign);
+        assert_eq!(scanner.advance_skip_empty(), Token::Number);
+        assert_eq!(scanner.advance_skip_empty(), Token::DashAssign);
+        assert_eq!(scanner.advance_skip_empty(), Token::Number);
+        assert_eq!(scanner.advance_skip_empty(), Token::StarAssign);
+        assert_eq!(scanner.advance_skip_empty(), Token::Number);
+        assert_eq!(scanner.advance_skip_empty(), Token::SlashAssign);
+        assert_eq!(scanner.advance_skip_empty(), Token::Number);
+        assert_eq!(scanner.advance_skip_empty(), Token::PercentAssign);
+    }
 }
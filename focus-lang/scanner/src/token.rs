@@ -32,18 +32,27 @@ pub enum Token {
     Percent,     // %
     Unknown,
 
-    ThinArrow,    // ->
-    Equal,        // ==
-    GreaterEqual, // >=
-    LessEqual,    // <=
-    NotEqual,     // !=
-    Or,           // ||
-    And,          // &&
-    Unit,         // ()
-    Range,        // ..
+    ThinArrow,     // ->
+    Equal,         // ==
+    GreaterEqual,  // >=
+    LessEqual,     // <=
+    NotEqual,      // !=
+    Or,            // ||
+    And,           // &&
+    Unit,          // ()
+    Range,         // ..
+    PlusAssign,    // +=
+    DashAssign,    // -=
+    StarAssign,    // *=
+    SlashAssign,   // /=
+    PercentAssign, // %=
 
     // Keywords
     Let,
+    Rec,
+    /// `and`, joining the bindings of a `let rec`.
+    AndLet,
+    Mut,
     Match,
     If,
     Then,
@@ -59,6 +68,7 @@ pub enum Token {
     In,
     Do,
     Fn,
+    As,
 }
 
 impl From<&str> for Token {
@@ -100,8 +110,16 @@ impl From<&str> for Token {
             "!=" => Token::NotEqual,
             "()" => Token::Unit,
             ".." => Token::Range,
+            "+=" => Token::PlusAssign,
+            "-=" => Token::DashAssign,
+            "*=" => Token::StarAssign,
+            "/=" => Token::SlashAssign,
+            "%=" => Token::PercentAssign,
 
             "let" => Token::Let,
+            "rec" => Token::Rec,
+            "and" => Token::AndLet,
+            "mut" => Token::Mut,
             "match" => Token::Match,
             "if" => Token::If,
             "then" => Token::Then,
@@ -116,6 +134,7 @@ impl From<&str> for Token {
             "in" => Token::In,
             "do" => Token::Do,
             "fn" => Token::Fn,
+            "as" => Token::As,
             c if c.chars().next().is_some_and(|c| c.is_whitespace()) => Token::Empty,
             c if c.chars().next().is_some_and(|c| c.is_numeric()) => Token::Number,
             c if c
@@ -145,6 +164,11 @@ impl Token {
                 | Token::Slash
                 | Token::Percent
                 | Token::Assign
+                | Token::PlusAssign
+                | Token::DashAssign
+                | Token::StarAssign
+                | Token::SlashAssign
+                | Token::PercentAssign
                 | Token::Equal
                 | Token::NotEqual
                 | Token::Greater
@@ -184,6 +208,9 @@ impl Token {
         matches!(
             self,
             Token::Let
+                | Token::Rec
+                | Token::AndLet
+                | Token::Mut
                 | Token::Match
                 | Token::If
                 | Token::Then
@@ -198,6 +225,7 @@ impl Token {
                 | Token::False
                 | Token::In
                 | Token::Fn
+                | Token::As
         )
     }
 }